@@ -1,52 +1,364 @@
-use glam::Vec2;
+use glam::{IVec2, UVec2, Vec2};
+use lowres::audio::Audio;
+use lowres::camera::PixelCamera;
+use lowres::ecs::World;
+use lowres::egui_raylib::InputFilter;
+use lowres::font::{Align, BitmapFont};
+use lowres::layers::Layers;
+use lowres::save::SaveSlots;
+use lowres::sfxr::{SfxrEditor, SfxrParams};
+use lowres::tracker::{TrackerSong, TrackerSync};
+use lowres::tween::{Animation, Ease, Repeat, Tween};
 use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub const FRAMES_PER_SECOND: u32 = 60;
+/// Size of the world layer, and so of the camera's view.
+pub const WORLD_DIMS: UVec2 = UVec2::new(240, 160);
+
+// render layers, composited bottom to top in the order main adds them
+pub const WORLD: &str = "world";
+pub const EFFECTS: &str = "effects";
+pub const HUD: &str = "hud";
+
+/// Bump when `SaveData` changes shape, and add a migration for the old version.
+pub const SAVE_VERSION: u32 = 1;
+pub const QUICK_SAVE: &str = "quicksave";
+
+/// Drop an .xm or .mod here to have the camera bump along to it.
+pub const MUSIC_PATH: &str = "assets/music.xm";
+
+lowres::inspect_struct!(State {
+    spark_count: 0..=200,
+    spark_color,
+    ring_color,
+});
 
 pub struct State {
     pub running: bool,
-    pub time_since_last_update: f32,
+    pub world: World,
+    /// Drifts after the first orbiting square and shakes when sparks burst.
+    pub camera: PixelCamera,
+    /// Where to spawn sparks on the next tick.
+    pub burst: Option<Vec2>,
+    /// Sparks per click.
+    pub spark_count: u32,
+    pub spark_color: Color,
+    pub ring_color: Color,
+    pub spark_sound: SfxrEditor,
+    /// The spark sound needs regenerating, and playing if `preview_spark` is set.
+    pub spark_changed: bool,
+    pub preview_spark: bool,
+    /// Radius of the ring around the cursor.
+    pub ring_pulse: Tween<f32>,
+    pub saves: SaveSlots<SaveData>,
+    /// Set by main once raylib is up; swap in `BitmapFont::load_bmfont` for your own.
+    pub font: Option<BitmapFont>,
+    /// Follows the music, if `start_music` found any.
+    pub beat: Option<TrackerSync>,
+}
+
+/// The parts of `State` worth keeping between runs.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    /// One per orbiting square, in query order.
+    pub orbit_angles: Vec<f32>,
+    pub spark_sound: SfxrParams,
 }
 
 impl State {
     pub fn new() -> Self {
+        let mut world = World::new();
+        for i in 0..3 {
+            world.spawn((
+                Orbit {
+                    center: Vec2::new(120.0, 80.0),
+                    radius: 30.0,
+                    angle: i as f32 * std::f32::consts::TAU / 3.0,
+                    speed: 0.03,
+                },
+                Position(Vec2::ZERO),
+                Square {
+                    size: 8.0,
+                    color: Color::RED,
+                },
+            ));
+        }
+        let mut camera = PixelCamera::new(WORLD_DIMS);
+        camera.dead_zone = Vec2::new(32.0, 24.0);
+        camera.follow_speed = 0.1;
+        camera.bounds = Some((Vec2::new(-40.0, -30.0), Vec2::new(280.0, 190.0)));
+        camera.snap_to(Vec2::new(120.0, 80.0));
         Self {
             running: true,
-            time_since_last_update: 0.0,
+            world,
+            camera,
+            burst: None,
+            spark_count: 24,
+            spark_color: Color::ORANGE,
+            ring_color: Color::new(0, 228, 48, 160),
+            spark_sound: SfxrEditor::new(SfxrParams::coin(3)),
+            spark_changed: true,
+            preview_spark: false,
+            ring_pulse: Tween::new(12.0, 16.0, 47, Ease::InOutSine)
+                .yoyo()
+                .repeat(Repeat::Forever),
+            saves: SaveSlots::new("lowres-2d-template", SAVE_VERSION),
+            font: None,
+            beat: None,
         }
     }
+
+    pub fn save_data(&self) -> SaveData {
+        SaveData {
+            orbit_angles: self
+                .world
+                .query::<Orbit>()
+                .map(|(_, orbit)| orbit.angle)
+                .collect(),
+            spark_sound: self.spark_sound.params.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, data: SaveData) {
+        let orbits = self.world.query_mut::<Orbit>();
+        for ((_, orbit), angle) in orbits.zip(data.orbit_angles) {
+            orbit.angle = angle;
+        }
+        self.spark_sound.params = data.spark_sound;
+        self.spark_changed = true;
+    }
+}
+
+/* ----------- components ---------------------------------------------- */
+
+pub struct Position(pub Vec2);
+
+/// Velocity in canvas pixels per tick.
+pub struct Velocity(pub Vec2);
+
+/// Ticks left before the entity despawns.
+pub struct Lifetime(pub u32);
+
+pub struct Orbit {
+    pub center: Vec2,
+    pub radius: f32,
+    pub angle: f32,
+    /// Radians per tick.
+    pub speed: f32,
 }
 
-pub fn process_events_and_input(rl: &mut RaylibHandle, state: &mut State) {
-    if rl.is_key_pressed(raylib::consts::KeyboardKey::KEY_ESCAPE) {
+pub struct Square {
+    pub size: f32,
+    pub color: Color,
+}
+
+pub fn process_events_and_input(rl: &mut RaylibHandle, state: &mut State, input: &InputFilter) {
+    if input.is_key_pressed(rl, raylib::consts::KeyboardKey::KEY_ESCAPE) {
         state.running = false;
     }
+    if input.is_mouse_button_pressed(rl, MouseButton::MOUSE_BUTTON_LEFT) {
+        let mouse = rl.get_mouse_position();
+        let at = state
+            .camera
+            .canvas_to_world(Vec2::new(mouse.x, mouse.y), 1.0);
+        lowres::editor::log(format!("click at ({:.0}, {:.0})", at.x, at.y));
+        state.burst = Some(at);
+    }
+    if cfg!(debug_assertions) {
+        if input.is_key_pressed(rl, raylib::consts::KeyboardKey::KEY_F5) {
+            quick_save(state);
+        }
+        if input.is_key_pressed(rl, raylib::consts::KeyboardKey::KEY_F9) {
+            quick_load(state);
+        }
+    }
 }
 
-pub fn step(rl: &mut RaylibHandle, rlt: &mut RaylibThread, state: &mut State) {}
+fn quick_save(state: &State) {
+    match state.saves.save(QUICK_SAVE, &state.save_data()) {
+        Ok(()) => lowres::editor::log(format!(
+            "saved {} in {}",
+            QUICK_SAVE,
+            state.saves.dir().display()
+        )),
+        Err(e) => lowres::editor::log(format!("quick save: {}", e)),
+    }
+}
 
-pub fn draw(state: &State, d: &mut RaylibTextureMode<RaylibDrawHandle>) {
-    d.draw_text("Low Res Sketch!", 12, 12, 12, Color::WHITE);
-    let mouse_pos = d.get_mouse_position();
-    d.draw_circle(mouse_pos.x as i32, mouse_pos.y as i32, 6.0, Color::GREEN);
+fn quick_load(state: &mut State) {
+    match state.saves.load(QUICK_SAVE) {
+        Ok(data) => {
+            state.restore(data);
+            lowres::editor::log("loaded quick save");
+        }
+        Err(e) => lowres::editor::log(format!("quick load: {}", e)),
+    }
+}
 
-    let angle = d.get_time() as f32;
+/// Plays `MUSIC_PATH` if it's there; the sketch runs silently without it.
+pub fn start_music(state: &mut State, audio: &mut Audio) {
+    let song = match TrackerSong::load(MUSIC_PATH) {
+        Ok(song) => song,
+        Err(e) => {
+            lowres::editor::log(format!("no music: {}", e));
+            return;
+        }
+    };
+    if let Err(e) = audio.load_music("music", MUSIC_PATH) {
+        lowres::editor::log(format!("no music: {}", e));
+        return;
+    }
+    audio.play_music("music", 0.0);
+    state.beat = Some(TrackerSync::new(song, FRAMES_PER_SECOND));
+}
+
+pub fn egui_ui(ctx: &egui::Context, state: &mut State) {
+    egui::Window::new("Sketch")
+        .default_pos([16.0, 96.0])
+        .show(ctx, |ui| {
+            ui.label("egui over the upscaled canvas");
+            ui.label(format!(
+                "{} entities, click to spawn more",
+                state.world.len()
+            ));
+            ui.collapsing("Spark sound", |ui| {
+                if state.spark_sound.ui(ui) {
+                    state.spark_changed = true;
+                    state.preview_spark = true;
+                }
+            });
+            if ui.button("Quit").clicked() {
+                state.running = false;
+            }
+        });
+}
 
-    let center = Vec2::new(d.get_screen_width() as f32, d.get_screen_height() as f32) / 2.0;
-    let offset = center / 4.0;
+pub fn step(rl: &mut RaylibHandle, rlt: &mut RaylibThread, state: &mut State, audio: &mut Audio) {
+    let world = &mut state.world;
+    let commands = world.commands();
 
-    for i in 0..3 {
-        let rot = glam::Mat2::from_angle(angle + i as f32 * 90.0);
-        let rect_pos_rotated = rot * offset + center;
+    state.ring_pulse.step(&mut ());
 
-        let size =
-            (((d.get_time() as f32 + i as f32 * 1.0) * 2.0).sin() + 1.0) / 2.0 * offset.y + 4.0;
-        d.draw_rectangle(
-            rect_pos_rotated.x as i32,
-            rect_pos_rotated.y as i32,
-            size as i32,
-            size as i32,
-            Color::RED,
-        );
+    if std::mem::take(&mut state.spark_changed) {
+        if let Err(e) = state.spark_sound.params.load_into(audio, "spark", 4) {
+            lowres::editor::log(format!("spark sound: {}", e));
+        }
+    }
+    if std::mem::take(&mut state.preview_spark) {
+        audio.play("spark");
+    }
+
+    if let Some(beat) = &mut state.beat {
+        if let Some(played) = audio.music_time_played() {
+            beat.resync(played as f64);
+        }
+        if beat.step().iter().any(|row| row.row % 8 == 0) {
+            state.camera.add_trauma(0.15);
+        }
+    }
+
+    if let Some(at) = state.burst.take() {
+        audio.play("spark");
+        state.camera.add_trauma(0.4);
+        for _ in 0..state.spark_count {
+            let angle = (rl.get_random_value::<i32>(0..360) as f32).to_radians();
+            let speed = rl.get_random_value::<i32>(5..20) as f32 / 10.0;
+            world.spawn((
+                Position(at),
+                Velocity(Vec2::from_angle(angle) * speed),
+                Lifetime(rl.get_random_value::<i32>(30..90) as u32),
+                Square {
+                    size: 2.0,
+                    color: state.spark_color,
+                },
+            ));
+        }
     }
+
+    world.for_each2_mut(|_, orbit: &mut Orbit, pos: &mut Position| {
+        orbit.angle += orbit.speed;
+        pos.0 = orbit.center + Vec2::from_angle(orbit.angle) * orbit.radius;
+    });
+    world.for_each2_mut(|_, vel: &mut Velocity, pos: &mut Position| {
+        vel.0.y += 0.05;
+        pos.0 += vel.0;
+    });
+    if let Some((_, _, pos)) = world.query2::<Orbit, Position>().next() {
+        state.camera.follow(pos.0);
+    }
+    state.camera.step(1.0 / FRAMES_PER_SECOND as f32);
+    world.for_each2_mut(|entity, life: &mut Lifetime, square: &mut Square| {
+        life.0 = life.0.saturating_sub(1);
+        if life.0 < 10 {
+            square.size = 1.0;
+        }
+        if life.0 == 0 {
+            commands.despawn(entity);
+        }
+    });
+
+    world.flush();
+}
+
+/// `alpha` blends the camera from the previous tick (0.0) to the current one (1.0).
+pub fn draw(
+    state: &State,
+    d: &mut RaylibDrawHandle,
+    rlt: &RaylibThread,
+    layers: &mut Layers,
+    alpha: f32,
+) {
+    let world_mouse = state
+        .camera
+        .canvas_to_world(layers.mouse_position(d, WORLD), alpha);
+    let effects_mouse = layers.mouse_position(d, EFFECTS);
+
+    let camera = state.camera.apply(layers.get_mut(WORLD), alpha);
+    layers.draw(d, rlt, WORLD, |d| {
+        let mut d = d.begin_mode2D(camera);
+        d.draw_rectangle_lines(-40, -30, 320, 220, Color::DARKGRAY);
+        d.draw_circle(
+            world_mouse.x as i32,
+            world_mouse.y as i32,
+            6.0,
+            Color::GREEN,
+        );
+
+        for (_, pos, square) in state.world.query2::<Position, Square>() {
+            let half = square.size / 2.0;
+            d.draw_rectangle(
+                (pos.0.x - half) as i32,
+                (pos.0.y - half) as i32,
+                square.size as i32,
+                square.size as i32,
+                square.color,
+            );
+        }
+    });
+
+    // medium res: smoother than the world and the hud
+    layers.draw(d, rlt, EFFECTS, |d| {
+        let radius = state.ring_pulse.value();
+        d.draw_ring(
+            Vector2::new(effects_mouse.x, effects_mouse.y),
+            radius,
+            radius + 2.0,
+            0.0,
+            360.0,
+            32,
+            state.ring_color,
+        );
+    });
+
+    layers.draw(d, rlt, HUD, |d| match &state.font {
+        Some(font) => font.draw_text(
+            d,
+            "Low Res Sketch!",
+            IVec2::new(WORLD_DIMS.x as i32 / 2, 6),
+            Align::Center,
+            Color::WHITE,
+        ),
+        None => d.draw_text("Low Res Sketch!", 6, 6, 10, Color::WHITE),
+    });
 }
@@ -1,18 +1,21 @@
 use glam::UVec2;
+use lowres::audio::{self, Audio};
+use lowres::timestep::Timestep;
 use raylib::prelude::*;
 use raylib::{ffi::SetTraceLogLevel, prelude::TraceLogLevel};
 
 mod sketch;
 
-const TIMESTEP: f32 = 1.0 / sketch::FRAMES_PER_SECOND as f32;
-
 fn main() {
     // Initialize the game state and Raylib
     let mut state = sketch::State::new();
+    let mut timestep = Timestep::new(sketch::FRAMES_PER_SECOND);
     let (mut rl, mut rlt) = raylib::init().title("raylib-rs-native-template").build();
     unsafe {
         SetTraceLogLevel(TraceLogLevel::LOG_WARNING as i32);
     }
+    let audio_device = audio::open_device();
+    let mut audio = Audio::new(audio_device.as_ref());
 
     // --- Native Resolution Setup ---
     // We only need one set of dimensions now.
@@ -34,13 +37,13 @@ fn main() {
         // Process inputs from the user
         sketch::process_events_and_input(&mut rl, &mut state);
 
+        // Music streams need feeding every frame, not just on ticks.
+        audio.update(rl.get_frame_time());
+
         // --- Fixed Timestep Update Logic ---
         // This ensures the game logic runs at a consistent rate.
-        let dt = rl.get_frame_time();
-        state.time_since_last_update += dt;
-        while state.time_since_last_update > TIMESTEP {
-            state.time_since_last_update -= TIMESTEP;
-            sketch::step(&mut rl, &mut rlt, &mut state);
+        for _ in 0..timestep.advance(rl.get_frame_time()) {
+            sketch::step(&mut rl, &mut rlt, &mut state, &mut audio);
         }
 
         // --- Drawing Logic ---
@@ -1,18 +1,29 @@
 use glam::Vec2;
+use lowres::audio::Audio;
+use lowres::tween::{Animation, Ease, Repeat, Tween};
 use raylib::prelude::*;
 
 pub const FRAMES_PER_SECOND: u32 = 60;
 
 pub struct State {
     pub running: bool,
-    pub time_since_last_update: f32,
+    /// Turn of the squares around the centre, in radians.
+    pub spin: Tween<f32>,
+    /// Size of each square, 0.0 to 1.0, a second apart.
+    pub pulses: [Tween<f32>; 3],
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
             running: true,
-            time_since_last_update: 0.0,
+            spin: Tween::new(0.0, std::f32::consts::TAU, 377, Ease::Linear).repeat(Repeat::Forever),
+            pulses: std::array::from_fn(|i| {
+                Tween::new(0.0, 1.0, 94, Ease::InOutSine)
+                    .yoyo()
+                    .repeat(Repeat::Forever)
+                    .delay(i as u32 * FRAMES_PER_SECOND)
+            }),
         }
     }
 }
@@ -23,24 +34,33 @@ pub fn process_events_and_input(rl: &mut RaylibHandle, state: &mut State) {
     }
 }
 
-pub fn step(rl: &mut RaylibHandle, rlt: &mut RaylibThread, state: &mut State) {}
+pub fn step(
+    _rl: &mut RaylibHandle,
+    _rlt: &mut RaylibThread,
+    state: &mut State,
+    _audio: &mut Audio,
+) {
+    state.spin.step(&mut ());
+    for pulse in &mut state.pulses {
+        pulse.step(&mut ());
+    }
+}
 
 pub fn draw(state: &State, d: &mut RaylibDrawHandle) {
     d.draw_text("Low Res Sketch!", 12, 12, 12, Color::WHITE);
     let mouse_pos = d.get_mouse_position();
     d.draw_circle(mouse_pos.x as i32, mouse_pos.y as i32, 6.0, Color::GREEN);
 
-    let angle = d.get_time() as f32;
+    let angle = state.spin.value();
 
     let center = Vec2::new(d.get_screen_width() as f32, d.get_screen_height() as f32) / 2.0;
     let offset = center / 4.0;
 
-    for i in 0..3 {
+    for (i, pulse) in state.pulses.iter().enumerate() {
         let rot = glam::Mat2::from_angle(angle + i as f32 * 90.0);
         let rect_pos_rotated = rot * offset + center;
 
-        let size =
-            (((d.get_time() as f32 + i as f32 * 1.0) * 2.0).sin() + 1.0) / 2.0 * offset.y + 4.0;
+        let size = pulse.value() * offset.y + 4.0;
         d.draw_rectangle(
             rect_pos_rotated.x as i32,
             rect_pos_rotated.y as i32,
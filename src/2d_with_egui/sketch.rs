@@ -1,14 +1,24 @@
 // src/2d_with_egui/sketch.rs
 use egui;
 use glam::{Mat2, Vec2};
+use lowres::audio::Audio;
+use lowres::sfxr::SfxrParams;
 use raylib::prelude::*;
 
-pub const FPS: u32 = 60;
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+lowres::inspect_struct!(State {
+    angle_deg: 0.0..=360.0,
+    speed_deg_per_s: 0.0..=360.0,
+    color,
+});
 
 #[derive(Debug)]
 pub struct State {
     pub running: bool,
     pub angle_deg: f32,
+    // angle at the previous tick, for interpolating between ticks in draw
+    pub prev_angle_deg: f32,
     pub speed_deg_per_s: f32,
     pub color: Color,
 }
@@ -18,21 +28,38 @@ impl State {
         Self {
             running: true,
             angle_deg: 0.0,
+            prev_angle_deg: 0.0,
             speed_deg_per_s: 120.0,
             color: Color::GREEN,
         }
     }
 }
 
+/// Generated, so the example needs no asset files.
+pub fn load_sounds(audio: &mut Audio) {
+    if let Err(e) = SfxrParams::coin(2).load_into(audio, "turn", 2) {
+        println!("turn sound: {}", e);
+    }
+}
+
 /* ----------- fixed-step update --------------------------------------- */
-pub fn step(st: &mut State, dt: f32) {
+pub fn step(st: &mut State, audio: &mut Audio, dt: f32) {
+    st.prev_angle_deg = st.angle_deg;
     st.angle_deg = (st.angle_deg + st.speed_deg_per_s * dt) % 360.0;
+    // a blip every full turn
+    if st.angle_deg < st.prev_angle_deg {
+        audio.play("turn");
+    }
 }
 
 /* ----------- raylib drawing ------------------------------------------ */
-pub fn draw(st: &State, d: &mut RaylibDrawHandle) {
+/// `alpha` blends from the previous tick (0.0) to the current one (1.0).
+pub fn draw(st: &State, d: &mut RaylibDrawHandle, alpha: f32) {
     let centre = Vec2::new(d.get_screen_width() as f32, d.get_screen_height() as f32) * 0.5;
-    let rot = Mat2::from_angle(st.angle_deg.to_radians());
+    // the angle wraps at 360, so blend along the shorter way round
+    let delta = (st.angle_deg - st.prev_angle_deg + 540.0) % 360.0 - 180.0;
+    let angle_deg = st.prev_angle_deg + delta * alpha;
+    let rot = Mat2::from_angle(angle_deg.to_radians());
     let offset = Vec2::new(150.0, 0.0);
 
     // three squares 120° apart
@@ -45,13 +72,6 @@ pub fn draw(st: &State, d: &mut RaylibDrawHandle) {
 /* ----------- egui overlay ------------------------------------------- */
 pub fn egui_ui(ctx: &egui::Context, st: &mut State) {
     egui::Window::new("Controls").show(ctx, |ui| {
-        ui.add(egui::Slider::new(&mut st.speed_deg_per_s, 0.0..=360.0).text("spin speed (°/s)"));
-
-        let mut rgb = [st.color.r, st.color.g, st.color.b];
-        if ui.color_edit_button_srgb(&mut rgb).changed() {
-            st.color = Color::new(rgb[0], rgb[1], rgb[2], 255);
-        }
-
         if ui.button("Quit").clicked() {
             st.running = false;
         }
@@ -0,0 +1,347 @@
+use lowres::inspect::{Inspect, InspectRanged};
+use raylib::prelude::*;
+
+/// Upper bound on point lights, must match MAX_POINT_LIGHTS in the fragment shader.
+pub const MAX_POINT_LIGHTS: usize = 4;
+
+const LIGHTING_VS: &str = r#"
+#version 330
+
+in vec3 vertexPosition;
+in vec2 vertexTexCoord;
+in vec3 vertexNormal;
+in vec4 vertexColor;
+
+uniform mat4 mvp;
+uniform mat4 matModel;
+uniform mat4 matNormal;
+
+out vec3 fragPosition;
+out vec2 fragTexCoord;
+out vec4 fragColor;
+out vec3 fragNormal;
+
+void main()
+{
+    fragPosition = vec3(matModel*vec4(vertexPosition, 1.0));
+    fragTexCoord = vertexTexCoord;
+    fragColor = vertexColor;
+    fragNormal = normalize(vec3(matNormal*vec4(vertexNormal, 1.0)));
+    gl_Position = mvp*vec4(vertexPosition, 1.0);
+}
+"#;
+
+const LIGHTING_FS: &str = r#"
+#version 330
+
+#define MAX_POINT_LIGHTS 4
+
+#define FOG_OFF 0
+#define FOG_LINEAR 1
+#define FOG_EXPONENTIAL 2
+
+in vec3 fragPosition;
+in vec2 fragTexCoord;
+in vec4 fragColor;
+in vec3 fragNormal;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+uniform vec3 viewPos;
+
+uniform vec3 ambient;
+
+uniform int dirEnabled;
+uniform vec3 dirDirection;
+uniform vec3 dirColor;
+
+uniform int pointCount;
+uniform vec3 pointPosition[MAX_POINT_LIGHTS];
+uniform vec3 pointColor[MAX_POINT_LIGHTS];
+uniform float pointRadius[MAX_POINT_LIGHTS];
+
+uniform int fogMode;
+uniform float fogStart;
+uniform float fogEnd;
+uniform float fogDensity;
+uniform vec3 fogColor;
+
+out vec4 finalColor;
+
+void main()
+{
+    vec4 base = texture(texture0, fragTexCoord)*colDiffuse*fragColor;
+    vec3 normal = normalize(fragNormal);
+
+    vec3 light = ambient;
+    if (dirEnabled == 1)
+    {
+        light += dirColor*max(dot(normal, -normalize(dirDirection)), 0.0);
+    }
+    for (int i = 0; i < pointCount; i++)
+    {
+        vec3 toLight = pointPosition[i] - fragPosition;
+        float dist = length(toLight);
+        float falloff = clamp(1.0 - dist/pointRadius[i], 0.0, 1.0);
+        light += pointColor[i]*max(dot(normal, toLight/dist), 0.0)*falloff*falloff;
+    }
+
+    vec3 color = base.rgb*light;
+
+    // visibility: 1.0 is unfogged, 0.0 is entirely fog color
+    float dist = length(viewPos - fragPosition);
+    float visibility = 1.0;
+    if (fogMode == FOG_LINEAR)
+    {
+        visibility = clamp((fogEnd - dist)/(fogEnd - fogStart), 0.0, 1.0);
+    }
+    else if (fogMode == FOG_EXPONENTIAL)
+    {
+        visibility = clamp(exp(-fogDensity*dist), 0.0, 1.0);
+    }
+
+    finalColor = vec4(mix(fogColor, color, visibility), base.a);
+}
+"#;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Fog {
+    Off,
+    /// Fully clear before `start`, fully fogged after `end` (world units from the camera).
+    Linear {
+        start: f32,
+        end: f32,
+    },
+    /// visibility = e^(-density * distance)
+    Exponential {
+        density: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, not the direction towards the light.
+    pub direction: Vector3,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub position: Vector3,
+    pub color: Color,
+    pub intensity: f32,
+    /// Distance at which the light has fully faded out.
+    pub radius: f32,
+}
+
+/// Everything the lighting shader needs, owned by the sketch `State`.
+#[derive(Debug, Clone)]
+pub struct LightingConfig {
+    pub ambient: Color,
+    pub ambient_intensity: f32,
+    pub directional: Option<DirectionalLight>,
+    /// Only the first `MAX_POINT_LIGHTS` are used.
+    pub point_lights: Vec<PointLight>,
+    pub fog: Fog,
+}
+
+lowres::inspect_struct!(DirectionalLight {
+    direction,
+    color,
+    intensity: 0.0..=4.0,
+});
+
+lowres::inspect_struct!(PointLight {
+    position,
+    color,
+    intensity: 0.0..=4.0,
+    radius: 0.0..=50.0,
+});
+
+lowres::inspect_struct!(LightingConfig {
+    ambient,
+    ambient_intensity: 0.0..=4.0,
+    directional,
+    point_lights,
+    fog,
+});
+
+impl Inspect for Fog {
+    fn inspect(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(label);
+            for (name, default) in [
+                ("off", Fog::Off),
+                (
+                    "linear",
+                    Fog::Linear {
+                        start: 5.0,
+                        end: 12.0,
+                    },
+                ),
+                ("exponential", Fog::Exponential { density: 0.1 }),
+            ] {
+                let selected = std::mem::discriminant(&*self) == std::mem::discriminant(&default);
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *self = default;
+                    changed = true;
+                }
+            }
+        });
+        match self {
+            Fog::Off => {}
+            Fog::Linear { start, end } => {
+                changed |= start.inspect_ranged(ui, "start", 0.0..=100.0);
+                changed |= end.inspect_ranged(ui, "end", 0.0..=100.0);
+            }
+            Fog::Exponential { density } => {
+                changed |= density.inspect_ranged(ui, "density", 0.0..=1.0);
+            }
+        }
+        changed
+    }
+}
+
+struct Locations {
+    ambient: i32,
+    dir_enabled: i32,
+    dir_direction: i32,
+    dir_color: i32,
+    point_count: i32,
+    point_position: i32,
+    point_color: i32,
+    point_radius: i32,
+    fog_mode: i32,
+    fog_start: i32,
+    fog_end: i32,
+    fog_density: i32,
+    fog_color: i32,
+}
+
+pub struct Lighting {
+    shader: Shader,
+    locs: Locations,
+}
+
+impl Lighting {
+    pub fn load(rl: &mut RaylibHandle, rlt: &RaylibThread) -> Self {
+        let mut shader = rl.load_shader_from_memory(rlt, Some(LIGHTING_VS), Some(LIGHTING_FS));
+
+        let view_pos = shader.get_shader_location("viewPos");
+        shader.locs_mut()[ShaderLocationIndex::SHADER_LOC_VECTOR_VIEW as usize] = view_pos;
+
+        let locs = Locations {
+            ambient: shader.get_shader_location("ambient"),
+            dir_enabled: shader.get_shader_location("dirEnabled"),
+            dir_direction: shader.get_shader_location("dirDirection"),
+            dir_color: shader.get_shader_location("dirColor"),
+            point_count: shader.get_shader_location("pointCount"),
+            point_position: shader.get_shader_location("pointPosition"),
+            point_color: shader.get_shader_location("pointColor"),
+            point_radius: shader.get_shader_location("pointRadius"),
+            fog_mode: shader.get_shader_location("fogMode"),
+            fog_start: shader.get_shader_location("fogStart"),
+            fog_end: shader.get_shader_location("fogEnd"),
+            fog_density: shader.get_shader_location("fogDensity"),
+            fog_color: shader.get_shader_location("fogColor"),
+        };
+
+        Self { shader, locs }
+    }
+
+    /// Upload the config to the shader. Call once per frame before drawing anything lit.
+    /// `fog_color` should be whatever the canvas is cleared with so distant geometry fades into it.
+    pub fn apply(&mut self, config: &LightingConfig, fog_color: Color, camera: &Camera3D) {
+        let locs = &self.locs;
+        let shader = &mut self.shader;
+
+        let view_loc = shader.locs()[ShaderLocationIndex::SHADER_LOC_VECTOR_VIEW as usize];
+        shader.set_shader_value(view_loc, camera.position);
+
+        // rlgl batches (draw_cube, draw_plane, ...) never upload matModel/matNormal,
+        // so reset them here or they keep whatever the last draw_model left behind.
+        let model_loc = shader.locs()[ShaderLocationIndex::SHADER_LOC_MATRIX_MODEL as usize];
+        let normal_loc = shader.locs()[ShaderLocationIndex::SHADER_LOC_MATRIX_NORMAL as usize];
+        shader.set_shader_value_matrix(model_loc, Matrix::identity());
+        shader.set_shader_value_matrix(normal_loc, Matrix::identity());
+
+        shader.set_shader_value(
+            locs.ambient,
+            scaled_rgb(config.ambient, config.ambient_intensity),
+        );
+
+        match config.directional {
+            Some(dir) => {
+                shader.set_shader_value(locs.dir_enabled, 1);
+                shader.set_shader_value(locs.dir_direction, dir.direction);
+                shader.set_shader_value(locs.dir_color, scaled_rgb(dir.color, dir.intensity));
+            }
+            None => shader.set_shader_value(locs.dir_enabled, 0),
+        }
+
+        let lights = &config.point_lights[..config.point_lights.len().min(MAX_POINT_LIGHTS)];
+        let positions: Vec<Vector3> = lights.iter().map(|l| l.position).collect();
+        let colors: Vec<[f32; 3]> = lights
+            .iter()
+            .map(|l| scaled_rgb(l.color, l.intensity))
+            .collect();
+        let radii: Vec<f32> = lights.iter().map(|l| l.radius.max(0.001)).collect();
+        shader.set_shader_value(locs.point_count, lights.len() as i32);
+        if !lights.is_empty() {
+            shader.set_shader_value_v(locs.point_position, &positions);
+            shader.set_shader_value_v(locs.point_color, &colors);
+            shader.set_shader_value_v(locs.point_radius, &radii);
+        }
+
+        let (mode, start, end, density) = match config.fog {
+            Fog::Off => (0, 0.0, 1.0, 0.0),
+            Fog::Linear { start, end } => (1, start, end.max(start + 0.001), 0.0),
+            Fog::Exponential { density } => (2, 0.0, 1.0, density),
+        };
+        shader.set_shader_value(locs.fog_mode, mode);
+        shader.set_shader_value(locs.fog_start, start);
+        shader.set_shader_value(locs.fog_end, end);
+        shader.set_shader_value(locs.fog_density, density);
+        shader.set_shader_value(locs.fog_color, scaled_rgb(fog_color, 1.0));
+    }
+
+    /// For primitive draws: `d3.begin_shader_mode(lighting.shader_mut())`.
+    pub fn shader_mut(&mut self) -> &mut Shader {
+        &mut self.shader
+    }
+
+    /// draw_model ignores shader mode and uses each material's shader, so swap ours in
+    /// for the duration of the draw. It is swapped back out afterwards because unloading
+    /// the model would otherwise also unload (and double free) the lighting shader.
+    pub fn draw_model<D: RaylibDraw3D>(
+        &self,
+        d: &mut D,
+        model: &mut Model,
+        position: Vector3,
+        scale: f32,
+        tint: Color,
+    ) {
+        let lit: ffi::Shader = *self.shader.as_ref();
+        let previous: Vec<ffi::Shader> = model
+            .materials_mut()
+            .iter_mut()
+            .map(|material| std::mem::replace(&mut material.shader, lit))
+            .collect();
+
+        d.draw_model(&*model, position, scale, tint);
+
+        for (material, shader) in model.materials_mut().iter_mut().zip(previous) {
+            material.shader = shader;
+        }
+    }
+}
+
+fn scaled_rgb(color: Color, intensity: f32) -> [f32; 3] {
+    [
+        color.r as f32 / 255.0 * intensity,
+        color.g as f32 / 255.0 * intensity,
+        color.b as f32 / 255.0 * intensity,
+    ]
+}
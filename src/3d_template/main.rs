@@ -1,17 +1,28 @@
 use glam::{IVec2, UVec2};
+use lowres::audio::{self, Audio};
+use lowres::editor::Editor;
+use lowres::egui_raylib::EguiRaylib;
+use lowres::inspect::inspector_window;
+use lowres::layers::Layers;
+use lowres::timestep::Timestep;
 use raylib::prelude::*;
 use raylib::{ffi::SetTraceLogLevel, prelude::TraceLogLevel};
 use std::env;
 
+mod lighting;
 mod sketch;
 
-const TIMESTEP: f32 = 1.0 / sketch::FRAMES_PER_SECOND as f32;
 fn main() {
     let mut state = sketch::State::new();
+    let mut timestep = Timestep::new(sketch::FRAMES_PER_SECOND);
+    // full resolution framebuffer on scaled displays; layers and egui still work in logical pixels
+    lowres::display::request_highdpi();
     let (mut rl, mut rlt) = raylib::init().title("raylib-rs-lowres-template").build();
     unsafe {
         SetTraceLogLevel(TraceLogLevel::LOG_WARNING as i32);
     }
+    let audio_device = audio::open_device();
+    let mut audio = Audio::new(audio_device.as_ref());
 
     let mut plane = rl
         .load_model(&rlt, "src/3d_template/assets/plane.obj")
@@ -35,6 +46,7 @@ fn main() {
         };
         mats[raylib::consts::MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].texture = texture;
     }
+    let mut lighting = lighting::Lighting::load(&mut rl, &rlt);
 
     let window_dims = UVec2::new(1280, 720);
     let dims = UVec2::new(240, 160);
@@ -46,40 +58,65 @@ fn main() {
     }
 
     center_window(&mut rl, window_dims);
-    let mouse_scale = dims.as_vec2() / window_dims.as_vec2();
-    rl.set_mouse_scale(mouse_scale.x, mouse_scale.y);
 
-    let mut render_texture = rl
-        .load_render_texture(&rlt, dims.x, dims.y)
-        .unwrap_or_else(|e| {
-            println!("Error creating render texture: {}", e);
-            std::process::exit(1);
-        });
+    let mut layers = Layers::new(window_dims);
+    for (name, layer_dims, clear_color) in [
+        (sketch::WORLD, dims, state.clear_color),
+        (sketch::HUD, window_dims, Color::BLANK),
+    ] {
+        layers
+            .add(&mut rl, &rlt, name, layer_dims, clear_color)
+            .unwrap_or_else(|e| {
+                println!("Error creating render texture: {}", e);
+                std::process::exit(1);
+            });
+    }
+    layers.set_mouse_layer(&mut rl, sketch::WORLD);
 
-    while state.running && !rl.window_should_close() {
-        sketch::process_events_and_input(&mut rl, &mut state);
+    // egui sits on top of the composited layers and reads window-space mouse coordinates
+    let mut egui = EguiRaylib::new(&mut rl);
+    egui.set_mouse_mapping(layers.mouse_offset(), layers.mouse_scale());
+    // F1 toggles dev mode: the canvas in an egui viewport with inspector, log and perf panels
+    let mut editor = Editor::new(false);
 
-        let dt = rl.get_frame_time();
-        state.time_since_last_update += dt;
-        while state.time_since_last_update > TIMESTEP {
-            state.time_since_last_update -= TIMESTEP;
+    while state.running && !rl.window_should_close() {
+        editor.prepare(&rl, &mut egui, &layers);
+        egui.run(&mut rl, |ctx| {
+            if editor.enabled {
+                editor.ui(ctx, &layers, &mut state);
+            } else {
+                sketch::egui_ui(ctx, &mut state);
+                inspector_window(ctx, "State", &mut state);
+            }
+            timestep.window(ctx);
+        });
+        editor.map_mouse(&mut rl, &mut egui, &mut layers);
+        let mut input = egui.input_filter();
+        editor.filter_input(&mut input);
+        sketch::process_events_and_input(&mut rl, &mut state, &input);
 
-            sketch::step(&mut rl, &mut rlt, &mut state);
+        audio.update(rl.get_frame_time());
+        for _ in 0..timestep.advance(rl.get_frame_time()) {
+            sketch::step(&mut rl, &mut rlt, &mut state, &mut audio);
         }
 
+        layers.get_mut(sketch::WORLD).clear_color = state.clear_color;
         let mut draw_handle = rl.begin_drawing(&rlt);
-        {
-            let low_res_draw_handle =
-                &mut draw_handle.begin_texture_mode(&rlt, &mut render_texture);
-            low_res_draw_handle.clear_background(Color::BLACK);
-            sketch::draw(&state, low_res_draw_handle, &mut plane);
-        }
-        scale_and_blit_render_texture_to_window(
+        layers.clear(&mut draw_handle, &rlt);
+        sketch::draw(
+            &state,
             &mut draw_handle,
-            &mut render_texture,
-            fullscreen,
-            window_dims,
+            &rlt,
+            &mut layers,
+            &mut plane,
+            &mut lighting,
         );
+        if editor.enabled {
+            draw_handle.clear_background(Color::BLACK);
+        } else {
+            layers.composite(&mut draw_handle, fullscreen);
+        }
+        egui.paint(&mut draw_handle);
     }
 }
 
@@ -91,37 +128,3 @@ pub fn center_window(rl: &mut raylib::RaylibHandle, window_dims: UVec2) {
     rl.set_window_position(offset.x, offset.y);
     rl.set_target_fps(144);
 }
-
-pub fn scale_and_blit_render_texture_to_window(
-    draw_handle: &mut RaylibDrawHandle,
-    render_texture: &mut RenderTexture2D,
-    fullscreen: bool,
-    window_dims: UVec2,
-) {
-    let source_rec = Rectangle::new(
-        0.0,
-        0.0,
-        render_texture.texture.width as f32,
-        -render_texture.texture.height as f32,
-    );
-    // dest rec should be the fullscreen resolution if graphics.fullscreen, otherwise window_dims
-    let dest_rec = if fullscreen {
-        // get the fullscreen resolution
-        let screen_width = draw_handle.get_screen_width();
-        let screen_height = draw_handle.get_screen_height();
-        Rectangle::new(0.0, 0.0, screen_width as f32, screen_height as f32)
-    } else {
-        Rectangle::new(0.0, 0.0, window_dims.x as f32, window_dims.y as f32)
-    };
-
-    let origin = Vector2::new(0.0, 0.0);
-
-    draw_handle.draw_texture_pro(
-        render_texture,
-        source_rec,
-        dest_rec,
-        origin,
-        0.0,
-        Color::WHITE,
-    );
-}
@@ -1,13 +1,40 @@
 use glam::Vec2;
+use lowres::audio::Audio;
+use lowres::egui_raylib::InputFilter;
+use lowres::layers::Layers;
+use lowres::tween::{Animation, Ease, Repeat, Tween};
 use raylib::prelude::*;
 
+use crate::lighting::{DirectionalLight, Fog, Lighting, LightingConfig, PointLight};
+
 pub const FRAMES_PER_SECOND: u32 = 60;
 
+// render layers, composited bottom to top in the order main adds them
+pub const WORLD: &str = "world";
+pub const HUD: &str = "hud";
+
+lowres::inspect_struct!(State {
+    camera,
+    clear_color,
+    lighting,
+});
+
 pub struct State {
     pub running: bool,
-    pub time_since_last_update: f32,
 
     pub camera: Camera3D,
+    /// Canvas clear color, also used as the fog color.
+    pub clear_color: Color,
+    pub lighting: LightingConfig,
+
+    /// Height of the floor plane.
+    pub plane_bob: Tween<f32>,
+    /// Turn of the cubes around the origin, in radians.
+    pub spin: Tween<f32>,
+    /// Size of each cube, 0.0 to 1.0, a second apart; the first also scales the model.
+    pub pulses: [Tween<f32>; 3],
+    /// Roll of the model, in degrees.
+    pub roll: Tween<f32>,
 }
 
 impl State {
@@ -19,70 +46,153 @@ impl State {
             60.0,
         );
 
+        let lighting = LightingConfig {
+            ambient: Color::new(70, 80, 110, 255),
+            ambient_intensity: 0.6,
+            directional: Some(DirectionalLight {
+                direction: Vector3::new(-0.4, -1.0, -0.3),
+                color: Color::new(255, 236, 200, 255),
+                intensity: 0.9,
+            }),
+            point_lights: vec![
+                PointLight {
+                    position: Vector3::new(2.0, 1.0, 0.0),
+                    color: Color::RED,
+                    intensity: 1.5,
+                    radius: 5.0,
+                },
+                PointLight {
+                    position: Vector3::new(-2.0, 1.0, 0.0),
+                    color: Color::SKYBLUE,
+                    intensity: 1.5,
+                    radius: 5.0,
+                },
+            ],
+            fog: Fog::Linear {
+                start: 5.0,
+                end: 12.0,
+            },
+        };
+
         Self {
             running: true,
-            time_since_last_update: 0.0,
 
             camera,
+            clear_color: Color::new(24, 26, 36, 255),
+            lighting,
+
+            plane_bob: Tween::new(-4.0, -2.0, 188, Ease::InOutSine)
+                .yoyo()
+                .repeat(Repeat::Forever),
+            spin: Tween::new(0.0, std::f32::consts::TAU, 377, Ease::Linear).repeat(Repeat::Forever),
+            pulses: std::array::from_fn(|i| {
+                Tween::new(0.0, 1.0, 94, Ease::InOutSine)
+                    .yoyo()
+                    .repeat(Repeat::Forever)
+                    .delay(i as u32 * FRAMES_PER_SECOND)
+            }),
+            roll: Tween::new(0.0, 360.0, 216, Ease::Linear).repeat(Repeat::Forever),
         }
     }
 }
 
-pub fn process_events_and_input(rl: &mut RaylibHandle, state: &mut State) {
-    if rl.is_key_pressed(raylib::consts::KeyboardKey::KEY_ESCAPE) {
+pub fn process_events_and_input(rl: &mut RaylibHandle, state: &mut State, input: &InputFilter) {
+    if input.is_key_pressed(rl, raylib::consts::KeyboardKey::KEY_ESCAPE) {
         state.running = false;
     }
 }
 
-pub fn step(_rl: &mut RaylibHandle, _rlt: &mut RaylibThread, _state: &mut State) {}
+pub fn egui_ui(ctx: &egui::Context, state: &mut State) {
+    egui::Window::new("Sketch")
+        .default_pos([16.0, 96.0])
+        .show(ctx, |ui| {
+            if ui.button("Quit").clicked() {
+                state.running = false;
+            }
+        });
+}
+
+pub fn step(
+    _rl: &mut RaylibHandle,
+    _rlt: &mut RaylibThread,
+    state: &mut State,
+    _audio: &mut Audio,
+) {
+    state.plane_bob.step(&mut ());
+    state.spin.step(&mut ());
+    for pulse in &mut state.pulses {
+        pulse.step(&mut ());
+    }
+    state.roll.step(&mut ());
+}
+
+pub fn draw(
+    state: &State,
+    d: &mut RaylibDrawHandle,
+    rlt: &RaylibThread,
+    layers: &mut Layers,
+    plane: &mut Model,
+    lighting: &mut Lighting,
+) {
+    layers.draw(d, rlt, HUD, |d| {
+        d.draw_text("Low Res Sketch!", 24, 24, 40, Color::WHITE);
+    });
+
+    layers.draw(d, rlt, WORLD, |d| draw_world(state, d, plane, lighting));
+}
+
+fn draw_world(
+    state: &State,
+    d: &mut RaylibTextureMode<RaylibDrawHandle>,
+    plane: &mut Model,
+    lighting: &mut Lighting,
+) {
+    lighting.apply(&state.lighting, state.clear_color, &state.camera);
 
-pub fn draw(state: &State, d: &mut RaylibTextureMode<RaylibDrawHandle>, plane: &mut Model) {
-    d.draw_text("Low Res Sketch!", 12, 12, 12, Color::WHITE);
     let mouse_pos = d.get_mouse_position();
     d.draw_circle(mouse_pos.x as i32, mouse_pos.y as i32, 6.0, Color::GREEN);
 
     let mut d3 = d.begin_mode3D(state.camera);
-    let plane_y = -3.0 + ((d3.get_time() as f32) * 1.0).sin() * 1.0;
-    d3.draw_plane(
-        Vector3::new(0.0, plane_y, 0.0),
-        Vector2::new(6.0, 6.0),
-        Color::LIGHTGRAY,
-    );
-
-    let angle = d3.get_time() as f32;
-    let center = Vec2::new(0.0, 0.0) / 2.0;
-    let offset = Vec2::new(10.0, 0.0) / 4.0;
-    for i in 0..3 {
-        let rot = glam::Mat2::from_angle(angle + i as f32 * 90.0);
-        let rect_pos_rotated = rot * offset + center;
-
-        let size = (((d3.get_time() as f32 + i as f32 * 1.0) * 2.0).sin() + 1.0) / 2.0 * 1.0 + 0.0;
-        d3.draw_cube(
-            Vector3::new(rect_pos_rotated.x, rect_pos_rotated.y, 0.0),
-            size,
-            size,
-            size,
-            Color::GOLD,
+    {
+        let mut lit = d3.begin_shader_mode(lighting.shader_mut());
+        lit.draw_plane(
+            Vector3::new(0.0, state.plane_bob.value(), 0.0),
+            Vector2::new(6.0, 6.0),
+            Color::LIGHTGRAY,
         );
-    }
 
-    let mut pitch = 0.0f32;
-    let mut roll = 0.0f32;
-    let mut yaw = 0.0f32;
+        let angle = state.spin.value();
+        let center = Vec2::new(0.0, 0.0) / 2.0;
+        let offset = Vec2::new(10.0, 0.0) / 4.0;
+        for (i, pulse) in state.pulses.iter().enumerate() {
+            let rot = glam::Mat2::from_angle(angle + i as f32 * 90.0);
+            let rect_pos_rotated = rot * offset + center;
+
+            let size = pulse.value();
+            lit.draw_cube(
+                Vector3::new(rect_pos_rotated.x, rect_pos_rotated.y, 0.0),
+                size,
+                size,
+                size,
+                Color::GOLD,
+            );
+        }
+    }
 
-    roll = d3.get_time() as f32 * 100.0;
-    // pitch = d3.get_time() as f32 * 100.0;
-    // yaw = d3.get_time() as f32 * 100.0;
+    let pitch = 0.0f32;
+    let yaw = 0.0f32;
+    let roll = state.roll.value();
     let mat = Matrix::rotate_xyz(Vector3::new(
         pitch.to_radians(),
         yaw.to_radians(),
         roll.to_radians(),
     ));
 
-    let size = 0.5 + (((d3.get_time() as f32 * 1.0) * 2.0).sin() + 1.0) / 2.0 * 1.0 + 0.0;
+    let size = 0.5 + state.pulses[0].value();
 
     plane.set_transform(&mat);
-    d3.draw_model(
+    lighting.draw_model(
+        &mut d3,
         plane,
         Vector3::new(0.0, 0.0, 0.0),
         0.05 * size,
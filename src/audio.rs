@@ -0,0 +1,295 @@
+//! Sound effects and music on top of raylib's audio device.
+//!
+//! The runner opens the device and hands the sketch an `Audio`. Without a device (no
+//! sound card, a headless CI box) every call still works and nothing plays, and names
+//! that were never loaded are ignored, so a sketch runs fine with its assets missing.
+//! ```ignore
+//! let audio_device = audio::open_device();
+//! let mut audio = Audio::new(audio_device.as_ref());
+//! audio.load_sound("jump", "assets/jump.wav", 4)?;
+//! audio.set_pitch_variation("jump", 2.0);
+//! audio.load_music("overworld", "assets/overworld.ogg")?;
+//! audio.play_music("overworld", 0.0);
+//! // every frame
+//! audio.update(rl.get_frame_time());
+//! // in step
+//! audio.play("jump");
+//! audio.play_music("boss", 2.0); // crossfades over two seconds
+//! ```
+
+use std::collections::HashMap;
+
+use raylib::core::error::Error;
+use raylib::prelude::*;
+
+use crate::rng::Rng;
+
+/// Copies of one sound that can play over each other.
+struct SoundSet<'a> {
+    voices: Vec<Sound<'a>>,
+    /// Next voice to steal when they're all busy, i.e. the one started longest ago.
+    next: usize,
+    volume: f32,
+    /// Random pitch change of up to this many semitones either way.
+    pitch_variation: f32,
+}
+
+struct Track<'a> {
+    name: String,
+    music: Music<'a>,
+    /// Fade level, 0.0 to 1.0, multiplied with the buses.
+    level: f32,
+    /// Level change per second; negative while fading out.
+    fade_rate: f32,
+}
+
+/// Opens raylib's audio device for `Audio::new`. None when there's no usable device
+/// (headless, busy, missing), which just means no sound.
+pub fn open_device() -> Option<RaylibAudio> {
+    match RaylibAudio::init_audio_device() {
+        // raylib reports a device it couldn't open by leaving it not ready, not as an error
+        Ok(device) if device.is_audio_device_ready() => Some(device),
+        Ok(_) => {
+            crate::editor::log("Audio disabled: could not open the audio device");
+            None
+        }
+        Err(e) => {
+            crate::editor::log(format!("Audio disabled: {}", e));
+            None
+        }
+    }
+}
+
+pub struct Audio<'a> {
+    device: Option<&'a RaylibAudio>,
+    /// 0.0 to 1.0; applies to everything.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    sounds: HashMap<String, SoundSet<'a>>,
+    music: HashMap<String, Music<'a>>,
+    /// Playing or fading tracks; the last one is the current track unless it's fading out.
+    tracks: Vec<Track<'a>>,
+    rng: Rng,
+}
+
+impl<'a> Audio<'a> {
+    /// `None` gives a silent backend where everything is a no-op.
+    pub fn new(device: Option<&'a RaylibAudio>) -> Self {
+        Self {
+            device,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            sounds: HashMap::new(),
+            music: HashMap::new(),
+            tracks: Vec::new(),
+            rng: Rng::new(0x2545_f491_4f6c_dd1d),
+        }
+    }
+
+    pub fn null() -> Self {
+        Self::new(None)
+    }
+
+    /// True when there's no device and nothing will ever be heard.
+    pub fn is_silent(&self) -> bool {
+        self.device.is_none()
+    }
+
+    /// Loads a sound effect that can have up to `voices` copies playing at once.
+    pub fn load_sound(&mut self, name: &str, path: &str, voices: usize) -> Result<(), Error> {
+        let Some(device) = self.device else {
+            return Ok(());
+        };
+        let wave = device.new_wave(path)?;
+        self.add_sound(name, &wave, voices)
+    }
+
+    /// Like `load_sound`, from an encoded file in memory; `file_type` is e.g. ".wav".
+    pub fn load_sound_from_memory(
+        &mut self,
+        name: &str,
+        file_type: &str,
+        bytes: &[u8],
+        voices: usize,
+    ) -> Result<(), Error> {
+        let Some(device) = self.device else {
+            return Ok(());
+        };
+        let wave = device.new_wave_from_memory(file_type, bytes)?;
+        self.add_sound(name, &wave, voices)
+    }
+
+    fn add_sound(&mut self, name: &str, wave: &Wave, voices: usize) -> Result<(), Error> {
+        let Some(device) = self.device else {
+            return Ok(());
+        };
+        let voices = (0..voices.max(1))
+            .map(|_| device.new_sound_from_wave(wave))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (volume, pitch_variation) = self
+            .sounds
+            .get(name)
+            .map_or((1.0, 0.0), |set| (set.volume, set.pitch_variation));
+        self.sounds.insert(
+            name.to_string(),
+            SoundSet {
+                voices,
+                next: 0,
+                volume,
+                pitch_variation,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn set_sound_volume(&mut self, name: &str, volume: f32) {
+        if let Some(set) = self.sounds.get_mut(name) {
+            set.volume = volume;
+        }
+    }
+
+    /// Each play shifts the pitch randomly by up to `semitones` up or down, so repeated
+    /// sounds don't grate.
+    pub fn set_pitch_variation(&mut self, name: &str, semitones: f32) {
+        if let Some(set) = self.sounds.get_mut(name) {
+            set.pitch_variation = semitones;
+        }
+    }
+
+    /// Plays a sound effect on a free voice, cutting off its oldest voice if they're
+    /// all busy.
+    pub fn play(&mut self, name: &str) {
+        self.play_with(name, 1.0, 0.5);
+    }
+
+    /// `pan` is 0.0 (right) to 1.0 (left), as raylib has it.
+    pub fn play_with(&mut self, name: &str, volume: f32, pan: f32) {
+        let offset = self.rng.float(2.0) - 1.0;
+        let Some(set) = self.sounds.get_mut(name) else {
+            return;
+        };
+        let index = match set.voices.iter().position(|voice| !voice.is_playing()) {
+            Some(free) => free,
+            None => {
+                let oldest = set.next;
+                set.voices[oldest].stop();
+                oldest
+            }
+        };
+        set.next = (index + 1) % set.voices.len();
+
+        let voice = &set.voices[index];
+        voice.set_volume(set.volume * volume * self.sfx_volume);
+        voice.set_pitch(2f32.powf(offset * set.pitch_variation / 12.0));
+        voice.set_pan(pan);
+        voice.play();
+    }
+
+    pub fn stop_sounds(&mut self) {
+        for voice in self.sounds.values().flat_map(|set| &set.voices) {
+            voice.stop();
+        }
+    }
+
+    /// Loads a track to be streamed by `play_music`.
+    pub fn load_music(&mut self, name: &str, path: &str) -> Result<(), Error> {
+        let Some(device) = self.device else {
+            return Ok(());
+        };
+        self.music.insert(name.to_string(), device.new_music(path)?);
+        Ok(())
+    }
+
+    /// Switches to a track, fading the current one out and the new one in over
+    /// `fade_seconds`. Playing the current track again does nothing.
+    pub fn play_music(&mut self, name: &str, fade_seconds: f32) {
+        if self.current_music() == Some(name) {
+            return;
+        }
+        self.stop_music(fade_seconds);
+        // a track that's still fading out comes back from where it is
+        let mut track = match self.tracks.iter().position(|track| track.name == name) {
+            Some(index) => self.tracks.remove(index),
+            None => {
+                let Some(music) = self.music.remove(name) else {
+                    return;
+                };
+                music.play_stream();
+                Track {
+                    name: name.to_string(),
+                    music,
+                    level: 0.0,
+                    fade_rate: 0.0,
+                }
+            }
+        };
+        if fade_seconds > 0.0 {
+            track.fade_rate = 1.0 / fade_seconds;
+        } else {
+            track.level = 1.0;
+            track.fade_rate = 0.0;
+        }
+        self.tracks.push(track);
+        self.apply_music_volume();
+    }
+
+    /// Fades out whatever's playing.
+    pub fn stop_music(&mut self, fade_seconds: f32) {
+        for track in &mut self.tracks {
+            if fade_seconds > 0.0 {
+                track.fade_rate = -1.0 / fade_seconds;
+            } else {
+                track.level = 0.0;
+                track.fade_rate = -1.0;
+            }
+        }
+    }
+
+    /// The track playing or fading in, if any.
+    pub fn current_music(&self) -> Option<&str> {
+        self.tracks
+            .last()
+            .filter(|track| track.fade_rate >= 0.0)
+            .map(|track| track.name.as_str())
+    }
+
+    /// How far into the current track the stream is, in seconds, by the sound card's
+    /// clock; it starts over when the track loops. None while nothing's playing.
+    pub fn music_time_played(&self) -> Option<f32> {
+        self.tracks
+            .last()
+            .filter(|track| track.fade_rate >= 0.0)
+            .map(|track| track.music.get_time_played())
+    }
+
+    /// Feeds music streams and advances fades. Call once per frame with the real frame
+    /// time; streams starve if this only runs on ticks.
+    pub fn update(&mut self, frame_time: f32) {
+        let Some(device) = self.device else {
+            return;
+        };
+        device.set_master_volume(self.master_volume);
+        for track in &mut self.tracks {
+            track.music.update_stream();
+            track.level = (track.level + track.fade_rate * frame_time).clamp(0.0, 1.0);
+        }
+        // finished fade-outs go back to the library for next time
+        let (faded, playing): (Vec<_>, Vec<_>) = std::mem::take(&mut self.tracks)
+            .into_iter()
+            .partition(|track| track.fade_rate < 0.0 && track.level <= 0.0);
+        self.tracks = playing;
+        for track in faded {
+            track.music.stop_stream();
+            self.music.insert(track.name, track.music);
+        }
+        self.apply_music_volume();
+    }
+
+    fn apply_music_volume(&self) {
+        for track in &self.tracks {
+            track.music.set_volume(track.level * self.music_volume);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! A pixel-perfect scrolling camera for low-res layers.
+//!
+//! The world is drawn through a raylib `Camera2D` snapped to whole pixels, into a layer
+//! added with `Layers::add_scrolling`, which is one pixel larger than the view on each
+//! axis. The fractional part of the camera position is left over as the layer's
+//! `subpixel_offset`, which the compositor applies in window pixels, so scrolling stays
+//! smooth at any window scale while the canvas itself never resamples.
+//!
+//! ```ignore
+//! // setup
+//! layers.add_scrolling(&mut rl, &rlt, WORLD, UVec2::new(240, 160), Color::BLACK)?;
+//! let mut camera = PixelCamera::new(UVec2::new(240, 160));
+//! camera.bounds = Some((Vec2::ZERO, map.pixel_size().as_vec2()));
+//! // step
+//! camera.follow(player.position);
+//! camera.step(timestep.dt());
+//! // draw
+//! let camera_2d = camera.apply(layers.get_mut(WORLD), timestep.alpha());
+//! layers.draw(d, rlt, WORLD, |d| {
+//!     // the tilemap takes the view rectangle and does its own offset
+//!     map.draw(d, camera.visible_rect(timestep.alpha()), Color::WHITE);
+//!     let mut d = d.begin_mode2D(camera_2d);
+//!     hero.draw(&mut d, &sheet, player.position, Color::WHITE);
+//! });
+//! ```
+
+use glam::{UVec2, Vec2};
+use raylib::prelude::*;
+
+use crate::layers::Layer;
+
+pub struct PixelCamera {
+    /// World point at the centre of the view.
+    pub position: Vec2,
+    /// Size of the view in canvas pixels, the layer's `dims`.
+    pub view: UVec2,
+    /// Size of the box around the centre the follow target can move in without
+    /// dragging the camera along.
+    pub dead_zone: Vec2,
+    /// Fraction of the remaining distance to cover each tick, 1.0 to lock on.
+    pub follow_speed: f32,
+    /// Min and max corners of the world the view has to stay inside, shake included. A
+    /// view larger than the bounds is centred on them.
+    pub bounds: Option<(Vec2, Vec2)>,
+    /// 0.0 to 1.0; shake grows with its square. Add to it with `add_trauma`.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub trauma_decay: f32,
+    /// Offset in pixels at full trauma.
+    pub max_shake: Vec2,
+    target: Option<Vec2>,
+    shake_time: f32,
+    shake_offset: Vec2,
+    top_left: Vec2,
+    prev_top_left: Vec2,
+}
+
+impl PixelCamera {
+    pub fn new(view: UVec2) -> Self {
+        let top_left = -view.as_vec2() / 2.0;
+        Self {
+            position: Vec2::ZERO,
+            view,
+            dead_zone: Vec2::ZERO,
+            follow_speed: 1.0,
+            bounds: None,
+            trauma: 0.0,
+            trauma_decay: 1.5,
+            max_shake: Vec2::new(8.0, 6.0),
+            target: None,
+            shake_time: 0.0,
+            shake_offset: Vec2::ZERO,
+            top_left,
+            prev_top_left: top_left,
+        }
+    }
+
+    /// Keeps `target` inside the dead zone from the next step on. Call every tick for
+    /// something that moves.
+    pub fn follow(&mut self, target: Vec2) {
+        self.target = Some(target);
+    }
+
+    pub fn stop_following(&mut self) {
+        self.target = None;
+    }
+
+    /// Jumps straight to `position`, without easing or interpolating from the old view.
+    pub fn snap_to(&mut self, position: Vec2) {
+        self.position = self.clamped(position);
+        self.top_left = self.position - self.view.as_vec2() / 2.0;
+        self.prev_top_left = self.top_left;
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Advances follow and shake by one tick of `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        if let Some(target) = self.target {
+            let half = self.dead_zone / 2.0;
+            let delta = target - self.position;
+            let outside = delta - delta.clamp(-half, half);
+            self.position += outside * self.follow_speed.clamp(0.0, 1.0);
+        }
+        self.position = self.clamped(self.position);
+
+        self.trauma = (self.trauma - self.trauma_decay * dt).max(0.0);
+        self.shake_time += dt;
+        let amount = self.trauma * self.trauma;
+        self.shake_offset = self.max_shake
+            * amount
+            * Vec2::new(noise(self.shake_time, 0.0), noise(self.shake_time, 17.3));
+
+        // clamped again so shaking against an edge doesn't show what's past it
+        self.prev_top_left = self.top_left;
+        self.top_left = self.clamped(self.position + self.shake_offset) - self.view.as_vec2() / 2.0;
+    }
+
+    /// World position of the view's top left corner, interpolated between the last two
+    /// steps by `alpha`.
+    pub fn top_left(&self, alpha: f32) -> Vec2 {
+        self.prev_top_left.lerp(self.top_left, alpha)
+    }
+
+    /// The world rectangle the view covers, including the padding pixel, for culling.
+    pub fn visible_rect(&self, alpha: f32) -> Rectangle {
+        let top_left = self.top_left(alpha).floor();
+        Rectangle::new(
+            top_left.x,
+            top_left.y,
+            (self.view.x + 1) as f32,
+            (self.view.y + 1) as f32,
+        )
+    }
+
+    /// Snaps the view to whole pixels and hands the leftover fraction to the layer.
+    /// Draw the world in the layer through the returned camera.
+    pub fn apply(&self, layer: &mut Layer, alpha: f32) -> Camera2D {
+        let top_left = self.top_left(alpha);
+        let snapped = top_left.floor();
+        layer.subpixel_offset = top_left - snapped;
+        Camera2D {
+            offset: Vector2::zero(),
+            target: Vector2::new(snapped.x, snapped.y),
+            rotation: 0.0,
+            zoom: 1.0,
+        }
+    }
+
+    /// Canvas pixels (e.g. the mouse in the layer) to world coordinates. Includes the
+    /// sub-pixel offset, so the result matches what's on screen.
+    pub fn canvas_to_world(&self, canvas_pos: Vec2, alpha: f32) -> Vec2 {
+        canvas_pos + self.top_left(alpha)
+    }
+
+    pub fn world_to_canvas(&self, world_pos: Vec2, alpha: f32) -> Vec2 {
+        world_pos - self.top_left(alpha)
+    }
+
+    fn clamped(&self, position: Vec2) -> Vec2 {
+        let Some((min, max)) = self.bounds else {
+            return position;
+        };
+        let half = self.view.as_vec2() / 2.0;
+        let clamp_axis = |p: f32, min: f32, max: f32, half: f32| {
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                p.clamp(min + half, max - half)
+            }
+        };
+        Vec2::new(
+            clamp_axis(position.x, min.x, max.x, half.x),
+            clamp_axis(position.y, min.y, max.y, half.y),
+        )
+    }
+}
+
+/// Smooth noise in -1.0..1.0 from a few out-of-step sines, different for each `seed`.
+fn noise(t: f32, seed: f32) -> f32 {
+    let t = t * 20.0 + seed;
+    (t.sin() + (t * 2.3 + 1.7).sin() * 0.6 + (t * 5.1 + 4.2).sin() * 0.3) / 1.9
+}
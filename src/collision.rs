@@ -0,0 +1,795 @@
+//! Shape overlap tests, casts, tile grid movement and a spatial hash, all in world units.
+//!
+//! Contacts say how to push the first shape out of the second: move it by
+//! `normal * depth`. Casts and sweeps report the fraction of the movement that was free
+//! and the surface normal hit.
+//! ```ignore
+//! let player = Shape::from(Aabb::from_size(pos, Vec2::new(8.0, 14.0)));
+//! if let Some(contact) = player.contact(&Shape::from(Circle::new(orb, 4.0))) {
+//!     pos += contact.normal * contact.depth;
+//! }
+//! // walk through a Tiled map's "solid" layer
+//! let solid = map.tile_layer("solid").unwrap();
+//! let sweep = sweep_tiles(body, vel, map.tile_size.as_vec2(), |t| solid.gid(t.x, t.y) != 0);
+//! if sweep.hit_y.is_some() && vel.y > 0.0 {
+//!     grounded = true;
+//! }
+//! // broad phase
+//! grid.clear();
+//! for (entity, bounds) in bodies {
+//!     grid.insert(entity, bounds);
+//! }
+//! for other in grid.query(bullet_bounds) { .. }
+//! ```
+
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec2};
+
+/// Below this, directions are treated as zero.
+const EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+/// Every point within `radius` of the segment from `a` to `b`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Vec2,
+    pub b: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// Unit direction to push the first shape out of the second.
+    pub normal: Vec2,
+    pub depth: f32,
+}
+
+impl Contact {
+    fn flipped(self) -> Self {
+        Self {
+            normal: -self.normal,
+            depth: self.depth,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// 0.0 to 1.0 along the cast.
+    pub t: f32,
+    pub point: Vec2,
+    /// Surface normal at `point`, facing back along the cast.
+    pub normal: Vec2,
+}
+
+/* ----------- shapes --------------------------------------------------- */
+
+impl Aabb {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    /// From a top left corner and size, like raylib's `Rectangle`.
+    pub fn from_size(pos: Vec2, size: Vec2) -> Self {
+        Self::new(pos, pos + size)
+    }
+
+    pub fn from_center(center: Vec2, half_extents: Vec2) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec2 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn translated(&self, by: Vec2) -> Self {
+        Self {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+
+    /// Grown by `amount` on every side.
+    pub fn expanded(&self, amount: Vec2) -> Self {
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Touching edges don't count.
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x
+            && other.min.x < self.max.x
+            && self.min.y < other.max.y
+            && other.min.y < self.max.y
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Where this box, moving by `delta`, first touches `other`. None if it doesn't, or
+    /// if they already overlap; use `Shape::contact` for that. Boxes that start flush
+    /// and move into each other hit at `t` 0.
+    pub fn sweep(&self, delta: Vec2, other: &Aabb) -> Option<Hit> {
+        let hit = Segment::new(self.center(), self.center() + delta)
+            .cast_aabb(&other.expanded(self.half_extents()))?;
+        Some(Hit {
+            point: hit.point - hit.normal * self.half_extents(),
+            ..hit
+        })
+    }
+}
+
+impl Circle {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center(self.center, Vec2::splat(self.radius))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) < self.radius * self.radius
+    }
+}
+
+impl Capsule {
+    pub fn new(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.a, self.b)
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.a, self.b).expanded(Vec2::splat(self.radius))
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.segment().closest_point(point).distance_squared(point) < self.radius * self.radius
+    }
+}
+
+impl Segment {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self { a, b }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.a, self.b)
+    }
+
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let ab = self.b - self.a;
+        let length_squared = ab.length_squared();
+        if length_squared < EPSILON {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(ab) / length_squared).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    /// Closest points on this segment and `other`, in that order.
+    pub fn closest_points(&self, other: &Segment) -> (Vec2, Vec2) {
+        if let Some(hit) = self.intersect(other) {
+            return (hit.point, hit.point);
+        }
+        // apart in 2D, the closest pair always has an endpoint in it
+        [
+            (self.a, other.closest_point(self.a)),
+            (self.b, other.closest_point(self.b)),
+            (self.closest_point(other.a), other.a),
+            (self.closest_point(other.b), other.b),
+        ]
+        .into_iter()
+        .min_by(|x, y| {
+            x.0.distance_squared(x.1)
+                .total_cmp(&y.0.distance_squared(y.1))
+        })
+        .unwrap()
+    }
+
+    /// Where this segment crosses `other`; the normal faces `self.a`.
+    pub fn intersect(&self, other: &Segment) -> Option<Hit> {
+        let r = self.b - self.a;
+        let s = other.b - other.a;
+        let denominator = r.perp_dot(s);
+        if denominator.abs() < EPSILON {
+            // parallel, or collinear which counts as a miss
+            return None;
+        }
+        let t = (other.a - self.a).perp_dot(s) / denominator;
+        let u = (other.a - self.a).perp_dot(r) / denominator;
+        if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let mut normal = s.perp().normalize_or_zero();
+        if normal.dot(r) > 0.0 {
+            normal = -normal;
+        }
+        Some(Hit {
+            t,
+            point: self.a + r * t,
+            normal,
+        })
+    }
+
+    /// Where this segment enters `b`. None if it misses or starts inside; starting on an
+    /// edge and moving in is a hit at `t` 0.
+    pub fn cast_aabb(&self, b: &Aabb) -> Option<Hit> {
+        let delta = self.b - self.a;
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = 1.0f32;
+        let mut normal = Vec2::ZERO;
+        for axis in 0..2 {
+            if delta[axis].abs() < EPSILON {
+                if self.a[axis] < b.min[axis] || self.a[axis] >= b.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1.0 / delta[axis];
+            let mut near = (b.min[axis] - self.a[axis]) * inverse;
+            let mut far = (b.max[axis] - self.a[axis]) * inverse;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            if near > t_enter {
+                t_enter = near;
+                normal = Vec2::ZERO;
+                normal[axis] = -delta[axis].signum();
+            }
+            t_exit = t_exit.min(far);
+        }
+        // starting inside puts every entry behind the start
+        if t_enter < 0.0 || t_enter > t_exit {
+            return None;
+        }
+        Some(Hit {
+            t: t_enter,
+            point: self.a + delta * t_enter,
+            normal,
+        })
+    }
+
+    /// The part of this segment inside `b`, edges included, as a range of t.
+    fn clip(&self, b: &Aabb) -> Option<(f32, f32)> {
+        let delta = self.b - self.a;
+        let (mut t_min, mut t_max) = (0.0f32, 1.0f32);
+        for axis in 0..2 {
+            if delta[axis].abs() < EPSILON {
+                if self.a[axis] < b.min[axis] || self.a[axis] > b.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1.0 / delta[axis];
+            let mut near = (b.min[axis] - self.a[axis]) * inverse;
+            let mut far = (b.max[axis] - self.a[axis]) * inverse;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+            t_min = t_min.max(near);
+            t_max = t_max.min(far);
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    /// Where this segment enters `c`. None if it misses or starts inside.
+    pub fn cast_circle(&self, c: &Circle) -> Option<Hit> {
+        let delta = self.b - self.a;
+        let to_start = self.a - c.center;
+        let a = delta.length_squared();
+        let half_b = to_start.dot(delta);
+        let c_term = to_start.length_squared() - c.radius * c.radius;
+        if a < EPSILON || c_term < 0.0 {
+            return None;
+        }
+        let discriminant = half_b * half_b - a * c_term;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let t = (-half_b - discriminant.sqrt()) / a;
+        if !(0.0..=1.0).contains(&t) {
+            return None;
+        }
+        let point = self.a + delta * t;
+        Some(Hit {
+            t,
+            point,
+            normal: (point - c.center).normalize_or_zero(),
+        })
+    }
+}
+
+/* ----------- contacts ------------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Aabb(Aabb),
+    Circle(Circle),
+    Capsule(Capsule),
+    /// Collides like a capsule with no radius, so not with other segments; use
+    /// `Segment::intersect` for those.
+    Segment(Segment),
+}
+
+impl From<Aabb> for Shape {
+    fn from(shape: Aabb) -> Self {
+        Shape::Aabb(shape)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(shape: Circle) -> Self {
+        Shape::Circle(shape)
+    }
+}
+
+impl From<Capsule> for Shape {
+    fn from(shape: Capsule) -> Self {
+        Shape::Capsule(shape)
+    }
+}
+
+impl From<Segment> for Shape {
+    fn from(shape: Segment) -> Self {
+        Shape::Segment(shape)
+    }
+}
+
+impl Shape {
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            Shape::Aabb(b) => *b,
+            Shape::Circle(c) => c.bounds(),
+            Shape::Capsule(c) => c.bounds(),
+            Shape::Segment(s) => s.bounds(),
+        }
+    }
+
+    pub fn translated(&self, by: Vec2) -> Self {
+        match *self {
+            Shape::Aabb(b) => Shape::Aabb(b.translated(by)),
+            Shape::Circle(c) => Shape::Circle(Circle::new(c.center + by, c.radius)),
+            Shape::Capsule(c) => Shape::Capsule(Capsule::new(c.a + by, c.b + by, c.radius)),
+            Shape::Segment(s) => Shape::Segment(Segment::new(s.a + by, s.b + by)),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        self.contact(other).is_some()
+    }
+
+    /// How to push this shape out of `other`, if they overlap.
+    pub fn contact(&self, other: &Shape) -> Option<Contact> {
+        match (self.rounded(), other.rounded()) {
+            (None, None) => {
+                let (Shape::Aabb(a), Shape::Aabb(b)) = (self, other) else {
+                    unreachable!()
+                };
+                aabb_aabb(a, b)
+            }
+            (Some(a), None) => {
+                let Shape::Aabb(b) = other else {
+                    unreachable!()
+                };
+                capsule_aabb(&a, b)
+            }
+            (None, Some(b)) => {
+                let Shape::Aabb(a) = self else { unreachable!() };
+                capsule_aabb(&b, a).map(Contact::flipped)
+            }
+            (Some(a), Some(b)) => {
+                let (pa, pb) = a.segment().closest_points(&b.segment());
+                round_contact(pa, pb, a.radius + b.radius, a.segment(), b.segment())
+            }
+        }
+    }
+
+    /// Everything but boxes as a capsule, circles being ones with no length.
+    fn rounded(&self) -> Option<Capsule> {
+        match *self {
+            Shape::Aabb(_) => None,
+            Shape::Circle(c) => Some(Capsule::new(c.center, c.center, c.radius)),
+            Shape::Capsule(c) => Some(c),
+            Shape::Segment(s) => Some(Capsule::new(s.a, s.b, 0.0)),
+        }
+    }
+}
+
+fn aabb_aabb(a: &Aabb, b: &Aabb) -> Option<Contact> {
+    let overlap = a.max.min(b.max) - a.min.max(b.min);
+    if overlap.x <= 0.0 || overlap.y <= 0.0 {
+        return None;
+    }
+    let away = a.center() - b.center();
+    Some(if overlap.x < overlap.y {
+        Contact {
+            normal: Vec2::new(if away.x < 0.0 { -1.0 } else { 1.0 }, 0.0),
+            depth: overlap.x,
+        }
+    } else {
+        Contact {
+            normal: Vec2::new(0.0, if away.y < 0.0 { -1.0 } else { 1.0 }),
+            depth: overlap.y,
+        }
+    })
+}
+
+/// Contact between two round shapes whose cores come closest at `pa` and `pb`.
+fn round_contact(pa: Vec2, pb: Vec2, radius: f32, a: Segment, b: Segment) -> Option<Contact> {
+    let away = pa - pb;
+    let distance = away.length();
+    if distance >= radius {
+        return None;
+    }
+    let normal = if distance > EPSILON {
+        away / distance
+    } else {
+        // cores touch or cross: push sideways off b, towards a's middle
+        let mut side = (b.b - b.a).perp().normalize_or_zero();
+        if side == Vec2::ZERO {
+            side = (a.b - a.a).perp().normalize_or(Vec2::Y);
+        }
+        let to_a = (a.a + a.b) * 0.5 - (b.a + b.b) * 0.5;
+        if side.dot(to_a) < 0.0 {
+            -side
+        } else {
+            side
+        }
+    };
+    Some(Contact {
+        normal,
+        depth: radius - distance,
+    })
+}
+
+fn capsule_aabb(c: &Capsule, b: &Aabb) -> Option<Contact> {
+    let segment = c.segment();
+    if let Some((t_min, t_max)) = segment.clip(b) {
+        // the core is in the box: out through whichever face the inside part reaches
+        // least far past, measured from the deeper of its two ends
+        let delta = segment.b - segment.a;
+        let ends = [segment.a + delta * t_min, segment.a + delta * t_max];
+        let faces = [
+            (Vec2::NEG_X, ends.map(|p| p.x - b.min.x)),
+            (Vec2::X, ends.map(|p| b.max.x - p.x)),
+            (Vec2::NEG_Y, ends.map(|p| p.y - b.min.y)),
+            (Vec2::Y, ends.map(|p| b.max.y - p.y)),
+        ];
+        let (normal, depth) = faces
+            .into_iter()
+            .map(|(normal, [a, b])| (normal, a.max(b)))
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+            .unwrap();
+        return Some(Contact {
+            normal,
+            depth: depth + c.radius,
+        });
+    }
+
+    // apart, the closest pair has a segment end or a box corner in it
+    let corners = [
+        b.min,
+        Vec2::new(b.max.x, b.min.y),
+        b.max,
+        Vec2::new(b.min.x, b.max.y),
+    ];
+    let (on_segment, on_box) = [c.a, c.b]
+        .into_iter()
+        .map(|p| (p, b.closest_point(p)))
+        .chain(corners.into_iter().map(|q| (segment.closest_point(q), q)))
+        .min_by(|x, y| {
+            x.0.distance_squared(x.1)
+                .total_cmp(&y.0.distance_squared(y.1))
+        })
+        .unwrap();
+    let away = on_segment - on_box;
+    let distance = away.length();
+    (distance < c.radius && distance > EPSILON).then(|| Contact {
+        normal: away / distance,
+        depth: c.radius - distance,
+    })
+}
+
+/* ----------- tile grids ----------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileSweep {
+    /// The box after moving.
+    pub aabb: Aabb,
+    /// How far it actually moved.
+    pub moved: Vec2,
+    /// The solid tile that stopped horizontal movement, if one did.
+    pub hit_x: Option<IVec2>,
+    pub hit_y: Option<IVec2>,
+}
+
+/// Moves `aabb` by `delta` through a grid of `tile_size` tiles, stopping flush against
+/// any tile `solid` says is solid. Horizontal movement goes first, then vertical, which
+/// lets boxes slide along walls and floors. A box that starts inside solid tiles can
+/// still move out of them.
+pub fn sweep_tiles(
+    aabb: Aabb,
+    delta: Vec2,
+    tile_size: Vec2,
+    solid: impl Fn(IVec2) -> bool,
+) -> TileSweep {
+    let (dx, hit_x) = sweep_axis(aabb, delta.x, 0, tile_size, &solid);
+    let moved_x = aabb.translated(Vec2::new(dx, 0.0));
+    let (dy, hit_y) = sweep_axis(moved_x, delta.y, 1, tile_size, &solid);
+    let moved = Vec2::new(dx, dy);
+    TileSweep {
+        aabb: aabb.translated(moved),
+        moved,
+        hit_x,
+        hit_y,
+    }
+}
+
+/// Distance `aabb` can move along `axis`, and the tile that blocked it.
+fn sweep_axis(
+    aabb: Aabb,
+    delta: f32,
+    axis: usize,
+    tile_size: Vec2,
+    solid: &impl Fn(IVec2) -> bool,
+) -> (f32, Option<IVec2>) {
+    if delta == 0.0 {
+        return (0.0, None);
+    }
+    let across = 1 - axis;
+    let size = tile_size[axis];
+    // tiles the box spans on the other axis; an edge on a tile boundary doesn't count
+    let first_row = (aabb.min[across] / tile_size[across]).floor() as i32;
+    let last_row = (aabb.max[across] / tile_size[across]).ceil() as i32 - 1;
+    let blocked = |column: i32| {
+        (first_row..=last_row).find_map(|row| {
+            let mut tile = IVec2::ZERO;
+            tile[axis] = column;
+            tile[across] = row;
+            solid(tile).then_some(tile)
+        })
+    };
+
+    // only tiles wholly ahead of the leading edge can block, so one the box already
+    // overlaps never traps it
+    if delta > 0.0 {
+        let edge = aabb.max[axis];
+        let first = (edge / size).ceil() as i32;
+        let last = ((edge + delta) / size).ceil() as i32 - 1;
+        for column in first..=last {
+            if let Some(tile) = blocked(column) {
+                return ((column as f32 * size - edge).max(0.0), Some(tile));
+            }
+        }
+    } else {
+        let edge = aabb.min[axis];
+        let first = (edge / size).floor() as i32 - 1;
+        let last = ((edge + delta) / size).floor() as i32;
+        for column in (last..=first).rev() {
+            if let Some(tile) = blocked(column) {
+                return (((column + 1) as f32 * size - edge).min(0.0), Some(tile));
+            }
+        }
+    }
+    (delta, None)
+}
+
+/* ----------- spatial hash --------------------------------------------- */
+
+/// Uniform grid for finding what might overlap an area without testing everything.
+/// Rebuild it each tick: `clear`, then `insert` everything that moves.
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<usize>>,
+    items: Vec<(T, Aabb)>,
+}
+
+impl<T: Copy> SpatialHash<T> {
+    /// Around the size of a typical object works well.
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(EPSILON),
+            cells: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Empties the grid but keeps its allocations.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn insert(&mut self, item: T, bounds: Aabb) {
+        let index = self.items.len();
+        self.items.push((item, bounds));
+        let (min, max) = self.cell_range(&bounds);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Items whose bounds overlap `area`, each once, in insertion order.
+    pub fn query(&self, area: Aabb) -> Vec<T> {
+        let mut found: Vec<usize> = Vec::new();
+        let (min, max) = self.cell_range(&area);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if let Some(cell) = self.cells.get(&IVec2::new(x, y)) {
+                    found.extend(cell.iter().filter(|&&i| self.items[i].1.overlaps(&area)));
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found.into_iter().map(|i| self.items[i].0).collect()
+    }
+
+    /// Items whose bounds contain `point`.
+    pub fn query_point(&self, point: Vec2) -> Vec<T> {
+        let cell = (point / self.cell_size).floor().as_ivec2();
+        self.cells.get(&cell).map_or(Vec::new(), |cell| {
+            cell.iter()
+                .filter(|&&i| self.items[i].1.contains(point))
+                .map(|&i| self.items[i].0)
+                .collect()
+        })
+    }
+
+    /// Every pair of items whose bounds overlap, each pair once.
+    pub fn pairs(&self) -> Vec<(T, T)> {
+        let mut pairs = Vec::new();
+        for (i, (item, bounds)) in self.items.iter().enumerate() {
+            let (min, max) = self.cell_range(bounds);
+            let mut others: Vec<usize> = Vec::new();
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    if let Some(cell) = self.cells.get(&IVec2::new(x, y)) {
+                        others.extend(cell.iter().filter(|&&j| j > i));
+                    }
+                }
+            }
+            others.sort_unstable();
+            others.dedup();
+            for j in others {
+                if bounds.overlaps(&self.items[j].1) {
+                    pairs.push((*item, self.items[j].0));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn cell_range(&self, bounds: &Aabb) -> (IVec2, IVec2) {
+        (
+            (bounds.min / self.cell_size).floor().as_ivec2(),
+            (bounds.max / self.cell_size).floor().as_ivec2(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flush_box_moving_in_hits_at_zero() {
+        let wall = Aabb::new(Vec2::new(10.0, 0.0), Vec2::new(20.0, 10.0));
+        let body = Aabb::new(Vec2::new(6.0, 2.0), Vec2::new(10.0, 6.0));
+        let hit = body.sweep(Vec2::new(3.0, 1.0), &wall).unwrap();
+        assert_eq!(hit.t, 0.0);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+        assert_eq!(hit.point.x, 10.0);
+
+        // sliding along it or backing off isn't a hit
+        assert!(body.sweep(Vec2::new(0.0, 3.0), &wall).is_none());
+        assert!(body.sweep(Vec2::new(-3.0, 1.0), &wall).is_none());
+    }
+
+    #[test]
+    fn sweep_hits_the_face_it_reaches_first() {
+        let wall = Aabb::new(Vec2::new(10.0, 0.0), Vec2::new(20.0, 10.0));
+        let body = Aabb::from_center(Vec2::new(0.0, 5.0), Vec2::splat(2.0));
+        let hit = body.sweep(Vec2::new(16.0, 0.0), &wall).unwrap();
+        assert_eq!(hit.t, 0.5);
+        assert_eq!(hit.normal, Vec2::NEG_X);
+        assert!(body.sweep(Vec2::new(7.0, 0.0), &wall).is_none());
+    }
+
+    #[test]
+    fn segment_starting_inside_is_not_a_cast_hit() {
+        let b = Aabb::new(Vec2::ZERO, Vec2::splat(10.0));
+        assert!(Segment::new(Vec2::splat(5.0), Vec2::new(20.0, 5.0))
+            .cast_aabb(&b)
+            .is_none());
+    }
+
+    #[test]
+    fn capsule_through_the_edge_of_a_box_goes_out_sideways() {
+        let capsule = Shape::from(Capsule::new(
+            Vec2::new(0.0, -5.0),
+            Vec2::new(2.0, 10.0),
+            0.5,
+        ));
+        let floor = Shape::from(Aabb::new(Vec2::ZERO, Vec2::new(100.0, 2.0)));
+        let contact = capsule.contact(&floor).unwrap();
+        assert_eq!(contact.normal, Vec2::NEG_X);
+        // the segment is at most x = 14/15 inside the box
+        assert!((contact.depth - (14.0 / 15.0 + 0.5)).abs() < 1e-4);
+
+        // and once pushed out, it's clear
+        let moved = capsule.translated(contact.normal * (contact.depth + 0.01));
+        assert!(moved.contact(&floor).is_none());
+    }
+
+    #[test]
+    fn circle_near_a_corner() {
+        let b = Shape::from(Aabb::new(Vec2::ZERO, Vec2::splat(10.0)));
+        let touching = Shape::from(Circle::new(Vec2::new(13.0, 14.0), 6.0));
+        let contact = touching.contact(&b).unwrap();
+        assert!((contact.normal - Vec2::new(0.6, 0.8)).length() < 1e-6);
+        assert!((contact.depth - 1.0).abs() < 1e-6);
+        assert!(Shape::from(Circle::new(Vec2::new(13.0, 14.0), 4.0))
+            .contact(&b)
+            .is_none());
+    }
+}
@@ -0,0 +1,48 @@
+//! High-DPI helpers.
+//!
+//! With `FLAG_WINDOW_HIGHDPI` raylib keeps its drawing coordinates (the "screen" size) in
+//! logical pixels and scales them onto a framebuffer (the "render" size) that is larger
+//! by the monitor's DPI scale. Everything in this crate works in logical pixels, which is
+//! also what egui calls points; only GL calls need the render size.
+
+use glam::{UVec2, Vec2};
+use raylib::prelude::*;
+
+/// Ask for a full resolution framebuffer on scaled displays.
+/// Call before `raylib::init().build()`, which adds its own flags on top.
+pub fn request_highdpi() {
+    unsafe { raylib::ffi::SetConfigFlags(ConfigFlags::FLAG_WINDOW_HIGHDPI as u32) };
+}
+
+/// Framebuffer pixels per logical pixel, i.e. egui's `native_pixels_per_point`.
+pub fn dpi_scale(rl: &RaylibHandle) -> f32 {
+    let render_width = rl.get_render_width();
+    let screen_width = rl.get_screen_width();
+    if render_width > 0 && screen_width > 0 {
+        render_width as f32 / screen_width as f32
+    } else {
+        rl.get_window_scale_dpi().x
+    }
+}
+
+/// Window size in logical pixels, what raylib draws in.
+pub fn screen_dims(rl: &RaylibHandle) -> UVec2 {
+    UVec2::new(rl.get_screen_width() as u32, rl.get_screen_height() as u32)
+}
+
+/// Framebuffer size in physical pixels, what GL viewports need.
+pub fn render_dims(rl: &RaylibHandle) -> UVec2 {
+    UVec2::new(rl.get_render_width() as u32, rl.get_render_height() as u32)
+}
+
+/// The mouse scale raylib applies on its own to turn cursor positions into logical
+/// pixels. Outside macOS a high-DPI window reports the cursor in framebuffer pixels and
+/// raylib scales it down by the DPI; anyone calling `set_mouse_scale` replaces that, so
+/// they need to fold it back in.
+pub fn cursor_scale(rl: &RaylibHandle) -> Vec2 {
+    if cfg!(target_os = "macos") || !rl.get_window_state().window_highdpi() {
+        return Vec2::ONE;
+    }
+    let dpi = rl.get_window_scale_dpi();
+    Vec2::new(1.0 / dpi.x, 1.0 / dpi.y)
+}
@@ -0,0 +1,486 @@
+//! A small ECS: generational entity ids, one sparse-set storage per component type,
+//! queries over one or two components and deferred commands.
+//!
+//! Systems are plain functions called from `step`. Anything that would change which
+//! entities exist while a query is running goes through [`Commands`], applied by
+//! [`World::flush`] at the end of the step:
+//! ```ignore
+//! pub fn step(rl: &mut RaylibHandle, rlt: &mut RaylibThread, state: &mut State) {
+//!     let commands = state.world.commands();
+//!     state.world.for_each2_mut(|entity, pos: &mut Position, life: &mut Lifetime| {
+//!         life.0 -= 1;
+//!         if life.0 == 0 {
+//!             commands.despawn(entity);
+//!         }
+//!     });
+//!     state.world.flush();
+//! }
+//! ```
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Index plus generation, so a stale id for a despawned entity never matches whatever
+/// reuses its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+/// Sparse set: `sparse` maps entity index to a slot in the packed `entities`/`components`.
+struct Storage<T> {
+    sparse: Vec<Option<u32>>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+}
+
+impl<T> Storage<T> {
+    fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn slot(&self, entity: Entity) -> Option<usize> {
+        let slot = (*self.sparse.get(entity.index as usize)?)? as usize;
+        (self.entities[slot] == entity).then_some(slot)
+    }
+
+    fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let index = entity.index as usize;
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, None);
+        }
+        match self.sparse[index] {
+            Some(slot) if self.entities[slot as usize] == entity => Some(std::mem::replace(
+                &mut self.components[slot as usize],
+                component,
+            )),
+            Some(_) => {
+                // left over from an older generation in this slot
+                self.remove_index(entity.index);
+                self.insert(entity, component)
+            }
+            None => {
+                self.sparse[index] = Some(self.entities.len() as u32);
+                self.entities.push(entity);
+                self.components.push(component);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.slot(entity)?;
+        self.remove_index(entity.index)
+    }
+
+    fn remove_index(&mut self, index: u32) -> Option<T> {
+        let slot = self.sparse.get_mut(index as usize)?.take()? as usize;
+        self.entities.swap_remove(slot);
+        let component = self.components.swap_remove(slot);
+        if let Some(moved) = self.entities.get(slot) {
+            self.sparse[moved.index as usize] = Some(slot as u32);
+        }
+        Some(component)
+    }
+}
+
+trait AnyStorage {
+    fn remove_index(&mut self, index: u32);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for Storage<T> {
+    fn remove_index(&mut self, index: u32) {
+        Storage::remove_index(self, index);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A set of components spawned together, implemented for tuples of up to six.
+pub trait Bundle {
+    fn insert_into(self, world: &mut World, entity: Entity);
+}
+
+macro_rules! impl_bundle {
+    ($($t:ident),*) => {
+        impl<$($t: 'static),*> Bundle for ($($t,)*) {
+            #[allow(non_snake_case)]
+            fn insert_into(self, world: &mut World, entity: Entity) {
+                let ($($t,)*) = self;
+                $(world.insert(entity, $t);)*
+            }
+        }
+    };
+}
+
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+/// Queue of world changes applied by `World::flush`. Cheap to clone, so systems can
+/// hold one while iterating the world.
+#[derive(Clone, Default)]
+pub struct Commands(Rc<RefCell<Vec<Command>>>);
+
+impl Commands {
+    pub fn spawn(&self, bundle: impl Bundle + 'static) {
+        self.add(move |world| {
+            world.spawn(bundle);
+        });
+    }
+
+    pub fn despawn(&self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+
+    pub fn insert<T: 'static>(&self, entity: Entity, component: T) {
+        self.add(move |world| {
+            world.insert(entity, component);
+        });
+    }
+
+    pub fn remove<T: 'static>(&self, entity: Entity) {
+        self.add(move |world| {
+            world.remove::<T>(entity);
+        });
+    }
+
+    /// Anything else that needs the whole world.
+    pub fn add(&self, command: impl FnOnce(&mut World) + 'static) {
+        self.0.borrow_mut().push(Box::new(command));
+    }
+}
+
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    commands: Commands,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        let entity = self.spawn_empty();
+        bundle.insert_into(self, entity);
+        entity
+    }
+
+    pub fn spawn_empty(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                (self.generations.len() - 1) as u32
+            }
+        };
+        self.alive[index as usize] = true;
+        Entity {
+            index,
+            generation: self.generations[index as usize],
+        }
+    }
+
+    /// Removes the entity and all its components. False if it was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_index(entity.index);
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false)
+            && self.generations[index] == entity.generation
+    }
+
+    /// Number of live entities.
+    pub fn len(&self) -> usize {
+        self.alive.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds or replaces a component, returning the old one. Ignored for dead entities.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .expect("storage type mismatch")
+            .insert(entity, component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity)
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        let storage = self.storage::<T>()?;
+        Some(&storage.components[storage.slot(entity)?])
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        let storage = self.storage_mut::<T>()?;
+        let slot = storage.slot(entity)?;
+        Some(&mut storage.components[slot])
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Every entity with an `A`.
+    pub fn query<A: 'static>(&self) -> impl Iterator<Item = (Entity, &A)> {
+        self.storage::<A>()
+            .into_iter()
+            .flat_map(|storage| storage.entities.iter().copied().zip(&storage.components))
+    }
+
+    pub fn query_mut<A: 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut A)> {
+        self.storage_mut::<A>().into_iter().flat_map(|storage| {
+            storage
+                .entities
+                .iter()
+                .copied()
+                .zip(storage.components.iter_mut())
+        })
+    }
+
+    /// Every entity with both an `A` and a `B`.
+    pub fn query2<A: 'static, B: 'static>(&self) -> impl Iterator<Item = (Entity, &A, &B)> {
+        let b = self.storage::<B>();
+        self.query::<A>().filter_map(move |(entity, a)| {
+            let b = b?;
+            Some((entity, a, &b.components[b.slot(entity)?]))
+        })
+    }
+
+    /// Mutable version of `query2`. A closure rather than an iterator, since both
+    /// storages have to be borrowed mutably at once.
+    pub fn for_each2_mut<A: 'static, B: 'static>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut A, &mut B),
+    ) {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "for_each2_mut needs two different component types"
+        );
+        // take B out of the map so A can be borrowed alongside it
+        let Some(mut b) = self.storages.remove(&TypeId::of::<B>()) else {
+            return;
+        };
+        if let Some(a) = self.storage_mut::<A>() {
+            let b = b
+                .as_any_mut()
+                .downcast_mut::<Storage<B>>()
+                .expect("storage type mismatch");
+            for (entity, a) in a.entities.iter().copied().zip(a.components.iter_mut()) {
+                if let Some(slot) = b.slot(entity) {
+                    f(entity, a, &mut b.components[slot]);
+                }
+            }
+        }
+        self.storages.insert(TypeId::of::<B>(), b);
+    }
+
+    /// A handle for queueing changes while the world is borrowed.
+    pub fn commands(&self) -> Commands {
+        self.commands.clone()
+    }
+
+    /// Applies queued commands, including any queued by the commands themselves.
+    /// Call once at the end of each step.
+    pub fn flush(&mut self) {
+        loop {
+            let queued = std::mem::take(&mut *self.commands.0.borrow_mut());
+            if queued.is_empty() {
+                break;
+            }
+            for command in queued {
+                command(self);
+            }
+        }
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Pos(i32);
+
+    #[derive(Debug, PartialEq)]
+    struct Vel(i32);
+
+    #[test]
+    fn spawn_and_despawn() {
+        let mut world = World::new();
+        let a = world.spawn((Pos(1), Vel(2)));
+        let b = world.spawn((Pos(3),));
+        assert_eq!(world.len(), 2);
+        assert_eq!(world.get::<Pos>(a), Some(&Pos(1)));
+        assert_eq!(world.get::<Vel>(a), Some(&Vel(2)));
+        assert!(!world.has::<Vel>(b));
+
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert!(!world.is_alive(a));
+        assert_eq!(world.get::<Pos>(a), None);
+        assert_eq!(world.len(), 1);
+        assert_eq!(world.get::<Pos>(b), Some(&Pos(3)));
+    }
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut world = World::new();
+        let old = world.spawn((Pos(1),));
+        world.despawn(old);
+        let new = world.spawn((Pos(2),));
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+        assert_eq!(world.get::<Pos>(old), None);
+        assert_eq!(world.insert(old, Pos(9)), None);
+        assert_eq!(world.get::<Pos>(new), Some(&Pos(2)));
+    }
+
+    #[test]
+    fn insert_replaces_and_returns_the_old_component() {
+        let mut world = World::new();
+        let a = world.spawn((Pos(1),));
+        assert_eq!(world.insert(a, Pos(2)), Some(Pos(1)));
+        assert_eq!(world.remove::<Pos>(a), Some(Pos(2)));
+        assert_eq!(world.remove::<Pos>(a), None);
+        assert!(world.is_alive(a));
+    }
+
+    #[test]
+    fn removing_from_the_middle_fixes_up_the_moved_entity() {
+        let mut world = World::new();
+        let entities: Vec<Entity> = (0..4).map(|i| world.spawn((Pos(i),))).collect();
+        // the last entity is swapped into the removed one's slot
+        assert_eq!(world.remove::<Pos>(entities[1]), Some(Pos(1)));
+        assert_eq!(world.get::<Pos>(entities[3]), Some(&Pos(3)));
+        world.despawn(entities[0]);
+        assert_eq!(world.get::<Pos>(entities[2]), Some(&Pos(2)));
+        assert_eq!(world.get::<Pos>(entities[3]), Some(&Pos(3)));
+
+        let mut left: Vec<i32> = world.query::<Pos>().map(|(_, pos)| pos.0).collect();
+        left.sort();
+        assert_eq!(left, [2, 3]);
+        for (entity, pos) in world.query::<Pos>() {
+            assert_eq!(entities[pos.0 as usize], entity);
+        }
+    }
+
+    #[test]
+    fn for_each2_mut_visits_entities_with_both() {
+        let mut world = World::new();
+        let moving = world.spawn((Pos(0), Vel(2)));
+        let still = world.spawn((Pos(5),));
+        let floating = world.spawn((Vel(7),));
+
+        let mut visited = Vec::new();
+        world.for_each2_mut(|entity, pos: &mut Pos, vel: &mut Vel| {
+            pos.0 += vel.0;
+            vel.0 = 0;
+            visited.push(entity);
+        });
+        assert_eq!(visited, [moving]);
+        assert_eq!(world.get::<Pos>(moving), Some(&Pos(2)));
+        assert_eq!(world.get::<Vel>(moving), Some(&Vel(0)));
+        assert_eq!(world.get::<Pos>(still), Some(&Pos(5)));
+        assert_eq!(world.get::<Vel>(floating), Some(&Vel(7)));
+        assert_eq!(world.query2::<Pos, Vel>().count(), 1);
+    }
+
+    #[test]
+    fn commands_wait_for_flush() {
+        let mut world = World::new();
+        let a = world.spawn((Pos(1),));
+        let commands = world.commands();
+        for (entity, _) in world.query::<Pos>() {
+            commands.despawn(entity);
+            commands.spawn((Pos(10),));
+        }
+        commands.add(|world| {
+            // queued while flushing, still applied by the same flush
+            world.commands().spawn((Vel(3),));
+        });
+        assert!(world.is_alive(a));
+        assert_eq!(world.len(), 1);
+
+        world.flush();
+        assert!(!world.is_alive(a));
+        let spawned: Vec<&Pos> = world.query::<Pos>().map(|(_, pos)| pos).collect();
+        assert_eq!(spawned, [&Pos(10)]);
+        assert_eq!(world.query::<Vel>().count(), 1);
+        assert!(world.commands.0.borrow().is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+//! Dev mode: the layers shown in an egui "Game" viewport with integer zoom, pan, a pixel
+//! grid and a color picker, docked between an inspector panel on the left, performance on
+//! the right and the log along the bottom. Each panel can be resized, and shown or hidden
+//! from the toolbar; the viewport takes whatever space they leave.
+//!
+//! Per frame, in the runner:
+//! ```ignore
+//! editor.prepare(&mut rl, &mut egui, &layers);
+//! egui.run(&mut rl, |ctx| editor.ui(ctx, &layers, &mut state));
+//! editor.map_mouse(&mut rl, &mut egui, &mut layers);
+//! let mut input = egui.input_filter();
+//! editor.filter_input(&mut input);
+//! /* step, then draw into the layers */
+//! if !editor.enabled { layers.composite(&mut d, fullscreen) }
+//! egui.paint(&mut d);
+//! ```
+
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+use egui::{
+    pos2, Color32, PointerButton, Pos2, Rect, Sense, Stroke, StrokeKind, TextureId, Vec2 as EVec2,
+};
+use glam::{UVec2, Vec2};
+use glow::HasContext;
+use raylib::prelude::*;
+
+use crate::egui_raylib::{EguiRaylib, InputFilter};
+use crate::inspect::Inspect;
+use crate::layers::Layers;
+
+const LOG_CAPACITY: usize = 500;
+const FRAME_HISTORY: usize = 240;
+const MAX_ZOOM: u32 = 32;
+// below this the grid lines would cover most of the image
+const MIN_GRID_ZOOM: u32 = 4;
+
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Appends a line to the editor's log panel (and stdout, so it isn't lost outside dev mode).
+pub fn log(message: impl Into<String>) {
+    let message = message.into();
+    println!("{}", message);
+    let mut log = LOG.lock().unwrap();
+    if log.len() == LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(message);
+}
+
+pub struct Editor {
+    pub enabled: bool,
+    pub show_inspector: bool,
+    pub show_log: bool,
+    pub show_performance: bool,
+    pub show_grid: bool,
+    /// `None` picks the largest integer zoom that fits the viewport.
+    pub zoom: Option<u32>,
+    pub pan: EVec2,
+    // the zoom actually used last frame, after fitting
+    current_zoom: u32,
+    // (GL texture id, egui id) for each layer, bottom first
+    textures: Vec<(u32, TextureId)>,
+    // where the canvas was shown last frame, in window pixels
+    canvas_rect: Option<Rect>,
+    canvas_hovered: bool,
+    // the wheel zoomed the viewport this frame, so the sketch shouldn't see it
+    wheel_used: bool,
+    hovered_pixel: Option<UVec2>,
+    hovered_color: Option<Color>,
+    frame_times: VecDeque<f32>,
+}
+
+impl Editor {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            show_inspector: true,
+            show_log: true,
+            show_performance: true,
+            show_grid: true,
+            zoom: None,
+            pan: EVec2::ZERO,
+            current_zoom: 1,
+            textures: Vec::new(),
+            canvas_rect: None,
+            canvas_hovered: false,
+            wheel_used: false,
+            hovered_pixel: None,
+            hovered_color: None,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+        }
+    }
+
+    /// Call before `egui.run`: handles the F1 toggle, records the frame time, registers
+    /// the layer textures with egui and reads back the pixel under the cursor.
+    pub fn prepare(&mut self, rl: &RaylibHandle, egui: &mut EguiRaylib, layers: &Layers) {
+        if rl.is_key_pressed(KeyboardKey::KEY_F1) {
+            self.enabled = !self.enabled;
+        }
+
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(rl.get_frame_time());
+
+        for (i, layer) in layers.iter().enumerate() {
+            let gl_id = layer.render_texture.texture.id;
+            let native = glow::NativeTexture(NonZeroU32::new(gl_id).expect("unloaded layer"));
+            match self.textures.get_mut(i) {
+                Some((id, _)) if *id == gl_id => {}
+                Some((id, texture_id)) => {
+                    egui.painter().replace_native_texture(*texture_id, native);
+                    *id = gl_id;
+                }
+                None => {
+                    let texture_id = egui.painter().register_native_texture(native);
+                    self.textures.push((gl_id, texture_id));
+                }
+            }
+        }
+
+        self.hovered_color = match (self.enabled, self.hovered_pixel) {
+            (true, Some(pixel)) => Some(read_pixel(egui, layers, pixel)),
+            _ => None,
+        };
+    }
+
+    /// The whole editor UI. Call inside `egui.run` when `enabled`.
+    pub fn ui(&mut self, ctx: &egui::Context, layers: &Layers, state: &mut impl Inspect) {
+        egui::TopBottomPanel::top("editor_toolbar").show(ctx, |ui| {
+            ui.horizontal(|ui| self.toolbar(ui, layers));
+        });
+        // panels claim space in the order they're added: log along the bottom, then the
+        // inspector and performance down either side, and the viewport gets the rest
+        egui::TopBottomPanel::bottom("editor_log")
+            .resizable(true)
+            .default_height(140.0)
+            .show_animated(ctx, self.show_log, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Log");
+                    if ui.small_button("Clear").clicked() {
+                        LOG.lock().unwrap().clear();
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in LOG.lock().unwrap().iter() {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+        egui::SidePanel::left("editor_inspector")
+            .resizable(true)
+            .default_width(280.0)
+            .show_animated(ctx, self.show_inspector, |ui| {
+                egui::ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .show(ui, |ui| state.inspect(ui, "State"));
+            });
+        egui::SidePanel::right("editor_performance")
+            .resizable(true)
+            .default_width(200.0)
+            .show_animated(ctx, self.show_performance, |ui| self.performance(ui));
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE.fill(Color32::from_gray(16)))
+            .show(ctx, |ui| self.viewport(ui, layers));
+    }
+
+    /// Points raylib's mouse (and egui's view of it) at the canvas while the editor is up,
+    /// and back at the whole window when it isn't. Call after `egui.run`.
+    pub fn map_mouse(&self, rl: &mut RaylibHandle, egui: &mut EguiRaylib, layers: &mut Layers) {
+        match self.canvas_rect.filter(|_| self.enabled) {
+            Some(rect) => layers.set_viewport(
+                rl,
+                Vec2::new(rect.min.x, rect.min.y),
+                Vec2::new(rect.width(), rect.height()),
+            ),
+            None => layers.reset_viewport(rl),
+        }
+        egui.set_mouse_mapping(layers.mouse_offset(), layers.mouse_scale());
+    }
+
+    /// In dev mode the sketch only gets the pointer while it's over the viewport, and
+    /// not the wheel while that zooms it. The viewport is itself an egui panel, so
+    /// egui's own claim is overridden here.
+    pub fn filter_input(&self, input: &mut InputFilter) {
+        if self.enabled {
+            input.pointer_captured = !self.canvas_hovered;
+            input.wheel_captured = self.wheel_used;
+        }
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui, layers: &Layers) {
+        ui.toggle_value(&mut self.show_inspector, "Inspector");
+        ui.toggle_value(&mut self.show_log, "Log");
+        ui.toggle_value(&mut self.show_performance, "Performance");
+        ui.separator();
+
+        let zoom = self.current_zoom;
+        if ui.button("-").clicked() {
+            self.zoom = Some(zoom.saturating_sub(1).max(1));
+        }
+        ui.label(match self.zoom {
+            Some(_) => format!("{}x", zoom),
+            None => format!("{}x (fit)", zoom),
+        });
+        if ui.button("+").clicked() {
+            self.zoom = Some((zoom + 1).min(MAX_ZOOM));
+        }
+        if ui.button("Fit").clicked() {
+            self.zoom = None;
+            self.pan = EVec2::ZERO;
+        }
+        ui.checkbox(&mut self.show_grid, "Grid");
+        ui.separator();
+
+        let dims = layers.canvas().dims();
+        ui.monospace(format!("{}x{}", dims.x, dims.y));
+        if let Some(pixel) = self.hovered_pixel {
+            ui.monospace(format!("({}, {})", pixel.x, pixel.y));
+        }
+        if let Some(color) = self.hovered_color {
+            let swatch = Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a);
+            let (rect, _) = ui.allocate_exact_size(EVec2::splat(14.0), Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, swatch);
+            ui.monospace(format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                color.r, color.g, color.b, color.a
+            ));
+        }
+    }
+
+    fn performance(&self, ui: &mut egui::Ui) {
+        ui.strong("Performance");
+        let count = self.frame_times.len().max(1) as f32;
+        let average = self.frame_times.iter().sum::<f32>() / count;
+        let worst = self.frame_times.iter().copied().fold(0.0, f32::max);
+        ui.monospace(format!("fps    {:6.1}", 1.0 / average.max(f32::EPSILON)));
+        ui.monospace(format!("avg ms {:6.2}", average * 1000.0));
+        ui.monospace(format!("max ms {:6.2}", worst * 1000.0));
+
+        // frame time sparkline, scaled so 33ms touches the top and 16.7ms is halfway
+        let (rect, _) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 60.0), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::from_gray(24));
+        painter.hline(
+            rect.x_range(),
+            rect.center().y,
+            Stroke::new(1.0, Color32::from_rgb(80, 120, 80)),
+        );
+        let step = rect.width() / (FRAME_HISTORY - 1) as f32;
+        let points: Vec<Pos2> = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(i, dt)| {
+                let t = (dt * 30.0).min(1.0);
+                pos2(
+                    rect.left() + i as f32 * step,
+                    rect.bottom() - t * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(1.0, Color32::LIGHT_GRAY),
+        ));
+    }
+
+    fn viewport(&mut self, ui: &mut egui::Ui, layers: &Layers) {
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        let dims = layers.canvas().dims();
+        let canvas_size = egui::vec2(dims.x as f32, dims.y as f32);
+
+        let fit = ((rect.width() / canvas_size.x).min(rect.height() / canvas_size.y) as u32)
+            .clamp(1, MAX_ZOOM);
+        let mut zoom = self.zoom.unwrap_or(fit);
+
+        self.wheel_used = false;
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = ui.input(|i| i.raw_scroll_delta.y);
+            self.wheel_used = scroll != 0.0;
+            let new_zoom = if scroll > 0.0 {
+                (zoom + 1).min(MAX_ZOOM)
+            } else if scroll < 0.0 {
+                zoom.saturating_sub(1).max(1)
+            } else {
+                zoom
+            };
+            if new_zoom != zoom {
+                // keep the canvas pixel under the pointer where it is
+                let min = canvas_min(rect, canvas_size, zoom, self.pan);
+                let canvas_pos = (pointer - min) / zoom as f32;
+                let new_min = pointer - canvas_pos * new_zoom as f32;
+                self.pan = new_min - canvas_min(rect, canvas_size, new_zoom, EVec2::ZERO);
+                zoom = new_zoom;
+                self.zoom = Some(zoom);
+            }
+        }
+        if response.dragged_by(PointerButton::Middle) {
+            self.pan += response.drag_delta();
+        }
+
+        let canvas_rect = Rect::from_min_size(
+            canvas_min(rect, canvas_size, zoom, self.pan),
+            canvas_size * zoom as f32,
+        );
+        let painter = ui.painter_at(rect);
+        // render textures are stored upside down
+        for (layer, (_, texture_id)) in layers.iter().zip(&self.textures) {
+            if layer.visible {
+                // leave out the padding of scrolling layers
+                let shown = layer.dims().as_vec2() / layer.texture_dims().as_vec2();
+                let uv = Rect::from_min_max(pos2(0.0, 1.0), pos2(shown.x, 1.0 - shown.y));
+                painter.image(*texture_id, canvas_rect, uv, Color32::WHITE);
+            }
+        }
+
+        if self.show_grid && zoom >= MIN_GRID_ZOOM {
+            let stroke = Stroke::new(1.0, Color32::from_black_alpha(80));
+            for x in 0..=dims.x {
+                let x = canvas_rect.left() + (x * zoom) as f32;
+                painter.vline(x, canvas_rect.y_range(), stroke);
+            }
+            for y in 0..=dims.y {
+                let y = canvas_rect.top() + (y * zoom) as f32;
+                painter.hline(canvas_rect.x_range(), y, stroke);
+            }
+        }
+
+        self.hovered_pixel = response
+            .hover_pos()
+            .filter(|pos| canvas_rect.contains(*pos))
+            .map(|pos| {
+                let pixel = (pos - canvas_rect.min) / zoom as f32;
+                UVec2::new(pixel.x as u32, pixel.y as u32).min(dims - 1)
+            });
+        if let Some(pixel) = self.hovered_pixel {
+            let min = canvas_rect.min + egui::vec2(pixel.x as f32, pixel.y as f32) * zoom as f32;
+            painter.rect_stroke(
+                Rect::from_min_size(min, EVec2::splat(zoom as f32)),
+                0.0,
+                Stroke::new(1.0, Color32::WHITE),
+                StrokeKind::Outside,
+            );
+        }
+
+        self.current_zoom = zoom;
+        self.canvas_rect = Some(canvas_rect);
+        self.canvas_hovered = response.hovered();
+    }
+}
+
+/// Top left of the canvas, centred in `rect` then panned, snapped to whole pixels.
+fn canvas_min(rect: Rect, canvas_size: EVec2, zoom: u32, pan: EVec2) -> Pos2 {
+    (rect.center() - canvas_size * zoom as f32 / 2.0 + pan).round()
+}
+
+/// Reads one pixel of the canvas layer as it was at the end of last frame.
+fn read_pixel(egui: &mut EguiRaylib, layers: &Layers, pixel: UVec2) -> Color {
+    let layer = layers.canvas();
+    let framebuffer = glow::NativeFramebuffer(
+        NonZeroU32::new(layer.render_texture.id).expect("layer without a framebuffer"),
+    );
+    // GL rows start at the bottom
+    let y = layer.texture_dims().y - 1 - pixel.y;
+    let mut rgba = [0u8; 4];
+    let gl = egui.painter().gl();
+    unsafe {
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(framebuffer));
+        gl.read_pixels(
+            pixel.x as i32,
+            y as i32,
+            1,
+            1,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(Some(&mut rgba)),
+        );
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+    }
+    Color::new(rgba[0], rgba[1], rgba[2], rgba[3])
+}
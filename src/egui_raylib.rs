@@ -0,0 +1,503 @@
+//! egui on top of raylib: input translation, platform output and painting via egui_glow.
+//!
+//! Per frame:
+//! ```ignore
+//! egui.run(&mut rl, |ctx| sketch::egui_ui(ctx, &mut state));
+//! let mut d = rl.begin_drawing(&rlt);
+//! /* raylib drawing */
+//! egui.paint(&mut d);
+//! ```
+
+use std::{ffi::CString, sync::Arc};
+
+use egui::{
+    CursorIcon, Event, Key, Modifiers, MouseWheelUnit, OutputCommand, PointerButton, Pos2,
+    RawInput, Rect, TexturesDelta,
+};
+use egui_glow::Painter;
+use glam::Vec2;
+use glow::HasContext;
+use raylib::prelude::*;
+
+use crate::display;
+
+const KEY_MAP: &[(KeyboardKey, Key)] = &[
+    (KeyboardKey::KEY_DOWN, Key::ArrowDown),
+    (KeyboardKey::KEY_LEFT, Key::ArrowLeft),
+    (KeyboardKey::KEY_RIGHT, Key::ArrowRight),
+    (KeyboardKey::KEY_UP, Key::ArrowUp),
+    (KeyboardKey::KEY_ESCAPE, Key::Escape),
+    (KeyboardKey::KEY_TAB, Key::Tab),
+    (KeyboardKey::KEY_BACKSPACE, Key::Backspace),
+    (KeyboardKey::KEY_ENTER, Key::Enter),
+    (KeyboardKey::KEY_KP_ENTER, Key::Enter),
+    (KeyboardKey::KEY_SPACE, Key::Space),
+    (KeyboardKey::KEY_INSERT, Key::Insert),
+    (KeyboardKey::KEY_DELETE, Key::Delete),
+    (KeyboardKey::KEY_HOME, Key::Home),
+    (KeyboardKey::KEY_END, Key::End),
+    (KeyboardKey::KEY_PAGE_UP, Key::PageUp),
+    (KeyboardKey::KEY_PAGE_DOWN, Key::PageDown),
+    (KeyboardKey::KEY_APOSTROPHE, Key::Quote),
+    (KeyboardKey::KEY_COMMA, Key::Comma),
+    (KeyboardKey::KEY_MINUS, Key::Minus),
+    (KeyboardKey::KEY_KP_SUBTRACT, Key::Minus),
+    (KeyboardKey::KEY_PERIOD, Key::Period),
+    (KeyboardKey::KEY_KP_DECIMAL, Key::Period),
+    (KeyboardKey::KEY_SLASH, Key::Slash),
+    (KeyboardKey::KEY_KP_DIVIDE, Key::Slash),
+    (KeyboardKey::KEY_SEMICOLON, Key::Semicolon),
+    (KeyboardKey::KEY_EQUAL, Key::Equals),
+    (KeyboardKey::KEY_KP_EQUAL, Key::Equals),
+    (KeyboardKey::KEY_KP_ADD, Key::Plus),
+    (KeyboardKey::KEY_LEFT_BRACKET, Key::OpenBracket),
+    (KeyboardKey::KEY_RIGHT_BRACKET, Key::CloseBracket),
+    (KeyboardKey::KEY_BACKSLASH, Key::Backslash),
+    (KeyboardKey::KEY_GRAVE, Key::Backtick),
+    (KeyboardKey::KEY_ZERO, Key::Num0),
+    (KeyboardKey::KEY_ONE, Key::Num1),
+    (KeyboardKey::KEY_TWO, Key::Num2),
+    (KeyboardKey::KEY_THREE, Key::Num3),
+    (KeyboardKey::KEY_FOUR, Key::Num4),
+    (KeyboardKey::KEY_FIVE, Key::Num5),
+    (KeyboardKey::KEY_SIX, Key::Num6),
+    (KeyboardKey::KEY_SEVEN, Key::Num7),
+    (KeyboardKey::KEY_EIGHT, Key::Num8),
+    (KeyboardKey::KEY_NINE, Key::Num9),
+    (KeyboardKey::KEY_KP_0, Key::Num0),
+    (KeyboardKey::KEY_KP_1, Key::Num1),
+    (KeyboardKey::KEY_KP_2, Key::Num2),
+    (KeyboardKey::KEY_KP_3, Key::Num3),
+    (KeyboardKey::KEY_KP_4, Key::Num4),
+    (KeyboardKey::KEY_KP_5, Key::Num5),
+    (KeyboardKey::KEY_KP_6, Key::Num6),
+    (KeyboardKey::KEY_KP_7, Key::Num7),
+    (KeyboardKey::KEY_KP_8, Key::Num8),
+    (KeyboardKey::KEY_KP_9, Key::Num9),
+    (KeyboardKey::KEY_A, Key::A),
+    (KeyboardKey::KEY_B, Key::B),
+    (KeyboardKey::KEY_C, Key::C),
+    (KeyboardKey::KEY_D, Key::D),
+    (KeyboardKey::KEY_E, Key::E),
+    (KeyboardKey::KEY_F, Key::F),
+    (KeyboardKey::KEY_G, Key::G),
+    (KeyboardKey::KEY_H, Key::H),
+    (KeyboardKey::KEY_I, Key::I),
+    (KeyboardKey::KEY_J, Key::J),
+    (KeyboardKey::KEY_K, Key::K),
+    (KeyboardKey::KEY_L, Key::L),
+    (KeyboardKey::KEY_M, Key::M),
+    (KeyboardKey::KEY_N, Key::N),
+    (KeyboardKey::KEY_O, Key::O),
+    (KeyboardKey::KEY_P, Key::P),
+    (KeyboardKey::KEY_Q, Key::Q),
+    (KeyboardKey::KEY_R, Key::R),
+    (KeyboardKey::KEY_S, Key::S),
+    (KeyboardKey::KEY_T, Key::T),
+    (KeyboardKey::KEY_U, Key::U),
+    (KeyboardKey::KEY_V, Key::V),
+    (KeyboardKey::KEY_W, Key::W),
+    (KeyboardKey::KEY_X, Key::X),
+    (KeyboardKey::KEY_Y, Key::Y),
+    (KeyboardKey::KEY_Z, Key::Z),
+    (KeyboardKey::KEY_F1, Key::F1),
+    (KeyboardKey::KEY_F2, Key::F2),
+    (KeyboardKey::KEY_F3, Key::F3),
+    (KeyboardKey::KEY_F4, Key::F4),
+    (KeyboardKey::KEY_F5, Key::F5),
+    (KeyboardKey::KEY_F6, Key::F6),
+    (KeyboardKey::KEY_F7, Key::F7),
+    (KeyboardKey::KEY_F8, Key::F8),
+    (KeyboardKey::KEY_F9, Key::F9),
+    (KeyboardKey::KEY_F10, Key::F10),
+    (KeyboardKey::KEY_F11, Key::F11),
+    (KeyboardKey::KEY_F12, Key::F12),
+];
+
+const BUTTON_MAP: &[(MouseButton, PointerButton)] = &[
+    (MouseButton::MOUSE_BUTTON_LEFT, PointerButton::Primary),
+    (MouseButton::MOUSE_BUTTON_RIGHT, PointerButton::Secondary),
+    (MouseButton::MOUSE_BUTTON_MIDDLE, PointerButton::Middle),
+    (MouseButton::MOUSE_BUTTON_BACK, PointerButton::Extra1),
+    (MouseButton::MOUSE_BUTTON_FORWARD, PointerButton::Extra2),
+];
+
+pub struct EguiRaylib {
+    painter: Painter,
+    ctx: egui::Context,
+    focused: bool,
+    cursor_icon: Option<CursorIcon>,
+    // whatever the runner passed to raylib's set_mouse_offset/set_mouse_scale,
+    // undone for egui's pointer
+    mouse_offset: Vec2,
+    mouse_scale: Vec2,
+    // output of the last `run`, consumed by `paint`
+    shapes: Vec<egui::epaint::ClippedShape>,
+    textures_delta: TexturesDelta,
+    pixels_per_point: f32,
+}
+
+impl EguiRaylib {
+    /// Must be called after the raylib window exists, since it loads GL through GLFW.
+    pub fn new(_rl: &mut RaylibHandle) -> Self {
+        unsafe {
+            // This call is what raylib itself does internally on desktop.
+            // We repeat it so that *glow* can see the same GL symbols later on.
+            raylib::ffi::rlLoadExtensions(Some(raylib::ffi::glfwGetProcAddress));
+        }
+
+        let gl = unsafe {
+            glow::Context::from_loader_function(|s| {
+                let cs = CString::new(s).unwrap();
+                raylib::ffi::glfwGetProcAddress(cs.as_ptr()) as *const _
+            })
+        };
+        let painter = Painter::new(Arc::new(gl), "", None, false)
+            .expect("could not create egui_glow painter");
+
+        Self {
+            painter,
+            ctx: egui::Context::default(),
+            focused: true,
+            cursor_icon: None,
+            mouse_offset: Vec2::ZERO,
+            mouse_scale: Vec2::ONE,
+            shapes: Vec::new(),
+            textures_delta: TexturesDelta::default(),
+            pixels_per_point: 1.0,
+        }
+    }
+
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    pub fn painter(&mut self) -> &mut Painter {
+        &mut self.painter
+    }
+
+    /// Tell the backend about the offset and scale handed to raylib, so egui keeps
+    /// getting window-space coordinates while the sketch gets canvas-space ones.
+    pub fn set_mouse_mapping(&mut self, mouse_offset: Vec2, mouse_scale: Vec2) {
+        self.mouse_offset = mouse_offset;
+        self.mouse_scale = mouse_scale;
+    }
+
+    /// Translates this frame's raylib input into egui's `RawInput`.
+    pub fn gather_input(&mut self, rl: &mut RaylibHandle) -> RawInput {
+        // raylib's logical pixels are egui's points, the framebuffer is dpi_scale times larger
+        let screen_dims = display::screen_dims(rl);
+        let mut raw = RawInput {
+            screen_rect: Some(Rect::from_min_size(
+                Pos2::ZERO,
+                egui::vec2(screen_dims.x as f32, screen_dims.y as f32),
+            )),
+            max_texture_side: Some(self.painter.max_texture_side()),
+            time: Some(rl.get_time()),
+            predicted_dt: rl.get_frame_time(),
+            ..Default::default()
+        };
+
+        raw.viewports
+            .entry(raw.viewport_id)
+            .or_default()
+            .native_pixels_per_point = Some(display::dpi_scale(rl));
+
+        let focused = rl.is_window_focused();
+        if focused != self.focused {
+            raw.events.push(Event::WindowFocused(focused));
+            self.focused = focused;
+        }
+        raw.focused = focused;
+
+        let modifiers = read_modifiers(rl);
+        raw.modifiers = modifiers;
+
+        /* pointer ---------------------------------------------------- */
+        let mp = rl.get_mouse_position();
+        let pos = Pos2::new(
+            mp.x / self.mouse_scale.x - self.mouse_offset.x,
+            mp.y / self.mouse_scale.y - self.mouse_offset.y,
+        );
+        if rl.is_cursor_on_screen() {
+            raw.events.push(Event::PointerMoved(pos));
+        } else {
+            raw.events.push(Event::PointerGone);
+        }
+        for &(rl_button, button) in BUTTON_MAP {
+            if rl.is_mouse_button_pressed(rl_button) {
+                raw.events.push(Event::PointerButton {
+                    pos,
+                    button,
+                    pressed: true,
+                    modifiers,
+                });
+            }
+            if rl.is_mouse_button_released(rl_button) {
+                raw.events.push(Event::PointerButton {
+                    pos,
+                    button,
+                    pressed: false,
+                    modifiers,
+                });
+            }
+        }
+
+        let wheel = rl.get_mouse_wheel_move_v();
+        if wheel.x != 0.0 || wheel.y != 0.0 {
+            raw.events.push(Event::MouseWheel {
+                unit: MouseWheelUnit::Line,
+                delta: egui::vec2(wheel.x, wheel.y),
+                modifiers,
+            });
+        }
+
+        /* keyboard --------------------------------------------------- */
+        for &(rl_key, key) in KEY_MAP {
+            let pressed = rl.is_key_pressed(rl_key);
+            let repeat = rl.is_key_pressed_repeat(rl_key);
+            if pressed || repeat {
+                // clipboard shortcuts become their own events, like egui-winit does
+                if modifiers.command && !repeat {
+                    match key {
+                        Key::C => {
+                            raw.events.push(Event::Copy);
+                            continue;
+                        }
+                        Key::X => {
+                            raw.events.push(Event::Cut);
+                            continue;
+                        }
+                        Key::V => {
+                            if let Ok(text) = rl.get_clipboard_text() {
+                                if !text.is_empty() {
+                                    raw.events.push(Event::Paste(text));
+                                }
+                            }
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+                raw.events.push(Event::Key {
+                    key,
+                    physical_key: Some(key),
+                    pressed: true,
+                    repeat,
+                    modifiers,
+                });
+            }
+            if rl.is_key_released(rl_key) {
+                raw.events.push(Event::Key {
+                    key,
+                    physical_key: Some(key),
+                    pressed: false,
+                    repeat: false,
+                    modifiers,
+                });
+            }
+        }
+
+        // text entry comes from the char queue so it respects keyboard layout and shift
+        let mut text = String::new();
+        while let Some(c) = rl.get_char_pressed() {
+            if !c.is_control() {
+                text.push(c);
+            }
+        }
+        if !text.is_empty() && !(modifiers.ctrl || modifiers.mac_cmd) {
+            raw.events.push(Event::Text(text));
+        }
+
+        raw
+    }
+
+    /// Gathers input, runs the ui closure and applies the platform output.
+    /// Shapes are kept until `paint`.
+    pub fn run(&mut self, rl: &mut RaylibHandle, ui: impl FnMut(&egui::Context)) {
+        let raw = self.gather_input(rl);
+        let out = self.ctx.run(raw, ui);
+
+        self.apply_platform_output(rl, out.platform_output);
+        self.shapes = out.shapes;
+        self.textures_delta.append(out.textures_delta);
+        self.pixels_per_point = out.pixels_per_point;
+    }
+
+    fn apply_platform_output(&mut self, rl: &mut RaylibHandle, output: egui::PlatformOutput) {
+        for command in output.commands {
+            match command {
+                OutputCommand::CopyText(text) => {
+                    if rl.set_clipboard_text(&text).is_err() {
+                        println!("egui: could not copy text containing a nul byte");
+                    }
+                }
+                OutputCommand::CopyImage(_) => {
+                    println!("egui: copying images to the clipboard is not supported");
+                }
+                OutputCommand::OpenUrl(open_url) => {
+                    println!("egui: open url {}", open_url.url);
+                }
+            }
+        }
+
+        if self.cursor_icon != Some(output.cursor_icon) {
+            if output.cursor_icon == CursorIcon::None {
+                rl.hide_cursor();
+            } else {
+                if self.cursor_icon == Some(CursorIcon::None) {
+                    rl.show_cursor();
+                }
+                rl.set_mouse_cursor(to_mouse_cursor(output.cursor_icon));
+            }
+            self.cursor_icon = Some(output.cursor_icon);
+        }
+    }
+
+    /// Paints the last `run` on top of whatever raylib has drawn so far this frame.
+    pub fn paint(&mut self, d: &mut RaylibDrawHandle) {
+        // flush raylib's batch first, otherwise it lands on top of egui at EndDrawing
+        unsafe { raylib::ffi::rlDrawRenderBatchActive() };
+
+        // the GL viewport covers the framebuffer, which is larger than the screen on HiDPI
+        let render_dims = display::render_dims(d);
+        let dims = [render_dims.x, render_dims.y];
+        let shapes = std::mem::take(&mut self.shapes);
+        let clipped = self.ctx.tessellate(shapes, self.pixels_per_point);
+        let textures_delta = std::mem::take(&mut self.textures_delta);
+
+        self.painter.paint_and_update_textures(
+            dims,
+            self.pixels_per_point,
+            &clipped,
+            &textures_delta,
+        );
+
+        // egui leaves scissoring on and its premultiplied blend func set; raylib only
+        // re-applies state it thinks changed, so put back what it expects.
+        unsafe {
+            let gl = self.painter.gl();
+            gl.disable(glow::SCISSOR_TEST);
+            gl.blend_equation(glow::FUNC_ADD);
+            gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+        }
+    }
+
+    pub fn wants_pointer_input(&self) -> bool {
+        self.ctx.wants_pointer_input()
+    }
+
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.ctx.wants_keyboard_input()
+    }
+
+    /// What egui claimed during the last `run`.
+    pub fn input_filter(&self) -> InputFilter {
+        InputFilter {
+            pointer_captured: self.ctx.wants_pointer_input() || self.ctx.is_pointer_over_area(),
+            keyboard_captured: self.ctx.wants_keyboard_input(),
+            wheel_captured: false,
+        }
+    }
+}
+
+/// Raylib input queries that go quiet while egui is using the mouse or keyboard, so
+/// clicking a window or typing in a text field doesn't also drive the sketch.
+/// `InputFilter::default()` lets everything through, for runners without egui.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputFilter {
+    pub pointer_captured: bool,
+    pub keyboard_captured: bool,
+    /// Just the mouse wheel, e.g. while the editor zooms with it.
+    pub wheel_captured: bool,
+}
+
+impl InputFilter {
+    pub fn is_key_pressed(&self, rl: &RaylibHandle, key: KeyboardKey) -> bool {
+        !self.keyboard_captured && rl.is_key_pressed(key)
+    }
+
+    pub fn is_key_pressed_repeat(&self, rl: &RaylibHandle, key: KeyboardKey) -> bool {
+        !self.keyboard_captured && rl.is_key_pressed_repeat(key)
+    }
+
+    pub fn is_key_down(&self, rl: &RaylibHandle, key: KeyboardKey) -> bool {
+        !self.keyboard_captured && rl.is_key_down(key)
+    }
+
+    pub fn is_key_released(&self, rl: &RaylibHandle, key: KeyboardKey) -> bool {
+        !self.keyboard_captured && rl.is_key_released(key)
+    }
+
+    pub fn is_mouse_button_pressed(&self, rl: &RaylibHandle, button: MouseButton) -> bool {
+        !self.pointer_captured && rl.is_mouse_button_pressed(button)
+    }
+
+    pub fn is_mouse_button_down(&self, rl: &RaylibHandle, button: MouseButton) -> bool {
+        !self.pointer_captured && rl.is_mouse_button_down(button)
+    }
+
+    pub fn is_mouse_button_released(&self, rl: &RaylibHandle, button: MouseButton) -> bool {
+        !self.pointer_captured && rl.is_mouse_button_released(button)
+    }
+
+    pub fn get_mouse_wheel_move(&self, rl: &RaylibHandle) -> f32 {
+        if self.pointer_captured || self.wheel_captured {
+            0.0
+        } else {
+            rl.get_mouse_wheel_move()
+        }
+    }
+}
+
+impl Drop for EguiRaylib {
+    fn drop(&mut self) {
+        self.painter.destroy();
+    }
+}
+
+fn read_modifiers(rl: &RaylibHandle) -> Modifiers {
+    let down = |a, b| rl.is_key_down(a) || rl.is_key_down(b);
+    let alt = down(KeyboardKey::KEY_LEFT_ALT, KeyboardKey::KEY_RIGHT_ALT);
+    let ctrl = down(
+        KeyboardKey::KEY_LEFT_CONTROL,
+        KeyboardKey::KEY_RIGHT_CONTROL,
+    );
+    let shift = down(KeyboardKey::KEY_LEFT_SHIFT, KeyboardKey::KEY_RIGHT_SHIFT);
+    let super_key = down(KeyboardKey::KEY_LEFT_SUPER, KeyboardKey::KEY_RIGHT_SUPER);
+    let mac = cfg!(target_os = "macos");
+    Modifiers {
+        alt,
+        ctrl,
+        shift,
+        mac_cmd: mac && super_key,
+        command: if mac { super_key } else { ctrl },
+    }
+}
+
+fn to_mouse_cursor(icon: CursorIcon) -> MouseCursor {
+    match icon {
+        CursorIcon::Text | CursorIcon::VerticalText => MouseCursor::MOUSE_CURSOR_IBEAM,
+        CursorIcon::PointingHand => MouseCursor::MOUSE_CURSOR_POINTING_HAND,
+        CursorIcon::Crosshair | CursorIcon::Cell => MouseCursor::MOUSE_CURSOR_CROSSHAIR,
+        CursorIcon::ResizeHorizontal
+        | CursorIcon::ResizeEast
+        | CursorIcon::ResizeWest
+        | CursorIcon::ResizeColumn => MouseCursor::MOUSE_CURSOR_RESIZE_EW,
+        CursorIcon::ResizeVertical
+        | CursorIcon::ResizeNorth
+        | CursorIcon::ResizeSouth
+        | CursorIcon::ResizeRow => MouseCursor::MOUSE_CURSOR_RESIZE_NS,
+        CursorIcon::ResizeNwSe | CursorIcon::ResizeNorthWest | CursorIcon::ResizeSouthEast => {
+            MouseCursor::MOUSE_CURSOR_RESIZE_NWSE
+        }
+        CursorIcon::ResizeNeSw | CursorIcon::ResizeNorthEast | CursorIcon::ResizeSouthWest => {
+            MouseCursor::MOUSE_CURSOR_RESIZE_NESW
+        }
+        CursorIcon::Move | CursorIcon::AllScroll | CursorIcon::Grab | CursorIcon::Grabbing => {
+            MouseCursor::MOUSE_CURSOR_RESIZE_ALL
+        }
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => MouseCursor::MOUSE_CURSOR_NOT_ALLOWED,
+        _ => MouseCursor::MOUSE_CURSOR_DEFAULT,
+    }
+}
@@ -0,0 +1,576 @@
+//! Bitmap fonts for text that stays crisp on a low-res canvas: BMFont (.fnt, text or
+//! binary), fixed-grid PNG fonts and TTFs rasterised once at their pixel size.
+//!
+//! All positions and sizes are whole canvas pixels, and glyphs are drawn unscaled.
+//! ```ignore
+//! let font = BitmapFont::load_bmfont(&mut rl, &rlt, "assets/m5x7.fnt")?;
+//! font.draw_text(d, "Low Res Sketch!", IVec2::new(120, 12), Align::Center, Color::WHITE);
+//! font.draw_text_box(d, &dialogue, IVec2::new(8, 120), 224, Align::Left, Color::WHITE);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use glam::{IVec2, UVec2};
+use raylib::prelude::*;
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    Texture(raylib::core::error::Error),
+    /// Something the loader can't make sense of.
+    Invalid(String),
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(e) => write!(f, "could not read font: {}", e),
+            FontError::Texture(e) => write!(f, "could not load font image: {}", e),
+            FontError::Invalid(msg) => write!(f, "bad font file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+    fn from(e: std::io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+impl From<raylib::core::error::Error> for FontError {
+    fn from(e: raylib::core::error::Error) -> Self {
+        FontError::Texture(e)
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> FontError {
+    FontError::Invalid(msg.into())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+    pub page: usize,
+    /// Where the glyph is on its page.
+    pub source: Rectangle,
+    /// From the pen position to the glyph's top left.
+    pub offset: IVec2,
+    /// How far the pen moves after the glyph.
+    pub advance: i32,
+}
+
+/// Everything from a .fnt file except the page images.
+#[derive(Debug, Clone, Default)]
+pub struct FontData {
+    pub line_height: i32,
+    /// Distance from the top of a line to the baseline.
+    pub base: i32,
+    /// Page image paths as written in the file, relative to it.
+    pub pages: Vec<String>,
+    pub glyphs: HashMap<char, Glyph>,
+    pub kerning: HashMap<(char, char), i32>,
+}
+
+impl FontData {
+    /// Parses either flavour of .fnt; binary files start with "BMF".
+    pub fn parse_bmfont(bytes: &[u8]) -> Result<Self, FontError> {
+        if bytes.starts_with(b"BMF") {
+            Self::parse_binary(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes).map_err(|_| invalid("not UTF-8 text"))?;
+            Self::parse_text(text)
+        }
+    }
+
+    fn parse_text(text: &str) -> Result<Self, FontError> {
+        let mut data = FontData::default();
+        let mut pages = Vec::new();
+        for line in text.lines() {
+            let (tag, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let fields = text_fields(rest);
+            let int = |key: &str| -> Result<i32, FontError> {
+                fields
+                    .get(key)
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| invalid(format!("{} line without {}", tag, key)))
+            };
+            match tag {
+                "common" => {
+                    data.line_height = int("lineHeight")?;
+                    data.base = int("base")?;
+                }
+                "page" => {
+                    let file = fields
+                        .get("file")
+                        .ok_or_else(|| invalid("page line without file"))?;
+                    pages.push((int("id")?, file.to_string()));
+                }
+                "char" => {
+                    let Some(c) = char::from_u32(int("id")? as u32) else {
+                        continue;
+                    };
+                    let glyph = Glyph {
+                        page: int("page")? as usize,
+                        source: Rectangle::new(
+                            int("x")? as f32,
+                            int("y")? as f32,
+                            int("width")? as f32,
+                            int("height")? as f32,
+                        ),
+                        offset: IVec2::new(int("xoffset")?, int("yoffset")?),
+                        advance: int("xadvance")?,
+                    };
+                    data.glyphs.insert(c, glyph);
+                }
+                "kerning" => {
+                    let first = char::from_u32(int("first")? as u32);
+                    let second = char::from_u32(int("second")? as u32);
+                    if let (Some(first), Some(second)) = (first, second) {
+                        data.kerning.insert((first, second), int("amount")?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        pages.sort_by_key(|(id, _)| *id);
+        data.pages = pages.into_iter().map(|(_, file)| file).collect();
+        Ok(data)
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<Self, FontError> {
+        if bytes.get(3) != Some(&3) {
+            return Err(invalid("only version 3 of the binary format is supported"));
+        }
+        let u16_at = |b: &[u8], i: usize| u16::from_le_bytes([b[i], b[i + 1]]);
+        let i16_at = |b: &[u8], i: usize| i16::from_le_bytes([b[i], b[i + 1]]);
+        let u32_at = |b: &[u8], i: usize| u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+
+        let mut data = FontData::default();
+        let mut at = 4;
+        while at + 5 <= bytes.len() {
+            let kind = bytes[at];
+            let size = u32_at(bytes, at + 1) as usize;
+            let block = bytes
+                .get(at + 5..at + 5 + size)
+                .ok_or_else(|| invalid("truncated block"))?;
+            at += 5 + size;
+            match kind {
+                // common
+                2 if block.len() >= 4 => {
+                    data.line_height = u16_at(block, 0) as i32;
+                    data.base = u16_at(block, 2) as i32;
+                }
+                // pages, null-terminated names
+                3 => {
+                    data.pages = block
+                        .split(|&b| b == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                // chars, 20 bytes each
+                4 => {
+                    for c in block.chunks_exact(20) {
+                        let Some(id) = char::from_u32(u32_at(c, 0)) else {
+                            continue;
+                        };
+                        let glyph = Glyph {
+                            page: c[18] as usize,
+                            source: Rectangle::new(
+                                u16_at(c, 4) as f32,
+                                u16_at(c, 6) as f32,
+                                u16_at(c, 8) as f32,
+                                u16_at(c, 10) as f32,
+                            ),
+                            offset: IVec2::new(i16_at(c, 12) as i32, i16_at(c, 14) as i32),
+                            advance: i16_at(c, 16) as i32,
+                        };
+                        data.glyphs.insert(id, glyph);
+                    }
+                }
+                // kerning pairs, 10 bytes each
+                5 => {
+                    for k in block.chunks_exact(10) {
+                        let first = char::from_u32(u32_at(k, 0));
+                        let second = char::from_u32(u32_at(k, 4));
+                        if let (Some(first), Some(second)) = (first, second) {
+                            data.kerning.insert((first, second), i16_at(k, 8) as i32);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// `key=value` pairs from a text .fnt line, where values may be quoted and contain spaces.
+fn text_fields(line: &str) -> HashMap<&str, &str> {
+    let mut fields = HashMap::new();
+    let mut rest = line.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let after = &rest[eq + 1..];
+        let (value, next) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+        } else {
+            let end = after.find(' ').unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        fields.insert(key, value);
+        rest = next.trim_start();
+    }
+    fields
+}
+
+/// Glyph atlases are either textures we loaded or the atlas raylib built for a TTF.
+enum Page {
+    Texture(Texture2D),
+    Font(Font),
+    /// raylib's built-in font, which raylib unloads itself.
+    Default(WeakFont),
+}
+
+impl AsRef<ffi::Texture2D> for Page {
+    fn as_ref(&self) -> &ffi::Texture2D {
+        match self {
+            Page::Texture(texture) => texture.as_ref(),
+            Page::Font(font) => &font.texture,
+            Page::Default(font) => &font.texture,
+        }
+    }
+}
+
+pub struct BitmapFont {
+    pub line_height: i32,
+    /// Distance from the top of a line to the baseline.
+    pub base: i32,
+    /// Extra pixels between characters, on top of each glyph's advance.
+    pub letter_spacing: i32,
+    /// Drawn in place of characters the font doesn't have; skipped if it's missing too.
+    pub fallback: char,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), i32>,
+    pages: Vec<Page>,
+}
+
+impl BitmapFont {
+    /// Loads an AngelCode BMFont, text or binary, and its page images.
+    pub fn load_bmfont(
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, FontError> {
+        let path = path.as_ref();
+        let data = FontData::parse_bmfont(&std::fs::read(path)?)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let pages = data
+            .pages
+            .iter()
+            .map(|page| {
+                let texture = rl.load_texture(rlt, &dir.join(page).to_string_lossy())?;
+                Ok(Page::Texture(texture))
+            })
+            .collect::<Result<Vec<_>, FontError>>()?;
+        Ok(Self::new(data, pages))
+    }
+
+    /// Loads a PNG laid out as a grid of `cell`-sized glyphs, left to right then top to
+    /// bottom, in the order given by `chars`. With `proportional` each glyph advances by
+    /// the width of its opaque pixels plus a one pixel gap instead of the full cell
+    /// width; spaces get half a cell.
+    pub fn load_grid(
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        path: impl AsRef<Path>,
+        cell: UVec2,
+        chars: &str,
+        proportional: bool,
+    ) -> Result<Self, FontError> {
+        let path = path.as_ref().to_string_lossy();
+        let image = Image::load_image(&path)?;
+        let columns = (image.width as u32 / cell.x.max(1)).max(1);
+        let pixels = image.get_image_data();
+        let opaque = |x: u32, y: u32| pixels[(y * image.width as u32 + x) as usize].a > 0;
+
+        let mut data = FontData {
+            line_height: cell.y as i32,
+            base: cell.y as i32,
+            ..Default::default()
+        };
+        for (i, c) in chars.chars().enumerate() {
+            let min = UVec2::new(i as u32 % columns, i as u32 / columns) * cell;
+            if min.y + cell.y > image.height as u32 {
+                return Err(invalid(format!("{} doesn't have a cell for {:?}", path, c)));
+            }
+            let advance = if !proportional {
+                cell.x as i32
+            } else if c == ' ' {
+                (cell.x / 2) as i32
+            } else {
+                (0..cell.x)
+                    .rev()
+                    .find(|&x| (0..cell.y).any(|y| opaque(min.x + x, min.y + y)))
+                    .map_or(0, |x| x as i32 + 2)
+            };
+            let glyph = Glyph {
+                page: 0,
+                source: Rectangle::new(min.x as f32, min.y as f32, cell.x as f32, cell.y as f32),
+                offset: IVec2::ZERO,
+                advance,
+            };
+            data.glyphs.insert(c, glyph);
+        }
+        let texture = rl.load_texture_from_image(rlt, &image)?;
+        Ok(Self::new(data, vec![Page::Texture(texture)]))
+    }
+
+    /// Rasterises a TTF or OTF once at `size` pixels, for the characters in `chars` or
+    /// printable ASCII. Pixel fonts only come out crisp at the size they were drawn for.
+    pub fn load_ttf(
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        path: impl AsRef<Path>,
+        size: i32,
+        chars: Option<&str>,
+    ) -> Result<Self, FontError> {
+        let font = rl.load_font_ex(rlt, &path.as_ref().to_string_lossy(), size, chars)?;
+        // raylib leaves font atlases with whatever filter its default is; make sure it's
+        // point filtering so the glyphs stay sharp when the canvas is scaled up
+        unsafe {
+            ffi::SetTextureFilter(font.texture, TextureFilter::TEXTURE_FILTER_POINT as i32);
+        }
+        let data = Self::raylib_font_data(&font);
+        Ok(Self::new(data, vec![Page::Font(font)]))
+    }
+
+    /// raylib's built-in pixel font, 10 pixels tall; handy until a sketch has its own.
+    pub fn raylib_default(rl: &RaylibHandle) -> Self {
+        let font = rl.get_font_default();
+        let data = Self::raylib_font_data(&font);
+        let mut font = Self::new(data, vec![Page::Default(font)]);
+        // raylib draws it a pixel apart
+        font.letter_spacing = 1;
+        font
+    }
+
+    fn raylib_font_data(font: &ffi::Font) -> FontData {
+        let count = font.glyphCount.max(0) as usize;
+        let (infos, recs) = unsafe {
+            (
+                std::slice::from_raw_parts(font.glyphs, count),
+                std::slice::from_raw_parts(font.recs, count),
+            )
+        };
+        let mut data = FontData {
+            line_height: font.baseSize,
+            base: font.baseSize,
+            ..Default::default()
+        };
+        for (info, rec) in infos.iter().zip(recs) {
+            let Some(c) = char::from_u32(info.value as u32) else {
+                continue;
+            };
+            let glyph = Glyph {
+                page: 0,
+                source: Rectangle::new(rec.x, rec.y, rec.width, rec.height),
+                offset: IVec2::new(info.offsetX, info.offsetY),
+                advance: if info.advanceX > 0 {
+                    info.advanceX
+                } else {
+                    rec.width as i32
+                },
+            };
+            data.glyphs.insert(c, glyph);
+        }
+        data
+    }
+
+    fn new(data: FontData, pages: Vec<Page>) -> Self {
+        Self {
+            line_height: data.line_height,
+            base: data.base,
+            letter_spacing: 0,
+            fallback: '?',
+            glyphs: data.glyphs,
+            kerning: data.kerning,
+            pages,
+        }
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&self.fallback))
+    }
+
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    /// Width of a single line, ignoring any newlines in it.
+    pub fn line_width(&self, line: &str) -> i32 {
+        let mut width = 0;
+        let mut prev = None;
+        for c in line.chars().filter(|&c| c != '\n') {
+            let Some(glyph) = self.glyph(c) else {
+                continue;
+            };
+            if let Some(prev) = prev {
+                width += self.kerning(prev, c) + self.letter_spacing;
+            }
+            width += glyph.advance;
+            prev = Some(c);
+        }
+        width
+    }
+
+    /// Size of `text` in canvas pixels, with a line per newline.
+    pub fn measure(&self, text: &str) -> IVec2 {
+        self.measure_lines(text.split('\n'))
+    }
+
+    /// Size of `text` after wrapping it to `width`.
+    pub fn measure_wrapped(&self, text: &str, width: i32) -> IVec2 {
+        self.measure_lines(self.wrap(text, width).iter().map(String::as_str))
+    }
+
+    fn measure_lines<'a>(&self, lines: impl Iterator<Item = &'a str>) -> IVec2 {
+        let (width, count) = lines.fold((0, 0), |(width, count), line| {
+            (width.max(self.line_width(line)), count + 1)
+        });
+        IVec2::new(width, count * self.line_height)
+    }
+
+    /// Breaks `text` into lines no wider than `width`, at spaces where possible and
+    /// mid-word for words that don't fit on a line of their own. Newlines are kept.
+    pub fn wrap(&self, text: &str, width: i32) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if self.line_width(&candidate) <= width {
+                    line = candidate;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                // the word alone might still be too long
+                for c in word.chars() {
+                    line.push(c);
+                    if self.line_width(&line) > width && line.chars().count() > 1 {
+                        line.pop();
+                        lines.push(std::mem::replace(&mut line, c.to_string()));
+                    }
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Draws `text` with its first line's top at `pos.y`. Each line starts, is centred
+    /// on, or ends at `pos.x` depending on `align`.
+    pub fn draw_text(
+        &self,
+        d: &mut impl RaylibDraw,
+        text: &str,
+        pos: IVec2,
+        align: Align,
+        color: Color,
+    ) {
+        for (i, line) in text.split('\n').enumerate() {
+            let x = match align {
+                Align::Left => pos.x,
+                Align::Center => pos.x - self.line_width(line) / 2,
+                Align::Right => pos.x - self.line_width(line),
+            };
+            self.draw_line(
+                d,
+                line,
+                IVec2::new(x, pos.y + i as i32 * self.line_height),
+                color,
+            );
+        }
+    }
+
+    /// Wraps `text` to `width` and aligns each line within the box starting at `pos`.
+    /// Returns the height used.
+    pub fn draw_text_box(
+        &self,
+        d: &mut impl RaylibDraw,
+        text: &str,
+        pos: IVec2,
+        width: i32,
+        align: Align,
+        color: Color,
+    ) -> i32 {
+        let lines = self.wrap(text, width);
+        for (i, line) in lines.iter().enumerate() {
+            let x = match align {
+                Align::Left => pos.x,
+                Align::Center => pos.x + (width - self.line_width(line)) / 2,
+                Align::Right => pos.x + width - self.line_width(line),
+            };
+            self.draw_line(
+                d,
+                line,
+                IVec2::new(x, pos.y + i as i32 * self.line_height),
+                color,
+            );
+        }
+        lines.len() as i32 * self.line_height
+    }
+
+    /// Draws one glyph with the pen at `pen` (top of the line), returning its advance.
+    /// Kerning and letter spacing are up to the caller.
+    pub fn draw_char(&self, d: &mut impl RaylibDraw, c: char, pen: IVec2, color: Color) -> i32 {
+        let Some(glyph) = self.glyph(c) else {
+            return 0;
+        };
+        if let Some(page) = self.pages.get(glyph.page) {
+            let at = pen + glyph.offset;
+            d.draw_texture_rec(
+                page,
+                glyph.source,
+                Vector2::new(at.x as f32, at.y as f32),
+                color,
+            );
+        }
+        glyph.advance
+    }
+
+    fn draw_line(&self, d: &mut impl RaylibDraw, line: &str, pos: IVec2, color: Color) {
+        let mut pen = pos;
+        let mut prev = None;
+        for c in line.chars() {
+            if self.glyph(c).is_none() {
+                continue;
+            }
+            if let Some(prev) = prev {
+                pen.x += self.kerning(prev, c) + self.letter_spacing;
+            }
+            pen.x += self.draw_char(d, c, pen, color);
+            prev = Some(c);
+        }
+    }
+}
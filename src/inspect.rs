@@ -0,0 +1,306 @@
+//! Live tweak panels for sketch `State`.
+//!
+//! Leaf types (numbers, `Color`, vectors, cameras, ...) implement [`Inspect`] here.
+//! Structs opt in by listing the fields to show, with an optional range for numbers:
+//! ```ignore
+//! lowres::inspect_struct!(State {
+//!     speed_deg_per_s: 0.0..=360.0,
+//!     color,
+//! });
+//! ```
+//! Fields that aren't listed are left out of the panel.
+
+use std::ops::RangeInclusive;
+
+use egui::{DragValue, Ui};
+use glam::{IVec2, IVec3, UVec2, Vec2, Vec3, Vec4};
+use raylib::prelude::*;
+
+#[doc(hidden)]
+pub use egui as __egui;
+
+pub trait Inspect {
+    /// Draws an editor for `self`. Returns true if anything changed.
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool;
+}
+
+/// Numbers that can be limited to a range by `inspect_struct!`.
+pub trait InspectRanged: Sized {
+    fn inspect_ranged(&mut self, ui: &mut Ui, label: &str, range: RangeInclusive<Self>) -> bool;
+}
+
+/// Shows `value` in its own window.
+pub fn inspector_window(ctx: &egui::Context, title: &str, value: &mut impl Inspect) {
+    egui::Window::new(title).vscroll(true).show(ctx, |ui| {
+        value.inspect(ui, title);
+    });
+}
+
+#[macro_export]
+macro_rules! inspect_struct {
+    (@field $value:expr, $ui:ident, $label:expr) => {
+        $crate::inspect::Inspect::inspect(&mut $value, $ui, $label)
+    };
+    (@field $value:expr, $ui:ident, $label:expr, $range:expr) => {
+        $crate::inspect::InspectRanged::inspect_ranged(&mut $value, $ui, $label, $range)
+    };
+    ($ty:ty { $($field:ident $(: $range:expr)?),* $(,)? }) => {
+        impl $crate::inspect::Inspect for $ty {
+            fn inspect(&mut self, ui: &mut $crate::inspect::__egui::Ui, label: &str) -> bool {
+                let mut changed = false;
+                $crate::inspect::__egui::CollapsingHeader::new(label)
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        $(
+                            changed |= $crate::inspect_struct!(
+                                @field self.$field, ui, stringify!($field) $(, $range)?
+                            );
+                        )*
+                    });
+                changed
+            }
+        }
+    };
+}
+
+fn labelled(ui: &mut Ui, label: &str, add_contents: impl FnOnce(&mut Ui) -> bool) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        add_contents(ui)
+    })
+    .inner
+}
+
+macro_rules! impl_inspect_number {
+    ($speed:expr => $($t:ty),*) => {$(
+        impl Inspect for $t {
+            fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+                labelled(ui, label, |ui| ui.add(DragValue::new(self).speed($speed)).changed())
+            }
+        }
+
+        impl InspectRanged for $t {
+            fn inspect_ranged(
+                &mut self,
+                ui: &mut Ui,
+                label: &str,
+                range: RangeInclusive<Self>,
+            ) -> bool {
+                labelled(ui, label, |ui| {
+                    ui.add(DragValue::new(self).speed($speed).range(range)).changed()
+                })
+            }
+        }
+    )*};
+}
+
+impl_inspect_number!(0.1 => f32, f64);
+impl_inspect_number!(1.0 => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Inspect for bool {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        ui.checkbox(self, label).changed()
+    }
+}
+
+impl Inspect for String {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        labelled(ui, label, |ui| ui.text_edit_singleline(self).changed())
+    }
+}
+
+impl Inspect for Color {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        let mut rgba = [self.r, self.g, self.b, self.a];
+        let changed = labelled(ui, label, |ui| {
+            ui.color_edit_button_srgba_unmultiplied(&mut rgba).changed()
+        });
+        if changed {
+            *self = Color::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+        }
+        changed
+    }
+}
+
+fn components<T: egui::emath::Numeric>(
+    ui: &mut Ui,
+    label: &str,
+    speed: f64,
+    values: &mut [&mut T],
+) -> bool {
+    const NAMES: [&str; 4] = ["x: ", "y: ", "z: ", "w: "];
+    labelled(ui, label, |ui| {
+        let mut changed = false;
+        for (value, name) in values.iter_mut().zip(NAMES) {
+            changed |= ui
+                .add(DragValue::new(&mut **value).speed(speed).prefix(name))
+                .changed();
+        }
+        changed
+    })
+}
+
+impl Inspect for Vector2 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 0.1, &mut [&mut self.x, &mut self.y])
+    }
+}
+
+impl Inspect for Vector3 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 0.1, &mut [&mut self.x, &mut self.y, &mut self.z])
+    }
+}
+
+impl Inspect for Vector4 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(
+            ui,
+            label,
+            0.1,
+            &mut [&mut self.x, &mut self.y, &mut self.z, &mut self.w],
+        )
+    }
+}
+
+impl Inspect for Vec2 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 0.1, &mut [&mut self.x, &mut self.y])
+    }
+}
+
+impl Inspect for Vec3 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 0.1, &mut [&mut self.x, &mut self.y, &mut self.z])
+    }
+}
+
+impl Inspect for Vec4 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(
+            ui,
+            label,
+            0.1,
+            &mut [&mut self.x, &mut self.y, &mut self.z, &mut self.w],
+        )
+    }
+}
+
+impl Inspect for IVec2 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 1.0, &mut [&mut self.x, &mut self.y])
+    }
+}
+
+impl Inspect for IVec3 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 1.0, &mut [&mut self.x, &mut self.y, &mut self.z])
+    }
+}
+
+impl Inspect for UVec2 {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        components(ui, label, 1.0, &mut [&mut self.x, &mut self.y])
+    }
+}
+
+impl Inspect for Camera2D {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        let mut changed = false;
+        egui::CollapsingHeader::new(label).show(ui, |ui| {
+            changed |= self.offset.inspect(ui, "offset");
+            changed |= self.target.inspect(ui, "target");
+            changed |= self.rotation.inspect_ranged(ui, "rotation", -180.0..=180.0);
+            changed |= self.zoom.inspect_ranged(ui, "zoom", 0.05..=20.0);
+        });
+        changed
+    }
+}
+
+/// Raw vectors plus orbit controls (yaw/pitch/distance around the target), which are
+/// usually what you actually want to nudge.
+impl Inspect for Camera3D {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        let mut changed = false;
+        egui::CollapsingHeader::new(label).show(ui, |ui| {
+            let mut perspective = self.camera_type() == CameraProjection::CAMERA_PERSPECTIVE;
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .selectable_value(&mut perspective, true, "perspective")
+                    .changed();
+                changed |= ui
+                    .selectable_value(&mut perspective, false, "orthographic")
+                    .changed();
+            });
+
+            let offset = Vec3::new(
+                self.position.x - self.target.x,
+                self.position.y - self.target.y,
+                self.position.z - self.target.z,
+            );
+            let mut distance = offset.length().max(0.001);
+            let mut yaw = offset.x.atan2(offset.z).to_degrees();
+            let mut pitch = (offset.y / distance).clamp(-1.0, 1.0).asin().to_degrees();
+            let mut orbited = false;
+            orbited |= yaw.inspect_ranged(ui, "yaw", -180.0..=180.0);
+            orbited |= pitch.inspect_ranged(ui, "pitch", -89.0..=89.0);
+            orbited |= distance.inspect_ranged(ui, "distance", 0.01..=1000.0);
+            if orbited {
+                let (yaw, pitch) = (yaw.to_radians(), pitch.to_radians());
+                let offset = Vec3::new(
+                    pitch.cos() * yaw.sin(),
+                    pitch.sin(),
+                    pitch.cos() * yaw.cos(),
+                ) * distance;
+                self.position = Vector3::new(
+                    self.target.x + offset.x,
+                    self.target.y + offset.y,
+                    self.target.z + offset.z,
+                );
+                changed = true;
+            }
+
+            changed |= self.position.inspect(ui, "position");
+            changed |= self.target.inspect(ui, "target");
+            changed |= self.up.inspect(ui, "up");
+            let fovy_label = if perspective { "fovy" } else { "height" };
+            changed |= self.fovy.inspect_ranged(ui, fovy_label, 1.0..=179.0);
+
+            if changed {
+                *self = if perspective {
+                    Camera3D::perspective(self.position, self.target, self.up, self.fovy)
+                } else {
+                    Camera3D::orthographic(self.position, self.target, self.up, self.fovy)
+                };
+            }
+        });
+        changed
+    }
+}
+
+impl<T: Inspect> Inspect for Option<T> {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        match self {
+            Some(value) => value.inspect(ui, label),
+            None => {
+                labelled(ui, label, |ui| {
+                    ui.weak("none");
+                    false
+                });
+                false
+            }
+        }
+    }
+}
+
+impl<T: Inspect> Inspect for Vec<T> {
+    fn inspect(&mut self, ui: &mut Ui, label: &str) -> bool {
+        let mut changed = false;
+        egui::CollapsingHeader::new(format!("{} [{}]", label, self.len())).show(ui, |ui| {
+            for (i, value) in self.iter_mut().enumerate() {
+                ui.push_id(i, |ui| changed |= value.inspect(ui, &format!("[{}]", i)));
+            }
+        });
+        changed
+    }
+}
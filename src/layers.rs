@@ -0,0 +1,275 @@
+//! Named render layers, each with its own render texture and resolution.
+//!
+//! The runner clears every layer at the start of the frame, the sketch draws into
+//! whichever layer suits each draw call, and the runner composites them in the order
+//! they were added, stretching each one over the window.
+
+use glam::{UVec2, Vec2};
+use raylib::core::error::Error;
+use raylib::prelude::*;
+
+use crate::display;
+
+pub struct Layer {
+    pub name: &'static str,
+    pub render_texture: RenderTexture2D,
+    pub clear_color: Color,
+    pub visible: bool,
+    /// Fraction of a layer pixel (0.0 to 1.0 on each axis) to shift the layer up and left
+    /// by when compositing, for scrolling layers.
+    pub subpixel_offset: Vec2,
+    padding: u32,
+}
+
+impl Layer {
+    /// The size the layer is shown at, without any scrolling padding.
+    pub fn dims(&self) -> UVec2 {
+        self.texture_dims() - self.padding
+    }
+
+    /// The size of the render texture, one pixel larger on each axis for scrolling layers.
+    pub fn texture_dims(&self) -> UVec2 {
+        UVec2::new(
+            self.render_texture.texture.width as u32,
+            self.render_texture.texture.height as u32,
+        )
+    }
+}
+
+pub struct Layers {
+    layers: Vec<Layer>,
+    window_dims: UVec2,
+    mouse_layer: Option<usize>,
+    // window-space rect the layers are shown in, the whole window unless an editor
+    // viewport says otherwise
+    viewport_min: Vec2,
+    viewport_size: Vec2,
+}
+
+impl Layers {
+    pub fn new(window_dims: UVec2) -> Self {
+        Self {
+            layers: Vec::new(),
+            window_dims,
+            mouse_layer: None,
+            viewport_min: Vec2::ZERO,
+            viewport_size: window_dims.as_vec2(),
+        }
+    }
+
+    /// Adds a layer on top of the existing ones.
+    /// Use `Color::BLANK` as the clear color for anything that overlays another layer.
+    pub fn add(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        name: &'static str,
+        dims: UVec2,
+        clear_color: Color,
+    ) -> Result<(), Error> {
+        self.add_padded(rl, rlt, name, dims, clear_color, 0)
+    }
+
+    /// Adds a layer for a scrolling camera: the render texture has an extra pixel on each
+    /// axis so that shifting it by `subpixel_offset` never shows an empty edge.
+    pub fn add_scrolling(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        name: &'static str,
+        dims: UVec2,
+        clear_color: Color,
+    ) -> Result<(), Error> {
+        self.add_padded(rl, rlt, name, dims, clear_color, 1)
+    }
+
+    fn add_padded(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        name: &'static str,
+        dims: UVec2,
+        clear_color: Color,
+        padding: u32,
+    ) -> Result<(), Error> {
+        assert!(
+            self.index_of(name).is_none(),
+            "layer '{}' already exists",
+            name
+        );
+        let render_texture = rl.load_render_texture(rlt, dims.x + padding, dims.y + padding)?;
+        self.layers.push(Layer {
+            name,
+            render_texture,
+            clear_color,
+            visible: true,
+            subpixel_offset: Vec2::ZERO,
+            padding,
+        });
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> &Layer {
+        &self.layers[self.expect_index(name)]
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> &mut Layer {
+        let index = self.expect_index(name);
+        &mut self.layers[index]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.iter()
+    }
+
+    pub fn window_dims(&self) -> UVec2 {
+        self.window_dims
+    }
+
+    /// The layer `set_mouse_layer` picked, or the bottom one.
+    pub fn canvas(&self) -> &Layer {
+        &self.layers[self.mouse_layer.unwrap_or(0)]
+    }
+
+    /// Scales raylib's mouse so `get_mouse_position` reports coordinates in this layer.
+    pub fn set_mouse_layer(&mut self, rl: &mut RaylibHandle, name: &str) {
+        self.mouse_layer = Some(self.expect_index(name));
+        self.apply_mouse_mapping(rl);
+    }
+
+    /// Maps the mouse onto layers shown in `size` pixels at `min` instead of the whole
+    /// window, e.g. an editor viewport.
+    pub fn set_viewport(&mut self, rl: &mut RaylibHandle, min: Vec2, size: Vec2) {
+        // raylib only takes whole-pixel offsets
+        self.viewport_min = min.round();
+        self.viewport_size = size;
+        self.apply_mouse_mapping(rl);
+    }
+
+    pub fn reset_viewport(&mut self, rl: &mut RaylibHandle) {
+        self.set_viewport(rl, Vec2::ZERO, self.window_dims.as_vec2());
+    }
+
+    /// The scale applied to the mouse, `Vec2::ONE` if there's no mouse layer and no viewport.
+    pub fn mouse_scale(&self) -> Vec2 {
+        match self.mouse_layer {
+            Some(index) => self.layers[index].dims().as_vec2() / self.viewport_size,
+            None => self.window_dims.as_vec2() / self.viewport_size,
+        }
+    }
+
+    /// The offset applied to the mouse, in window pixels (raylib applies it before scaling).
+    pub fn mouse_offset(&self) -> Vec2 {
+        -self.viewport_min
+    }
+
+    /// Mouse position in the pixel space of any layer, regardless of which one
+    /// `set_mouse_layer` picked.
+    pub fn mouse_position(&self, rl: &RaylibHandle, name: &str) -> Vec2 {
+        let raw = rl.get_mouse_position();
+        let window_pos = Vec2::new(raw.x, raw.y) / self.mouse_scale() - self.mouse_offset();
+        self.window_to_layer(name, window_pos)
+    }
+
+    pub fn window_to_layer(&self, name: &str, window_pos: Vec2) -> Vec2 {
+        (window_pos - self.viewport_min) / self.viewport_size * self.get(name).dims().as_vec2()
+    }
+
+    pub fn layer_to_layer(&self, from: &str, to: &str, pos: Vec2) -> Vec2 {
+        pos / self.get(from).dims().as_vec2() * self.get(to).dims().as_vec2()
+    }
+
+    pub fn clear(&mut self, draw_handle: &mut RaylibDrawHandle, rlt: &RaylibThread) {
+        for layer in &mut self.layers {
+            let mut d = draw_handle.begin_texture_mode(rlt, &mut layer.render_texture);
+            d.clear_background(layer.clear_color);
+        }
+    }
+
+    /// Runs `f` with the named layer bound as the render target.
+    pub fn draw<'a>(
+        &mut self,
+        draw_handle: &mut RaylibDrawHandle<'a>,
+        rlt: &RaylibThread,
+        name: &str,
+        f: impl FnOnce(&mut RaylibTextureMode<RaylibDrawHandle<'a>>),
+    ) {
+        let index = self.expect_index(name);
+        let mut d = draw_handle.begin_texture_mode(rlt, &mut self.layers[index].render_texture);
+        f(&mut d);
+    }
+
+    /// Blits every visible layer to the window, bottom first.
+    pub fn composite(&mut self, draw_handle: &mut RaylibDrawHandle, fullscreen: bool) {
+        for layer in self.layers.iter_mut().filter(|layer| layer.visible) {
+            scale_and_blit_render_texture_to_window(
+                draw_handle,
+                &mut layer.render_texture,
+                layer.padding,
+                layer.subpixel_offset,
+                fullscreen,
+                self.window_dims,
+            );
+        }
+    }
+
+    fn apply_mouse_mapping(&self, rl: &mut RaylibHandle) {
+        // raylib computes (cursor + offset) * scale, and on scaled displays the cursor may
+        // be in framebuffer pixels, so fold its own DPI scale into both
+        let cursor_scale = display::cursor_scale(rl);
+        let offset = self.mouse_offset() / cursor_scale;
+        let scale = self.mouse_scale() * cursor_scale;
+        rl.set_mouse_offset(Vector2::new(offset.x, offset.y));
+        rl.set_mouse_scale(scale.x, scale.y);
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    fn expect_index(&self, name: &str) -> usize {
+        self.index_of(name)
+            .unwrap_or_else(|| panic!("no layer named '{}'", name))
+    }
+}
+
+/// Stretches `render_texture`, less `padding` pixels on the right and bottom, over the
+/// window. `subpixel_offset` is in texture pixels and moves the whole image up and left by
+/// that fraction of a scaled pixel, letting the padding slide into view.
+pub fn scale_and_blit_render_texture_to_window(
+    draw_handle: &mut RaylibDrawHandle,
+    render_texture: &mut RenderTexture2D,
+    padding: u32,
+    subpixel_offset: Vec2,
+    fullscreen: bool,
+    window_dims: UVec2,
+) {
+    let texture_dims = Vec2::new(
+        render_texture.texture.width as f32,
+        render_texture.texture.height as f32,
+    );
+    let source_rec = Rectangle::new(0.0, 0.0, texture_dims.x, -texture_dims.y);
+    // dest size should be the fullscreen resolution if graphics.fullscreen, otherwise window_dims.
+    // Both are logical pixels; on high-DPI displays raylib's screen scale stretches them
+    // over the whole framebuffer.
+    let dest_dims = if fullscreen {
+        display::screen_dims(draw_handle).as_vec2()
+    } else {
+        window_dims.as_vec2()
+    };
+    let pixel_scale = dest_dims / (texture_dims - padding as f32);
+    let dest_min = -subpixel_offset * pixel_scale;
+    let dest_size = texture_dims * pixel_scale;
+    let dest_rec = Rectangle::new(dest_min.x, dest_min.y, dest_size.x, dest_size.y);
+
+    let origin = Vector2::new(0.0, 0.0);
+
+    draw_handle.draw_texture_pro(
+        render_texture,
+        source_rec,
+        dest_rec,
+        origin,
+        0.0,
+        Color::WHITE,
+    );
+}
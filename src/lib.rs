@@ -0,0 +1,26 @@
+//! Shared pieces used by the template binaries.
+
+pub mod audio;
+pub mod camera;
+pub mod collision;
+pub mod display;
+pub mod ecs;
+pub mod editor;
+pub mod egui_raylib;
+pub mod font;
+pub mod inspect;
+pub mod layers;
+pub mod mask;
+pub mod particles;
+pub mod rich_text;
+pub mod rng;
+pub mod save;
+pub mod scene;
+pub mod scheduler;
+pub mod sfxr;
+pub mod sprite;
+pub mod tilemap;
+pub mod timestep;
+pub mod tracker;
+pub mod transition;
+pub mod tween;
@@ -0,0 +1,416 @@
+//! Pixel-perfect collision from sprite alpha, one bit per pixel.
+//!
+//! Masks are packed 64 pixels to a word, so testing two sprites costs a few AND
+//! instructions per row rather than one per pixel. Positions are whole canvas pixels,
+//! the same ones sprites snap to when drawn.
+//! ```ignore
+//! let masks = SpriteMasks::from_sheet(&sheet, 128)?;
+//! // in step, with each sprite's position
+//! let (hero_mask, hero_at) = masks.placed(&sheet, &hero, hero_pos);
+//! let (bat_mask, bat_at) = bat_masks.placed(&bat_sheet, &bat, bat_pos);
+//! if hero_mask.overlaps(hero_at, bat_mask, bat_at) {
+//!     hurt = true;
+//! }
+//! if hero_mask.overlaps_rect(hero_at, spikes) { .. }
+//! ```
+
+use glam::{IVec2, Vec2};
+use raylib::prelude::*;
+
+use crate::sprite::{AnimatedSprite, Frame, IRect, SpriteSheet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    width: i32,
+    height: i32,
+    /// Words per row.
+    stride: usize,
+    /// Row by row; bit `k` of a word is the pixel `k` columns after the word's first.
+    /// Bits past `width` are always clear.
+    bits: Vec<u64>,
+}
+
+impl Mask {
+    pub fn new(width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        let stride = (width as usize).div_ceil(64);
+        Self {
+            width,
+            height,
+            stride,
+            bits: vec![0; stride * height as usize],
+        }
+    }
+
+    pub fn from_fn(width: i32, height: i32, solid: impl Fn(i32, i32) -> bool) -> Self {
+        let mut mask = Self::new(width, height);
+        for y in 0..mask.height {
+            for x in 0..mask.width {
+                if solid(x, y) {
+                    mask.set(x, y, true);
+                }
+            }
+        }
+        mask
+    }
+
+    /// Pixels of `rect` in `image` with alpha of at least `threshold` are solid.
+    pub fn from_image(image: &Image, rect: IRect, threshold: u8) -> Self {
+        let pixels = image.get_image_data();
+        Self::from_pixels(&pixels, image.width, rect, threshold)
+    }
+
+    /// Like `from_image`, from pixels already read out, so a sheet is only read once.
+    fn from_pixels(pixels: &[Color], image_width: i32, rect: IRect, threshold: u8) -> Self {
+        Self::from_fn(rect.size.x, rect.size.y, |x, y| {
+            let (px, py) = (rect.pos.x + x, rect.pos.y + y);
+            px >= 0
+                && py >= 0
+                && px < image_width
+                && pixels
+                    .get((py * image_width + px) as usize)
+                    .is_some_and(|pixel| pixel.a >= threshold)
+        })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// False outside the mask.
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+        let word = self.bits[y as usize * self.stride + x as usize / 64];
+        word >> (x % 64) & 1 != 0
+    }
+
+    /// Does nothing outside the mask.
+    pub fn set(&mut self, x: i32, y: i32, solid: bool) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let word = &mut self.bits[y as usize * self.stride + x as usize / 64];
+        let bit = 1u64 << (x % 64);
+        if solid {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Number of solid pixels.
+    pub fn count(&self) -> u32 {
+        self.bits.iter().map(|word| word.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&word| word == 0)
+    }
+
+    /// Mirrored left to right and/or top to bottom.
+    pub fn flipped(&self, flip_x: bool, flip_y: bool) -> Self {
+        Self::from_fn(self.width, self.height, |x, y| {
+            let x = if flip_x { self.width - 1 - x } else { x };
+            let y = if flip_y { self.height - 1 - y } else { y };
+            self.get(x, y)
+        })
+    }
+
+    /// The 64 pixels of row `y` starting at column `x`, which may be off either side.
+    fn row_bits(&self, y: i32, x: i32) -> u64 {
+        let row = &self.bits[y as usize * self.stride..][..self.stride];
+        let word = |i: i32| {
+            if i < 0 {
+                0
+            } else {
+                row.get(i as usize).copied().unwrap_or(0)
+            }
+        };
+        let (index, shift) = (x.div_euclid(64), x.rem_euclid(64));
+        if shift == 0 {
+            word(index)
+        } else {
+            word(index) >> shift | word(index + 1) << (64 - shift)
+        }
+    }
+
+    /// Whether any solid pixel of this mask, with its top left at `pos`, lands on a
+    /// solid pixel of `other` at `other_pos`.
+    pub fn overlaps(&self, pos: IVec2, other: &Mask, other_pos: IVec2) -> bool {
+        let offset = other_pos - pos;
+        let top = offset.y.max(0);
+        let bottom = (offset.y + other.height).min(self.height);
+        let left = offset.x.max(0);
+        let right = (offset.x + other.width).min(self.width);
+        if top >= bottom || left >= right {
+            return false;
+        }
+        let first_word = left / 64;
+        let last_word = (right - 1) / 64;
+        (top..bottom).any(|y| {
+            let row = &self.bits[y as usize * self.stride..];
+            (first_word..=last_word)
+                .any(|i| row[i as usize] & other.row_bits(y - offset.y, i * 64 - offset.x) != 0)
+        })
+    }
+
+    /// Whether any solid pixel of this mask, with its top left at `pos`, is inside
+    /// `rect`.
+    pub fn overlaps_rect(&self, pos: IVec2, rect: IRect) -> bool {
+        let min = (rect.pos - pos).max(IVec2::ZERO);
+        let max = (rect.pos + rect.size - pos).min(IVec2::new(self.width, self.height));
+        if min.x >= max.x || min.y >= max.y {
+            return false;
+        }
+        (min.y..max.y).any(|y| {
+            let mut x = min.x;
+            while x < max.x {
+                let n = (max.x - x).min(64);
+                let columns = if n == 64 { u64::MAX } else { (1u64 << n) - 1 };
+                if self.row_bits(y, x) & columns != 0 {
+                    return true;
+                }
+                x += n;
+            }
+            false
+        })
+    }
+
+    /// Whether the pixel at `point` is solid, with the mask's top left at `pos`.
+    pub fn contains(&self, pos: IVec2, point: IVec2) -> bool {
+        let local = point - pos;
+        self.get(local.x, local.y)
+    }
+}
+
+/// A mask for every frame of a sheet in every flip, each covering the untrimmed sprite
+/// so they line up with `SpriteSheet::draw_frame`.
+pub struct SpriteMasks {
+    /// Per frame: as is, flipped x, flipped y, flipped both.
+    frames: Vec<[Mask; 4]>,
+}
+
+impl SpriteMasks {
+    /// Reads the sheet's texture back from the GPU once, at load time.
+    pub fn from_sheet(
+        sheet: &SpriteSheet,
+        threshold: u8,
+    ) -> Result<Self, raylib::core::error::Error> {
+        let image = sheet.texture.load_image()?;
+        Ok(Self::from_image(&image, sheet.frames(), threshold))
+    }
+
+    pub fn from_image(image: &Image, frames: &[Frame], threshold: u8) -> Self {
+        let pixels = image.get_image_data();
+        let frames = frames
+            .iter()
+            .map(|frame| {
+                let trimmed = Mask::from_pixels(&pixels, image.width, frame.source, threshold);
+                let size = frame.size.as_ivec2();
+                let mask = Mask::from_fn(size.x, size.y, |x, y| {
+                    trimmed.get(x - frame.offset.x, y - frame.offset.y)
+                });
+                let flipped_x = mask.flipped(true, false);
+                let flipped_y = mask.flipped(false, true);
+                let flipped_both = mask.flipped(true, true);
+                [mask, flipped_x, flipped_y, flipped_both]
+            })
+            .collect();
+        Self { frames }
+    }
+
+    pub fn get(&self, frame: usize, flip_x: bool, flip_y: bool) -> Option<&Mask> {
+        let variant = flip_x as usize | (flip_y as usize) << 1;
+        self.frames.get(frame).map(|masks| &masks[variant])
+    }
+
+    /// The mask for `frame` and where its top left goes when drawn with `origin` at
+    /// `position`, matching `SpriteSheet::draw_frame`.
+    pub fn at(
+        &self,
+        frame: usize,
+        position: Vec2,
+        origin: IVec2,
+        flip_x: bool,
+        flip_y: bool,
+    ) -> Option<(&Mask, IVec2)> {
+        let mask = self.get(frame, flip_x, flip_y)?;
+        let mut origin = origin;
+        if flip_x {
+            origin.x = mask.width - origin.x;
+        }
+        if flip_y {
+            origin.y = mask.height - origin.y;
+        }
+        Some((mask, position.round().as_ivec2() - origin))
+    }
+
+    /// The mask for a sprite's current frame and flips, placed as `AnimatedSprite::draw`
+    /// would draw it. An empty mask if the frame is missing.
+    pub fn placed<'m>(
+        &'m self,
+        sheet: &SpriteSheet,
+        sprite: &AnimatedSprite,
+        position: Vec2,
+    ) -> (&'m Mask, IVec2) {
+        const EMPTY: &Mask = &Mask {
+            width: 0,
+            height: 0,
+            stride: 0,
+            bits: Vec::new(),
+        };
+        let frame = sprite.frame();
+        self.at(
+            frame,
+            position,
+            sheet.pivot(frame),
+            sprite.flip_x,
+            sprite.flip_y,
+        )
+        .unwrap_or((EMPTY, IVec2::ZERO))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sparse, irregular pattern, so overlaps depend on the exact offset.
+    fn speckled(width: i32, height: i32, seed: u32) -> Mask {
+        Mask::from_fn(width, height, |x, y| {
+            let h =
+                (x as u32).wrapping_mul(0x9e37_79b1) ^ (y as u32).wrapping_mul(0x85eb_ca77) ^ seed;
+            h.wrapping_mul(0xc2b2_ae3d) >> 28 == 0
+        })
+    }
+
+    fn naive_overlaps(a: &Mask, pos: IVec2, b: &Mask, other_pos: IVec2) -> bool {
+        (0..a.height()).any(|y| {
+            (0..a.width()).any(|x| {
+                let world = pos + IVec2::new(x, y);
+                a.get(x, y) && b.contains(other_pos, world)
+            })
+        })
+    }
+
+    fn naive_overlaps_rect(a: &Mask, pos: IVec2, rect: IRect) -> bool {
+        (0..a.height()).any(|y| {
+            (0..a.width()).any(|x| {
+                let world = pos + IVec2::new(x, y);
+                a.get(x, y)
+                    && world.cmpge(rect.pos).all()
+                    && world.cmplt(rect.pos + rect.size).all()
+            })
+        })
+    }
+
+    #[test]
+    fn wide_masks_match_a_per_pixel_check() {
+        let a = speckled(150, 6, 1);
+        let b = speckled(70, 5, 2);
+        let pos = IVec2::new(3, -2);
+        let (mut hits, mut misses) = (0, 0);
+        for dy in -6..=7 {
+            for dx in -80..=160 {
+                let other_pos = pos + IVec2::new(dx, dy);
+                let expected = naive_overlaps(&a, pos, &b, other_pos);
+                assert_eq!(
+                    a.overlaps(pos, &b, other_pos),
+                    expected,
+                    "at {}, {}",
+                    dx,
+                    dy
+                );
+                assert_eq!(
+                    b.overlaps(other_pos, &a, pos),
+                    expected,
+                    "at {}, {}",
+                    dx,
+                    dy
+                );
+                if expected {
+                    hits += 1;
+                } else {
+                    misses += 1;
+                }
+            }
+        }
+        assert!(hits > 0 && misses > 0);
+    }
+
+    #[test]
+    fn single_pixels_across_a_word_boundary() {
+        let mut wide = Mask::new(130, 1);
+        wide.set(63, 0, true);
+        wide.set(64, 0, true);
+        let dot = Mask::from_fn(1, 1, |_, _| true);
+        for x in -70..140 {
+            let expected = x == 63 || x == 64;
+            assert_eq!(wide.overlaps(IVec2::ZERO, &dot, IVec2::new(x, 0)), expected);
+            assert_eq!(dot.overlaps(IVec2::new(x, 0), &wide, IVec2::ZERO), expected);
+        }
+        // a mask straddling the boundary, placed on either side of it
+        let pair = Mask::from_fn(2, 1, |x, _| x == 1);
+        assert!(wide.overlaps(IVec2::ZERO, &pair, IVec2::new(62, 0)));
+        assert!(wide.overlaps(IVec2::ZERO, &pair, IVec2::new(63, 0)));
+        assert!(!wide.overlaps(IVec2::ZERO, &pair, IVec2::new(64, 0)));
+        assert!(!wide.overlaps(IVec2::ZERO, &pair, IVec2::new(61, 0)));
+    }
+
+    #[test]
+    fn flips_mirror_each_axis() {
+        // an L with a speckled fill, so no flip maps it onto itself
+        let speckles = speckled(70, 9, 3);
+        let mask = Mask::from_fn(70, 9, |x, y| x == 0 || y == 8 || speckles.get(x, y));
+        for (flip_x, flip_y) in [(true, false), (false, true), (true, true)] {
+            let flipped = mask.flipped(flip_x, flip_y);
+            assert_ne!(flipped, mask);
+            assert_eq!(flipped.count(), mask.count());
+            assert_eq!(flipped.flipped(flip_x, flip_y), mask);
+            for y in 0..9 {
+                for x in 0..70 {
+                    let from_x = if flip_x { 69 - x } else { x };
+                    let from_y = if flip_y { 8 - y } else { y };
+                    assert_eq!(flipped.get(x, y), mask.get(from_x, from_y));
+                }
+            }
+        }
+        assert_eq!(mask.flipped(false, false), mask);
+    }
+
+    #[test]
+    fn rects_partly_outside_match_a_per_pixel_check() {
+        let mask = speckled(140, 7, 4);
+        let pos = IVec2::new(-5, 2);
+        let (mut hits, mut misses) = (0, 0);
+        for y in [-10, 0, 3, 8] {
+            for x in (-80..=150).step_by(7) {
+                for size in [
+                    IVec2::new(1, 1),
+                    IVec2::new(9, 2),
+                    IVec2::new(70, 3),
+                    IVec2::new(200, 20),
+                ] {
+                    let rect = IRect {
+                        pos: IVec2::new(x, y),
+                        size,
+                    };
+                    let expected = naive_overlaps_rect(&mask, pos, rect);
+                    assert_eq!(mask.overlaps_rect(pos, rect), expected, "{:?}", rect);
+                    if expected {
+                        hits += 1;
+                    } else {
+                        misses += 1;
+                    }
+                }
+            }
+        }
+        assert!(hits > 0 && misses > 0);
+    }
+}
@@ -0,0 +1,384 @@
+//! Particle effects for the low res canvas: pixels, squares or sprites, moved on fixed
+//! ticks and drawn at whole pixel positions.
+//!
+//! Everything random comes from the emitter's seed, so the same effect started on the
+//! same tick plays out identically every time. Colors step through a list rather than
+//! blending, keeping particles on the palette.
+//! ```ignore
+//! let sparks = EmitterConfig {
+//!     shape: EmitShape::Circle { radius: 3.0 },
+//!     radial: true,
+//!     speed: (0.5, 2.0),
+//!     lifetime: (20, 40),
+//!     gravity: Vec2::new(0.0, 0.05),
+//!     colors: ColorMode::Ramp(vec![Color::WHITE, Color::YELLOW, Color::ORANGE, Color::MAROON]),
+//!     ..Default::default()
+//! };
+//! // step
+//! particles.burst(&sparks, hit_position, 24);
+//! particles.step();
+//! // draw, inside a layer
+//! particles.draw(d, None);
+//! ```
+
+use glam::Vec2;
+use raylib::prelude::*;
+
+use crate::rng::Rng;
+
+/* ----------- config --------------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParticleLook {
+    Pixel,
+    /// Centered on the particle, `n` pixels across.
+    Square(i32),
+    /// A region of the texture passed to `draw`, centered and tinted by the color.
+    Sprite(Rectangle),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorMode {
+    /// Steps through the colors evenly over each particle's life.
+    Ramp(Vec<Color>),
+    /// Loops through the colors, `ticks_per_color` each, from a random start.
+    Cycle {
+        colors: Vec<Color>,
+        ticks_per_color: u32,
+    },
+}
+
+/// Where new particles appear, relative to the emitter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitShape {
+    Point,
+    /// Anywhere on the line from the emitter to the emitter plus `to`.
+    Line {
+        to: Vec2,
+    },
+    /// Anywhere in a box of `size` centered on the emitter.
+    Rect {
+        size: Vec2,
+    },
+    /// Anywhere in the circle.
+    Circle {
+        radius: f32,
+    },
+    /// On the circle's edge only.
+    Ring {
+        radius: f32,
+    },
+}
+
+/// Speeds are in canvas pixels per tick and lifetimes in ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmitterConfig {
+    pub shape: EmitShape,
+    /// Particles per tick while the emitter is active; fractions carry over, so 0.25 is
+    /// one every fourth tick.
+    pub rate: f32,
+    /// Lowest and highest, picked at random per particle.
+    pub lifetime: (u32, u32),
+    pub speed: (f32, f32),
+    /// Radians; 0 is right and positive turns towards +y, i.e. down the canvas.
+    pub direction: f32,
+    /// Random turn up to this many radians either side of `direction`.
+    pub spread: f32,
+    /// Aim away from the emitter instead of along `direction`, with `spread` still
+    /// applied; suits explosions from circles and rings.
+    pub radial: bool,
+    pub gravity: Vec2,
+    /// Fraction of velocity lost each tick.
+    pub drag: f32,
+    pub look: ParticleLook,
+    pub colors: ColorMode,
+    /// New particles are dropped while this many are alive.
+    pub max_particles: usize,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        Self {
+            shape: EmitShape::Point,
+            rate: 0.0,
+            lifetime: (30, 30),
+            speed: (1.0, 1.0),
+            direction: -std::f32::consts::FRAC_PI_2,
+            spread: std::f32::consts::PI,
+            radial: false,
+            gravity: Vec2::ZERO,
+            drag: 0.0,
+            look: ParticleLook::Pixel,
+            colors: ColorMode::Ramp(vec![Color::WHITE]),
+            max_particles: 512,
+        }
+    }
+}
+
+impl ColorMode {
+    fn color(&self, particle: &Particle) -> Color {
+        match self {
+            ColorMode::Ramp(colors) => {
+                let step = particle.age as usize * colors.len() / particle.life.max(1) as usize;
+                colors.get(step.min(colors.len().saturating_sub(1)))
+            }
+            ColorMode::Cycle {
+                colors,
+                ticks_per_color,
+            } => {
+                // phase is any u32, so wrap both parts before adding
+                let count = colors.len().max(1);
+                let step = (particle.age / (*ticks_per_color).max(1)) as usize % count
+                    + particle.phase as usize % count;
+                colors.get(step % count)
+            }
+        }
+        .copied()
+        .unwrap_or(Color::WHITE)
+    }
+}
+
+/* ----------- emitter -------------------------------------------------- */
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: u32,
+    life: u32,
+    /// Start offset into a color cycle.
+    phase: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub config: EmitterConfig,
+    pub position: Vec2,
+    /// Emits at `config.rate` while true; particles already out carry on either way.
+    pub active: bool,
+    particles: Vec<Particle>,
+    /// Fraction of a particle owed from earlier ticks.
+    owed: f32,
+    rng: Rng,
+}
+
+impl Emitter {
+    pub fn new(config: EmitterConfig, position: Vec2, seed: u64) -> Self {
+        Self {
+            config,
+            position,
+            active: true,
+            particles: Vec::new(),
+            owed: 0.0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Emits `count` particles at once, active or not.
+    pub fn burst(&mut self, count: u32) {
+        for _ in 0..count {
+            self.spawn();
+        }
+    }
+
+    pub fn step(&mut self) {
+        if self.active {
+            self.owed += self.config.rate;
+            while self.owed >= 1.0 {
+                self.owed -= 1.0;
+                self.spawn();
+            }
+        }
+        let keep = 1.0 - self.config.drag;
+        for particle in &mut self.particles {
+            particle.age += 1;
+            particle.velocity = (particle.velocity + self.config.gravity) * keep;
+            particle.position += particle.velocity;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.life);
+    }
+
+    /// Particles alive.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Not emitting and nothing left on screen.
+    pub fn is_idle(&self) -> bool {
+        !self.active && self.particles.is_empty()
+    }
+
+    /// Draws every particle at its whole pixel position. Each emitter draws one kind of
+    /// particle, so raylib batches them all into a single draw call. `texture` is only
+    /// needed for `ParticleLook::Sprite`.
+    pub fn draw(&self, d: &mut impl RaylibDraw, texture: Option<&Texture2D>) {
+        for particle in &self.particles {
+            let at = particle.position.round().as_ivec2();
+            let color = self.config.colors.color(particle);
+            match self.config.look {
+                ParticleLook::Pixel => d.draw_pixel(at.x, at.y, color),
+                ParticleLook::Square(size) => {
+                    d.draw_rectangle(at.x - size / 2, at.y - size / 2, size, size, color)
+                }
+                ParticleLook::Sprite(source) => {
+                    let Some(texture) = texture else {
+                        return;
+                    };
+                    let corner = Vector2::new(
+                        (at.x - source.width as i32 / 2) as f32,
+                        (at.y - source.height as i32 / 2) as f32,
+                    );
+                    d.draw_texture_rec(texture, source, corner, color);
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.config.max_particles {
+            return;
+        }
+        let offset = match self.config.shape {
+            EmitShape::Point => Vec2::ZERO,
+            EmitShape::Line { to } => to * self.random(),
+            EmitShape::Rect { size } => {
+                (Vec2::new(self.random(), self.random()) - Vec2::splat(0.5)) * size
+            }
+            EmitShape::Circle { radius } => {
+                // sqrt spreads them evenly rather than bunched in the middle
+                let distance = radius * self.random().sqrt();
+                Vec2::from_angle(self.random() * std::f32::consts::TAU) * distance
+            }
+            EmitShape::Ring { radius } => {
+                Vec2::from_angle(self.random() * std::f32::consts::TAU) * radius
+            }
+        };
+        let aim = if self.config.radial && offset != Vec2::ZERO {
+            offset.to_angle()
+        } else {
+            self.config.direction
+        };
+        let angle = aim + (self.random() * 2.0 - 1.0) * self.config.spread;
+        let (low, high) = self.config.speed;
+        let speed = low + (high - low) * self.random();
+        let (shortest, longest) = self.config.lifetime;
+        let life = shortest + self.rng.int(longest.saturating_sub(shortest));
+        let phase = self.rng.next_u64() as u32;
+        self.particles.push(Particle {
+            position: self.position + offset,
+            velocity: Vec2::from_angle(angle) * speed,
+            age: 0,
+            life: life.max(1),
+            phase,
+        });
+    }
+
+    /// 0.0 up to 1.0.
+    fn random(&mut self) -> f32 {
+        self.rng.float(1.0)
+    }
+}
+
+/* ----------- system --------------------------------------------------- */
+
+/// A set of emitters stepped and drawn together. One-off bursts are dropped once their
+/// particles die, as is any emitter set inactive.
+pub struct ParticleSystem {
+    pub emitters: Vec<Emitter>,
+    /// Seeds each new emitter, so a replay that adds the same emitters in the same
+    /// order gets the same particles.
+    next_seed: u64,
+}
+
+impl ParticleSystem {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            emitters: Vec::new(),
+            next_seed: seed,
+        }
+    }
+
+    /// Adds a continuous emitter and returns it for tweaking; move it with `position`
+    /// and clear `active` to retire it.
+    pub fn add(&mut self, config: &EmitterConfig, position: Vec2) -> &mut Emitter {
+        let seed = self.seed();
+        self.emitters
+            .push(Emitter::new(config.clone(), position, seed));
+        self.emitters.last_mut().unwrap()
+    }
+
+    /// Fires `count` particles once.
+    pub fn burst(&mut self, config: &EmitterConfig, position: Vec2, count: u32) {
+        let seed = self.seed();
+        let mut emitter = Emitter::new(config.clone(), position, seed);
+        emitter.active = false;
+        emitter.burst(count);
+        self.emitters.push(emitter);
+    }
+
+    pub fn step(&mut self) {
+        for emitter in &mut self.emitters {
+            emitter.step();
+        }
+        self.emitters.retain(|emitter| !emitter.is_idle());
+    }
+
+    /// Particles alive across all emitters.
+    pub fn len(&self) -> usize {
+        self.emitters.iter().map(Emitter::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emitters.iter().all(Emitter::is_empty)
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// Draws shapes first and sprites after, so switching between raylib's shape
+    /// texture and `texture` happens once rather than per emitter.
+    pub fn draw(&self, d: &mut impl RaylibDraw, texture: Option<&Texture2D>) {
+        let is_sprite = |emitter: &&Emitter| matches!(emitter.config.look, ParticleLook::Sprite(_));
+        for emitter in self.emitters.iter().filter(|e| !is_sprite(e)) {
+            emitter.draw(d, texture);
+        }
+        for emitter in self.emitters.iter().filter(is_sprite) {
+            emitter.draw(d, texture);
+        }
+    }
+
+    fn seed(&mut self) -> u64 {
+        self.next_seed = self.next_seed.wrapping_add(1);
+        self.next_seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_handles_any_phase() {
+        let mode = ColorMode::Cycle {
+            colors: vec![Color::RED, Color::GREEN, Color::BLUE],
+            ticks_per_color: 2,
+        };
+        let particle = |age, phase| Particle {
+            position: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            age,
+            life: u32::MAX,
+            phase,
+        };
+        // u32::MAX is a multiple of 3, so it starts on red like phase 0
+        assert_eq!(mode.color(&particle(2, 0)), Color::GREEN);
+        assert_eq!(mode.color(&particle(2, u32::MAX)), Color::GREEN);
+        assert_eq!(mode.color(&particle(u32::MAX - 1, u32::MAX)), Color::GREEN);
+    }
+}
@@ -0,0 +1,405 @@
+//! Dialogue-style text with inline markup, drawn glyph by glyph with a `BitmapFont`.
+//!
+//! Tags: `[color=red]..[/color]` (a name or `#rrggbb[aa]`), `[wave]..[/wave]`,
+//! `[shake]..[/shake]` and `[pause=N]`, which holds the typewriter for N extra ticks before
+//! the next character. `[[` is a literal `[`, and anything else in brackets is left as
+//! text. Effects are driven by `step`, so they look the same at any frame rate.
+//! ```ignore
+//! let mut line = RichText::new(&font, "Hello [color=gold]traveller[/color].[pause=20] [wave]Welcome![/wave]", Some(200))
+//!     .with_typewriter(2);
+//! // step
+//! if line.step() {
+//!     // fully shown
+//! }
+//! // draw
+//! line.draw(d, &font, IVec2::new(8, 120), Color::WHITE);
+//! ```
+
+use glam::IVec2;
+use raylib::prelude::*;
+
+use crate::font::BitmapFont;
+
+#[derive(Debug, Clone, Copy)]
+struct RichGlyph {
+    c: char,
+    color: Option<Color>,
+    wave: bool,
+    shake: bool,
+    /// Extra typewriter ticks before this glyph appears.
+    pause: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reveal {
+    ticks_per_char: u32,
+    revealed: usize,
+    wait: u32,
+    finished: bool,
+}
+
+pub struct RichText {
+    glyphs: Vec<RichGlyph>,
+    /// Pen position of each glyph relative to the text's top left, None for the spaces
+    /// and newlines that wrapping swallowed.
+    positions: Vec<Option<IVec2>>,
+    /// A pause at the very end, held before the typewriter reports it's done.
+    end_pause: u32,
+    size: IVec2,
+    tick: u64,
+    reveal: Option<Reveal>,
+}
+
+impl RichText {
+    /// Parses `markup` and lays it out with `font`, wrapped to `wrap_width` if given.
+    /// Everything is shown straight away unless `with_typewriter` is used.
+    pub fn new(font: &BitmapFont, markup: &str, wrap_width: Option<i32>) -> Self {
+        let (glyphs, end_pause) = parse(markup);
+        let plain: String = glyphs.iter().map(|glyph| glyph.c).collect();
+        let lines = match wrap_width {
+            Some(width) => font.wrap(&plain, width),
+            None => plain.split('\n').map(String::from).collect(),
+        };
+
+        // wrapping only ever drops the spaces and newlines it breaks lines at, so walk
+        // the lines and the source together, skipping whatever isn't in the lines
+        let mut positions = vec![None; glyphs.len()];
+        let mut source = plain.chars().enumerate();
+        let mut width = 0;
+        for (row, line) in lines.iter().enumerate() {
+            let mut pen = IVec2::new(0, row as i32 * font.line_height);
+            let mut prev = None;
+            for c in line.chars() {
+                let Some(index) = source.by_ref().find(|&(_, s)| s == c).map(|(i, _)| i) else {
+                    break;
+                };
+                if let Some(prev) = prev {
+                    pen.x += font.kerning(prev, c) + font.letter_spacing;
+                }
+                positions[index] = Some(pen);
+                pen.x += font.glyph(c).map_or(0, |glyph| glyph.advance);
+                prev = Some(c);
+            }
+            width = width.max(pen.x);
+        }
+
+        Self {
+            glyphs,
+            positions,
+            end_pause,
+            size: IVec2::new(width, lines.len() as i32 * font.line_height),
+            tick: 0,
+            reveal: None,
+        }
+    }
+
+    /// Reveals one character every `ticks_per_char` steps instead of all at once.
+    pub fn with_typewriter(mut self, ticks_per_char: u32) -> Self {
+        let ticks_per_char = ticks_per_char.max(1);
+        self.reveal = Some(Reveal {
+            ticks_per_char,
+            revealed: 0,
+            wait: self.pause_before(0),
+            finished: false,
+        });
+        self
+    }
+
+    /// Advances effects and the typewriter by one tick. True on the tick the typewriter
+    /// finishes, including any pause at the end.
+    pub fn step(&mut self) -> bool {
+        self.tick += 1;
+        let Some(mut reveal) = self.reveal else {
+            return false;
+        };
+        let mut just_finished = false;
+        if reveal.finished {
+            // nothing left to do
+        } else if reveal.wait > 0 {
+            reveal.wait -= 1;
+        } else if reveal.revealed < self.glyphs.len() {
+            reveal.revealed += 1;
+            reveal.wait = reveal.ticks_per_char - 1 + self.pause_before(reveal.revealed);
+        } else {
+            reveal.finished = true;
+            just_finished = true;
+        }
+        self.reveal = Some(reveal);
+        just_finished
+    }
+
+    /// Shows everything now, e.g. when the player presses a button mid-line. The next
+    /// `step` reports the finish.
+    pub fn skip(&mut self) {
+        if let Some(reveal) = &mut self.reveal {
+            reveal.revealed = self.glyphs.len();
+            reveal.wait = 0;
+        }
+    }
+
+    /// Always true without a typewriter.
+    pub fn is_finished(&self) -> bool {
+        self.reveal.is_none_or(|reveal| reveal.finished)
+    }
+
+    /// Number of characters showing.
+    pub fn revealed(&self) -> usize {
+        self.reveal
+            .map_or(self.glyphs.len(), |reveal| reveal.revealed)
+    }
+
+    /// Size of the whole laid out text in canvas pixels, revealed or not.
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    /// The text without its tags.
+    pub fn plain_text(&self) -> String {
+        self.glyphs.iter().map(|glyph| glyph.c).collect()
+    }
+
+    /// Draws the revealed glyphs with the text's top left at `pos`, in `color` unless
+    /// a tag says otherwise.
+    pub fn draw(&self, d: &mut impl RaylibDraw, font: &BitmapFont, pos: IVec2, color: Color) {
+        let revealed = self.revealed();
+        for (i, (glyph, at)) in self.glyphs.iter().zip(&self.positions).enumerate() {
+            if i >= revealed {
+                break;
+            }
+            let Some(at) = at else {
+                continue;
+            };
+            let mut offset = IVec2::ZERO;
+            if glyph.wave {
+                let phase = self.tick as f32 * 0.15 + i as f32 * 0.6;
+                offset.y += (phase.sin() * 2.0).round() as i32;
+            }
+            if glyph.shake {
+                // a new offset every third tick reads as shaking rather than noise
+                let h = hash(self.tick / 3, i as u64);
+                offset += IVec2::new((h % 3) as i32 - 1, ((h / 3) % 3) as i32 - 1);
+            }
+            font.draw_char(d, glyph.c, pos + *at + offset, glyph.color.unwrap_or(color));
+        }
+    }
+
+    fn pause_before(&self, index: usize) -> u32 {
+        self.glyphs
+            .get(index)
+            .map_or(self.end_pause, |glyph| glyph.pause)
+    }
+}
+
+fn hash(a: u64, b: u64) -> u64 {
+    let mut x = a.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ b.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^= x >> 31;
+    x = x.wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 29)
+}
+
+/// Splits markup into styled glyphs, plus any pause left over at the end.
+fn parse(markup: &str) -> (Vec<RichGlyph>, u32) {
+    let mut glyphs = Vec::new();
+    let mut colors: Vec<Color> = Vec::new();
+    let mut wave = 0u32;
+    let mut shake = 0u32;
+    let mut pause = 0;
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("[[") {
+            rest = after;
+        } else if c == '[' {
+            if let Some(end) = rest.find(']') {
+                let tag = &rest[1..end];
+                let (name, value) = tag.split_once('=').unwrap_or((tag, ""));
+                let known = match (name.trim(), value.trim()) {
+                    ("color", value) => match parse_color(value) {
+                        Some(color) => {
+                            colors.push(color);
+                            true
+                        }
+                        None => false,
+                    },
+                    ("/color", _) => colors.pop().is_some(),
+                    ("wave", _) => {
+                        wave += 1;
+                        true
+                    }
+                    ("/wave", _) => {
+                        wave = wave.saturating_sub(1);
+                        true
+                    }
+                    ("shake", _) => {
+                        shake += 1;
+                        true
+                    }
+                    ("/shake", _) => {
+                        shake = shake.saturating_sub(1);
+                        true
+                    }
+                    ("pause", value) => match value.parse::<u32>() {
+                        Ok(ticks) => {
+                            pause += ticks;
+                            true
+                        }
+                        Err(_) => false,
+                    },
+                    _ => false,
+                };
+                if known {
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+            rest = &rest[1..];
+        } else {
+            rest = &rest[c.len_utf8()..];
+        }
+        glyphs.push(RichGlyph {
+            c,
+            color: colors.last().copied(),
+            wave: wave > 0,
+            shake: shake > 0,
+            pause: std::mem::take(&mut pause),
+        });
+    }
+    (glyphs, pause)
+}
+
+/// A raylib color name in lower case, or `#rrggbb` / `#rrggbbaa`.
+fn parse_color(s: &str) -> Option<Color> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16).ok()?;
+        let [r, g, b, a] = match hex.len() {
+            6 => ((value << 8) | 0xff).to_be_bytes(),
+            8 => value.to_be_bytes(),
+            _ => return None,
+        };
+        return Some(Color::new(r, g, b, a));
+    }
+    Some(match s.to_ascii_lowercase().as_str() {
+        "white" => Color::WHITE,
+        "black" => Color::BLACK,
+        "gray" | "grey" => Color::GRAY,
+        "lightgray" | "lightgrey" => Color::LIGHTGRAY,
+        "darkgray" | "darkgrey" => Color::DARKGRAY,
+        "red" => Color::RED,
+        "maroon" => Color::MAROON,
+        "orange" => Color::ORANGE,
+        "yellow" => Color::YELLOW,
+        "gold" => Color::GOLD,
+        "green" => Color::GREEN,
+        "lime" => Color::LIME,
+        "darkgreen" => Color::DARKGREEN,
+        "skyblue" => Color::SKYBLUE,
+        "blue" => Color::BLUE,
+        "darkblue" => Color::DARKBLUE,
+        "purple" => Color::PURPLE,
+        "violet" => Color::VIOLET,
+        "pink" => Color::PINK,
+        "magenta" => Color::MAGENTA,
+        "beige" => Color::BEIGE,
+        "brown" => Color::BROWN,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(markup: &str) -> String {
+        parse(markup).0.iter().map(|glyph| glyph.c).collect()
+    }
+
+    fn rgba(color: Option<Color>) -> Option<[u8; 4]> {
+        color.map(|c| [c.r, c.g, c.b, c.a])
+    }
+
+    /// Laid out without a font; the typewriter only cares about the glyphs.
+    fn typewriter(markup: &str, ticks_per_char: u32) -> RichText {
+        let (glyphs, end_pause) = parse(markup);
+        RichText {
+            positions: vec![None; glyphs.len()],
+            glyphs,
+            end_pause,
+            size: IVec2::ZERO,
+            tick: 0,
+            reveal: None,
+        }
+        .with_typewriter(ticks_per_char)
+    }
+
+    #[test]
+    fn double_bracket_is_a_literal_bracket() {
+        assert_eq!(plain("a[[b]"), "a[b]");
+        assert_eq!(plain("[[color=red]x"), "[color=red]x");
+    }
+
+    #[test]
+    fn unknown_tags_stay_as_text() {
+        assert_eq!(plain("[foo]x[/foo]"), "[foo]x[/foo]");
+        assert_eq!(plain("[color=nope]x"), "[color=nope]x");
+        assert_eq!(plain("[pause=soon]x"), "[pause=soon]x");
+        assert_eq!(plain("x[/color]"), "x[/color]");
+        assert_eq!(plain("unclosed [wave"), "unclosed [wave");
+    }
+
+    #[test]
+    fn colors_nest() {
+        let (glyphs, _) = parse("[color=red]a[color=#00ff0080]b[/color]c[/color]d");
+        let colors: Vec<_> = glyphs.iter().map(|glyph| rgba(glyph.color)).collect();
+        let red = rgba(Some(Color::RED));
+        assert_eq!(colors, [red, Some([0, 255, 0, 128]), red, None]);
+    }
+
+    #[test]
+    fn effects_and_pauses_attach_to_the_next_glyph() {
+        let (glyphs, end_pause) = parse("[wave]a[/wave][pause=3][pause=2]b[shake]c");
+        assert!(glyphs[0].wave && !glyphs[1].wave);
+        assert_eq!(
+            glyphs.iter().map(|glyph| glyph.pause).collect::<Vec<_>>(),
+            [0, 5, 0]
+        );
+        assert!(glyphs[2].shake);
+        assert_eq!(end_pause, 0);
+    }
+
+    #[test]
+    fn pause_at_the_end_is_kept_separately() {
+        let (glyphs, end_pause) = parse("hi[pause=5]");
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(end_pause, 5);
+    }
+
+    #[test]
+    fn typewriter_finishes_once_after_the_end_pause() {
+        let mut text = typewriter("abc[pause=4]", 2);
+        let mut last_reveal = None;
+        let mut finishes = Vec::new();
+        for tick in 1..=40 {
+            let before = text.revealed();
+            if text.step() {
+                finishes.push(tick);
+            }
+            if text.revealed() == 3 && before < 3 {
+                last_reveal = Some(tick);
+            }
+        }
+        // the last glyph still gets its own ticks_per_char before the end pause
+        let last_reveal = last_reveal.expect("never fully revealed");
+        assert_eq!(finishes, [last_reveal + 2 + 4]);
+        assert!(text.is_finished());
+    }
+
+    #[test]
+    fn skip_reveals_everything_and_finishes_on_the_next_step() {
+        let mut text = typewriter("hello[pause=30]", 3);
+        text.step();
+        text.skip();
+        assert_eq!(text.revealed(), 5);
+        assert!(!text.is_finished());
+        assert!(text.step());
+        assert!(!text.step());
+    }
+}
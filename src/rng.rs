@@ -0,0 +1,69 @@
+//! A small seeded random number generator for gameplay and effects.
+//!
+//! It's a xorshift, so the same seed gives the same numbers on every run and platform
+//! (replays, sfxr presets) and nothing needs raylib's handle.
+//! ```ignore
+//! let mut rng = Rng::new(seed);
+//! let angle = rng.float(std::f32::consts::TAU);
+//! let damage = 2 + rng.int(3);
+//! if rng.flip() { .. }
+//! ```
+
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Any seed works, zero included; nearby seeds still give unrelated sequences.
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// 0.0 up to `range`.
+    pub fn float(&mut self, range: f32) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * range
+    }
+
+    /// 0 to `max` inclusive.
+    pub fn int(&mut self, max: u32) -> u32 {
+        (self.next_u64() % (max as u64 + 1)) as u32
+    }
+
+    pub fn flip(&mut self) -> bool {
+        self.int(1) == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+        let first: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..8).map(|_| c.next_u64()).collect::<Vec<_>>());
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+
+    #[test]
+    fn stays_in_range() {
+        let mut rng = Rng::new(3);
+        let mut seen = [false; 4];
+        for _ in 0..1000 {
+            let f = rng.float(2.5);
+            assert!((0.0..=2.5).contains(&f));
+            seen[rng.int(3) as usize] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
+}
@@ -0,0 +1,436 @@
+//! Save slots on disk for anything serde can serialize.
+//!
+//! A save is a one line JSON header (format version, payload length and CRC-32) followed
+//! by the payload as JSON. Writes go to a temporary file that's renamed over the slot,
+//! so a crash mid-save leaves the previous save intact, and a damaged or truncated file
+//! is reported rather than half loaded. Saves from older versions are brought up to date
+//! by migrations, one version step at a time, before deserializing.
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct SaveData { level: u32, lives: u32, best_time: f32 }
+//!
+//! let saves = SaveSlots::<SaveData>::new("my-sketch", 2)
+//!     // version 1 called it "time"
+//!     .migration(1, |mut data| {
+//!         data["best_time"] = data["time"].take();
+//!         Ok(data)
+//!     });
+//! saves.save("slot1", &data)?;
+//! let data = saves.load("slot1")?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const MAGIC: &str = "lowres-save";
+const EXTENSION: &str = "sav";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Slot names are letters, digits, spaces, `-`, `_` and `.`, and don't start with `.`.
+    InvalidSlot(String),
+    /// Not a save file, or damaged: bad header, wrong length or checksum mismatch.
+    Corrupt(String),
+    /// Written by a newer version than this one knows about.
+    TooNew {
+        version: u32,
+        current: u32,
+    },
+    /// A migration failed, or one is missing for a version in between.
+    Migration {
+        from: u32,
+        message: String,
+    },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Json(e) => write!(f, "{}", e),
+            SaveError::InvalidSlot(slot) => write!(f, "{:?} isn't a valid slot name", slot),
+            SaveError::Corrupt(msg) => write!(f, "corrupt save: {}", msg),
+            SaveError::TooNew { version, current } => write!(
+                f,
+                "save is version {} but this build only reads up to {}",
+                version, current
+            ),
+            SaveError::Migration { from, message } => {
+                write!(f, "migrating from version {}: {}", from, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Json(e)
+    }
+}
+
+fn corrupt(msg: impl Into<String>) -> SaveError {
+    SaveError::Corrupt(msg.into())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    magic: String,
+    version: u32,
+    length: usize,
+    /// CRC-32 of the payload bytes.
+    checksum: u32,
+}
+
+type Migration = Box<dyn Fn(Value) -> Result<Value, String>>;
+
+/// Where saves go: `$XDG_DATA_HOME/<app>/saves`, falling back to
+/// `~/.local/share/<app>/saves`, on Linux; the usual per-user app data folder on
+/// Windows and macOS; `./saves` if none of that can be worked out.
+pub fn save_dir(app_name: &str) -> PathBuf {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    let home = || env_dir("HOME");
+    let base = if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        env_dir("XDG_DATA_HOME").or_else(|| home().map(|home| home.join(".local/share")))
+    };
+    match base {
+        Some(base) => base.join(app_name).join("saves"),
+        None => PathBuf::from("saves"),
+    }
+}
+
+/// Named save files of type `T` in one directory, written at `version`.
+pub struct SaveSlots<T> {
+    dir: PathBuf,
+    version: u32,
+    /// Keyed by the version they upgrade from.
+    migrations: BTreeMap<u32, Migration>,
+    _data: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> SaveSlots<T> {
+    /// Slots in the platform save directory for `app_name`.
+    pub fn new(app_name: &str, version: u32) -> Self {
+        Self::in_dir(save_dir(app_name), version)
+    }
+
+    pub fn in_dir(dir: impl Into<PathBuf>, version: u32) -> Self {
+        Self {
+            dir: dir.into(),
+            version,
+            migrations: BTreeMap::new(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Upgrades the JSON of a version `from` save to version `from + 1`.
+    pub fn migration(
+        mut self,
+        from: u32,
+        migrate: impl Fn(Value) -> Result<Value, String> + 'static,
+    ) -> Self {
+        self.migrations.insert(from, Box::new(migrate));
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The file for `slot`, which has to be a plain name so it stays in `dir`.
+    pub fn path(&self, slot: &str) -> Result<PathBuf, SaveError> {
+        let valid = !slot.is_empty()
+            && !slot.starts_with('.')
+            && slot
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'));
+        if !valid {
+            return Err(SaveError::InvalidSlot(slot.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", slot, EXTENSION)))
+    }
+
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).is_ok_and(|path| path.is_file())
+    }
+
+    /// Slot names present on disk, sorted.
+    pub fn list(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut slots: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        slots.sort();
+        slots
+    }
+
+    pub fn delete(&self, slot: &str) -> Result<(), SaveError> {
+        match fs::remove_file(self.path(slot)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes `data` to `slot`, replacing the old save only once the new one is fully
+    /// on disk.
+    pub fn save(&self, slot: &str, data: &T) -> Result<(), SaveError> {
+        let payload = serde_json::to_vec_pretty(data)?;
+        let header = Header {
+            magic: MAGIC.to_string(),
+            version: self.version,
+            length: payload.len(),
+            checksum: crc32(&payload),
+        };
+
+        let path = self.path(slot)?;
+        fs::create_dir_all(&self.dir)?;
+        let temp = path.with_extension(format!("{}.tmp", EXTENSION));
+        let mut file = fs::File::create(&temp)?;
+        serde_json::to_writer(&mut file, &header)?;
+        file.write_all(b"\n")?;
+        file.write_all(&payload)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, &path)?;
+        // the rename is only sure to survive a crash once the directory is on disk too
+        #[cfg(unix)]
+        fs::File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+
+    /// Reads `slot`, checking it and migrating it from older versions first.
+    pub fn load(&self, slot: &str) -> Result<T, SaveError> {
+        let bytes = fs::read(self.path(slot)?)?;
+        let newline = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| corrupt("no header"))?;
+        let header: Header = serde_json::from_slice(&bytes[..newline])
+            .map_err(|e| corrupt(format!("bad header: {}", e)))?;
+        if header.magic != MAGIC {
+            return Err(corrupt("not a save file"));
+        }
+        let payload = &bytes[newline + 1..];
+        if payload.len() != header.length {
+            return Err(corrupt(format!(
+                "expected {} bytes of data, found {}",
+                header.length,
+                payload.len()
+            )));
+        }
+        if crc32(payload) != header.checksum {
+            return Err(corrupt("checksum mismatch"));
+        }
+        if header.version > self.version {
+            return Err(SaveError::TooNew {
+                version: header.version,
+                current: self.version,
+            });
+        }
+
+        let mut data: Value = serde_json::from_slice(payload)?;
+        for from in header.version..self.version {
+            let migrate = self.migrations.get(&from).ok_or(SaveError::Migration {
+                from,
+                message: "no migration registered".to_string(),
+            })?;
+            data = migrate(data).map_err(|message| SaveError::Migration { from, message })?;
+        }
+        Ok(serde_json::from_value(data)?)
+    }
+}
+
+/// CRC-32 (IEEE), bit at a time; saves are small.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A fresh directory under the system temp dir, removed again on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("lowres-save-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Data {
+        level: u32,
+        best_time: f32,
+        lives: u32,
+    }
+
+    const DATA: Data = Data {
+        level: 3,
+        best_time: 41.5,
+        lives: 2,
+    };
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn saves_load_list_and_delete() {
+        let dir = TempDir::new("round-trip");
+        let saves = SaveSlots::<Data>::in_dir(&dir.0, 1);
+        saves.save("b", &DATA).unwrap();
+        saves.save("a.2", &DATA).unwrap();
+        assert_eq!(saves.load("b").unwrap(), DATA);
+        assert_eq!(saves.list(), ["a.2", "b"]);
+
+        saves.delete("b").unwrap();
+        saves.delete("b").unwrap();
+        assert!(!saves.exists("b"));
+        assert_eq!(saves.list(), ["a.2"]);
+    }
+
+    #[test]
+    fn slot_names_stay_in_the_directory() {
+        let saves = SaveSlots::<Data>::in_dir("saves", 1);
+        assert_eq!(saves.path("a.b").unwrap(), Path::new("saves/a.b.sav"));
+        assert_eq!(saves.path("slot 1").unwrap(), Path::new("saves/slot 1.sav"));
+        for slot in ["", ".", "..", "../x", "a/b", "a\\b", ".hidden", "/abs"] {
+            assert!(
+                matches!(saves.path(slot), Err(SaveError::InvalidSlot(_))),
+                "{:?}",
+                slot
+            );
+        }
+        assert!(matches!(
+            saves.save("../x", &DATA),
+            Err(SaveError::InvalidSlot(_))
+        ));
+    }
+
+    #[test]
+    fn damaged_saves_are_corrupt() {
+        let dir = TempDir::new("corrupt");
+        let saves = SaveSlots::<Data>::in_dir(&dir.0, 1);
+        saves.save("slot", &DATA).unwrap();
+        let path = saves.path("slot").unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let is_corrupt = |bytes: &[u8]| {
+            fs::write(&path, bytes).unwrap();
+            matches!(saves.load("slot"), Err(SaveError::Corrupt(_)))
+        };
+
+        assert!(is_corrupt(&bytes[..bytes.len() - 3]));
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 2;
+        flipped[last] ^= 0x01;
+        assert!(is_corrupt(&flipped));
+        assert!(is_corrupt(b"{\"level\": 3}"));
+        assert!(is_corrupt(b"not a save\n{}"));
+        assert!(!is_corrupt(&bytes));
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_refused() {
+        let dir = TempDir::new("too-new");
+        SaveSlots::<Data>::in_dir(&dir.0, 3)
+            .save("slot", &DATA)
+            .unwrap();
+        let result = SaveSlots::<Data>::in_dir(&dir.0, 2).load("slot");
+        assert!(matches!(
+            result,
+            Err(SaveError::TooNew {
+                version: 3,
+                current: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn migrations_run_in_order_up_to_the_current_version() {
+        let dir = TempDir::new("migrate");
+        SaveSlots::<Value>::in_dir(&dir.0, 1)
+            .save("slot", &json!({ "level": 3, "time": 41.5 }))
+            .unwrap();
+
+        let saves = SaveSlots::<Data>::in_dir(&dir.0, 3)
+            .migration(2, |mut data| {
+                data["lives"] = json!(2);
+                Ok(data)
+            })
+            .migration(1, |mut data| {
+                data["best_time"] = data["time"].take();
+                Ok(data)
+            });
+        assert_eq!(saves.load("slot").unwrap(), DATA);
+
+        // a gap in the chain is an error, not a half-migrated save
+        let missing = SaveSlots::<Data>::in_dir(&dir.0, 3).migration(1, Ok);
+        assert!(matches!(
+            missing.load("slot"),
+            Err(SaveError::Migration { from: 2, .. })
+        ));
+        let failing = SaveSlots::<Data>::in_dir(&dir.0, 2).migration(1, |_| Err("nope".into()));
+        assert!(matches!(
+            failing.load("slot"),
+            Err(SaveError::Migration { from: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+//! A stack of scenes (title, gameplay, pause, ...) sharing the sketch's input/step/draw
+//! hooks. Scenes change the stack by returning a [`SceneCommand`] from `step`.
+//!
+//! Only the top scene gets input and steps. Drawing starts from the highest scene that
+//! isn't an overlay, so a pause menu draws on top of the frozen gameplay underneath it.
+//! `C` is whatever outlives individual scenes: scores, settings, loaded assets.
+//! Transitions are played by the stack, see [`crate::transition`].
+
+use glam::UVec2;
+use raylib::prelude::*;
+
+use crate::egui_raylib::InputFilter;
+use crate::layers::Layers;
+use crate::transition::{Transition, TransitionRenderer};
+
+pub trait Scene<C> {
+    fn name(&self) -> &str;
+
+    /// Called once per frame before stepping. Record what was pressed; act on it in `step`.
+    fn input(&mut self, _rl: &mut RaylibHandle, _game: &mut C, _input: &InputFilter) {}
+
+    /// Called once per tick while this is the top scene.
+    fn step(&mut self, rl: &mut RaylibHandle, rlt: &RaylibThread, game: &mut C) -> SceneCommand<C>;
+
+    fn draw(&self, d: &mut RaylibDrawHandle, rlt: &RaylibThread, layers: &mut Layers, game: &C);
+
+    /// Overlays let the scene below keep drawing underneath them.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Called when the scene above this one is popped.
+    fn resume(&mut self, _game: &mut C) {}
+}
+
+pub enum SceneCommand<C> {
+    Stay,
+    Push(Box<dyn Scene<C>>),
+    Pop,
+    /// Swaps the top scene.
+    Replace(Box<dyn Scene<C>>),
+    /// Clears the stack down to just this scene.
+    ReplaceAll(Box<dyn Scene<C>>),
+    /// Empties the stack, which ends the run loop.
+    Quit,
+    /// Applies the command, then plays `transition` from the last frame of the old scenes
+    /// to the new ones.
+    Transition(Transition, Box<SceneCommand<C>>),
+}
+
+impl<C> SceneCommand<C> {
+    pub fn with_transition(self, transition: Transition) -> Self {
+        SceneCommand::Transition(transition, Box::new(self))
+    }
+}
+
+struct ActiveTransition {
+    transition: Transition,
+    tick: u32,
+}
+
+impl ActiveTransition {
+    fn progress(&self) -> f32 {
+        self.tick as f32 / self.transition.ticks.max(1) as f32
+    }
+}
+
+pub struct SceneStack<C> {
+    scenes: Vec<Box<dyn Scene<C>>>,
+    transition: Option<ActiveTransition>,
+    // created on the first draw, once the canvas size is known
+    renderer: Option<TransitionRenderer>,
+    // canvas size the renderer couldn't be created at; transitions are skipped until it changes
+    renderer_failed: Option<UVec2>,
+}
+
+impl<C> SceneStack<C> {
+    pub fn new(initial: Box<dyn Scene<C>>) -> Self {
+        Self {
+            scenes: vec![initial],
+            transition: None,
+            renderer: None,
+            renderer_failed: None,
+        }
+    }
+
+    /// True once something returned `Quit` or popped the last scene.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn top(&self) -> Option<&dyn Scene<C>> {
+        self.scenes.last().map(|scene| scene.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scenes.iter().map(|scene| scene.name())
+    }
+
+    pub fn input(&mut self, rl: &mut RaylibHandle, game: &mut C, input: &InputFilter) {
+        if self.transition.is_some() {
+            return;
+        }
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.input(rl, game, input);
+        }
+    }
+
+    /// Advances a running transition, or steps the top scene and applies its command.
+    /// Scenes are frozen while a transition plays.
+    pub fn step(&mut self, rl: &mut RaylibHandle, rlt: &RaylibThread, game: &mut C) {
+        if let Some(active) = &mut self.transition {
+            active.tick += 1;
+            if active.tick >= active.transition.ticks {
+                self.transition = None;
+            }
+            return;
+        }
+
+        if let Some(scene) = self.scenes.last_mut() {
+            let command = scene.step(rl, rlt, game);
+            self.apply(command, game);
+        }
+    }
+
+    /// Draws the visible scenes bottom first, then blends any transition into the canvas.
+    pub fn draw(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        layers: &mut Layers,
+        game: &C,
+    ) {
+        let first_visible = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in &self.scenes[first_visible..] {
+            scene.draw(d, rlt, layers, game);
+        }
+
+        let (canvas, dims) = (layers.canvas().name, layers.canvas().texture_dims());
+        if self.renderer.as_ref().map(|r| r.dims()) != Some(dims)
+            && self.renderer_failed != Some(dims)
+        {
+            self.renderer = match TransitionRenderer::new(d, rlt, dims) {
+                Ok(renderer) => {
+                    self.renderer_failed = None;
+                    Some(renderer)
+                }
+                Err(e) => {
+                    crate::editor::log(format!("Transitions disabled: {}", e));
+                    self.renderer_failed = Some(dims);
+                    self.transition = None;
+                    None
+                }
+            };
+        }
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        let canvas = &mut layers.get_mut(canvas).render_texture;
+        match &self.transition {
+            // every idle frame might be the last one before a transition starts
+            None => renderer.capture_outgoing(d, rlt, canvas),
+            Some(active) => {
+                renderer.blend_into(d, rlt, canvas, &active.transition, active.progress())
+            }
+        }
+    }
+
+    fn apply(&mut self, command: SceneCommand<C>, game: &mut C) {
+        match command {
+            SceneCommand::Stay => {}
+            SceneCommand::Push(scene) => self.scenes.push(scene),
+            SceneCommand::Pop => {
+                self.scenes.pop();
+                if let Some(scene) = self.scenes.last_mut() {
+                    scene.resume(game);
+                }
+            }
+            SceneCommand::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            SceneCommand::ReplaceAll(scene) => {
+                self.scenes.clear();
+                self.scenes.push(scene);
+            }
+            SceneCommand::Quit => self.scenes.clear(),
+            SceneCommand::Transition(transition, command) => {
+                self.apply(*command, game);
+                if transition.ticks > 0 && self.renderer_failed.is_none() {
+                    self.transition = Some(ActiveTransition {
+                        transition,
+                        tick: 0,
+                    });
+                }
+            }
+        }
+    }
+}
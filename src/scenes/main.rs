@@ -0,0 +1,61 @@
+use glam::UVec2;
+use lowres::audio::{self, Audio};
+use lowres::egui_raylib::InputFilter;
+use lowres::layers::Layers;
+use lowres::scene::SceneStack;
+use lowres::timestep::Timestep;
+use raylib::prelude::*;
+use raylib::{ffi::SetTraceLogLevel, prelude::TraceLogLevel};
+
+mod scenes;
+
+fn main() {
+    let mut timestep = Timestep::new(scenes::FRAMES_PER_SECOND);
+    lowres::display::request_highdpi();
+    let (mut rl, rlt) = raylib::init()
+        .size(1280, 720)
+        .title("raylib-rs-lowres-scenes")
+        .build();
+    unsafe {
+        SetTraceLogLevel(TraceLogLevel::LOG_WARNING as i32);
+    }
+    let audio_device = audio::open_device();
+    // after the audio device so it's dropped first; it borrows the device for sound
+    let mut game = scenes::Game::new(Audio::new(audio_device.as_ref()));
+    let mut scene_stack = SceneStack::new(Box::new(scenes::Title::new()));
+    // escape pauses and backs out of menus instead of closing the window
+    rl.set_exit_key(None);
+
+    let window_dims = UVec2::new(1280, 720);
+    let dims = UVec2::new(240, 160);
+    let mut layers = Layers::new(window_dims);
+    for (name, layer_dims, clear_color) in [
+        (scenes::WORLD, dims, Color::BLACK),
+        (scenes::HUD, window_dims, Color::BLANK),
+    ] {
+        layers
+            .add(&mut rl, &rlt, name, layer_dims, clear_color)
+            .unwrap_or_else(|e| {
+                println!("Error creating render texture: {}", e);
+                std::process::exit(1);
+            });
+    }
+    layers.set_mouse_layer(&mut rl, scenes::WORLD);
+
+    // no egui here, so nothing ever captures input
+    let input = InputFilter::default();
+
+    while !scene_stack.is_empty() && !rl.window_should_close() {
+        scene_stack.input(&mut rl, &mut game, &input);
+
+        game.audio.update(rl.get_frame_time());
+        for _ in 0..timestep.advance(rl.get_frame_time()) {
+            scene_stack.step(&mut rl, &rlt, &mut game);
+        }
+
+        let mut draw_handle = rl.begin_drawing(&rlt);
+        layers.clear(&mut draw_handle, &rlt);
+        scene_stack.draw(&mut draw_handle, &rlt, &mut layers, &game);
+        layers.composite(&mut draw_handle, false);
+    }
+}
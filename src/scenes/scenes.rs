@@ -0,0 +1,338 @@
+use glam::Vec2;
+use lowres::audio::Audio;
+use lowres::egui_raylib::InputFilter;
+use lowres::layers::Layers;
+use lowres::scene::{Scene, SceneCommand};
+use lowres::sfxr::SfxrParams;
+use lowres::transition::{Transition, TransitionKind};
+use raylib::prelude::*;
+
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+// render layers, composited bottom to top in the order main adds them
+pub const WORLD: &str = "world";
+pub const HUD: &str = "hud";
+
+const ROUND_TICKS: u32 = 20 * FRAMES_PER_SECOND;
+
+const TRANSITION_TICKS: u32 = 40;
+
+/// Lives across scenes.
+pub struct Game<'a> {
+    pub high_score: u32,
+    pub last_score: u32,
+    pub audio: Audio<'a>,
+}
+
+impl<'a> Game<'a> {
+    pub fn new(mut audio: Audio<'a>) -> Self {
+        if let Err(e) = SfxrParams::coin(7).load_into(&mut audio, "coin", 2) {
+            lowres::editor::log(format!("coin sound: {}", e));
+        }
+        Self {
+            high_score: 0,
+            last_score: 0,
+            audio,
+        }
+    }
+}
+
+fn draw_centered(
+    d: &mut RaylibTextureMode<RaylibDrawHandle>,
+    text: &str,
+    y: i32,
+    size: i32,
+    color: Color,
+) {
+    let width = d.measure_text(text, size);
+    d.draw_text(text, (240 - width) / 2, y, size, color);
+}
+
+/* ----------- title --------------------------------------------------- */
+
+pub struct Title {
+    ticks: u32,
+    start: bool,
+    quit: bool,
+}
+
+impl Title {
+    pub fn new() -> Self {
+        Self {
+            ticks: 0,
+            start: false,
+            quit: false,
+        }
+    }
+}
+
+impl<'a> Scene<Game<'a>> for Title {
+    fn name(&self) -> &str {
+        "title"
+    }
+
+    fn input(&mut self, rl: &mut RaylibHandle, _game: &mut Game<'a>, input: &InputFilter) {
+        self.start |= input.is_key_pressed(rl, KeyboardKey::KEY_ENTER);
+        self.quit |= input.is_key_pressed(rl, KeyboardKey::KEY_ESCAPE);
+    }
+
+    fn step(
+        &mut self,
+        _rl: &mut RaylibHandle,
+        _rlt: &RaylibThread,
+        _game: &mut Game<'a>,
+    ) -> SceneCommand<Game<'a>> {
+        self.ticks += 1;
+        if self.quit {
+            SceneCommand::Quit
+        } else if self.start {
+            self.start = false;
+            SceneCommand::Replace(Box::new(Gameplay::new())).with_transition(Transition::new(
+                TransitionKind::Iris(Color::BLACK),
+                TRANSITION_TICKS,
+            ))
+        } else {
+            SceneCommand::Stay
+        }
+    }
+
+    fn draw(
+        &self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        layers: &mut Layers,
+        game: &Game<'a>,
+    ) {
+        let blink = (self.ticks / 30) % 2 == 0;
+        layers.draw(d, rlt, WORLD, |d| {
+            draw_centered(d, "COLLECT", 40, 20, Color::GOLD);
+            if blink {
+                draw_centered(d, "press enter", 100, 10, Color::RAYWHITE);
+            }
+            draw_centered(
+                d,
+                &format!("best {}", game.high_score),
+                140,
+                10,
+                Color::GRAY,
+            );
+        });
+    }
+}
+
+/* ----------- gameplay ------------------------------------------------ */
+
+pub struct Gameplay {
+    player: Vec2,
+    coin: Vec2,
+    score: u32,
+    ticks_left: u32,
+    direction: Vec2,
+    pause: bool,
+}
+
+impl Gameplay {
+    pub fn new() -> Self {
+        Self {
+            player: Vec2::new(120.0, 80.0),
+            coin: Vec2::new(40.0, 40.0),
+            score: 0,
+            ticks_left: ROUND_TICKS,
+            direction: Vec2::ZERO,
+            pause: false,
+        }
+    }
+}
+
+impl<'a> Scene<Game<'a>> for Gameplay {
+    fn name(&self) -> &str {
+        "gameplay"
+    }
+
+    fn input(&mut self, rl: &mut RaylibHandle, _game: &mut Game<'a>, input: &InputFilter) {
+        let mut direction = Vec2::ZERO;
+        for (key, step) in [
+            (KeyboardKey::KEY_LEFT, Vec2::NEG_X),
+            (KeyboardKey::KEY_RIGHT, Vec2::X),
+            (KeyboardKey::KEY_UP, Vec2::NEG_Y),
+            (KeyboardKey::KEY_DOWN, Vec2::Y),
+        ] {
+            if input.is_key_down(rl, key) {
+                direction += step;
+            }
+        }
+        self.direction = direction.normalize_or_zero();
+        self.pause |= input.is_key_pressed(rl, KeyboardKey::KEY_ESCAPE)
+            || input.is_key_pressed(rl, KeyboardKey::KEY_P);
+    }
+
+    fn step(
+        &mut self,
+        rl: &mut RaylibHandle,
+        _rlt: &RaylibThread,
+        game: &mut Game<'a>,
+    ) -> SceneCommand<Game<'a>> {
+        if self.pause {
+            self.pause = false;
+            return SceneCommand::Push(Box::new(Pause::new()));
+        }
+
+        self.player =
+            (self.player + self.direction * 1.5).clamp(Vec2::ZERO, Vec2::new(240.0, 160.0));
+        if self.player.distance(self.coin) < 8.0 {
+            self.score += 1;
+            game.audio.play("coin");
+            self.coin = Vec2::new(
+                rl.get_random_value::<i32>(8..232) as f32,
+                rl.get_random_value::<i32>(8..152) as f32,
+            );
+        }
+
+        self.ticks_left -= 1;
+        if self.ticks_left == 0 {
+            game.last_score = self.score;
+            game.high_score = game.high_score.max(self.score);
+            return SceneCommand::Replace(Box::new(GameOver { restart: false }))
+                .with_transition(Transition::new(TransitionKind::Dissolve, TRANSITION_TICKS));
+        }
+        SceneCommand::Stay
+    }
+
+    fn draw(
+        &self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        layers: &mut Layers,
+        _game: &Game<'a>,
+    ) {
+        layers.draw(d, rlt, WORLD, |d| {
+            d.draw_circle(self.coin.x as i32, self.coin.y as i32, 3.0, Color::GOLD);
+            d.draw_rectangle(
+                self.player.x as i32 - 3,
+                self.player.y as i32 - 3,
+                6,
+                6,
+                Color::SKYBLUE,
+            );
+            d.draw_text(&format!("{}", self.score), 4, 4, 10, Color::RAYWHITE);
+            let seconds = self.ticks_left.div_ceil(FRAMES_PER_SECOND);
+            d.draw_text(&format!("{}", seconds), 220, 4, 10, Color::RAYWHITE);
+        });
+    }
+}
+
+/* ----------- pause (overlay) ----------------------------------------- */
+
+pub struct Pause {
+    resume: bool,
+    quit_to_title: bool,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self {
+            resume: false,
+            quit_to_title: false,
+        }
+    }
+}
+
+impl<'a> Scene<Game<'a>> for Pause {
+    fn name(&self) -> &str {
+        "pause"
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn input(&mut self, rl: &mut RaylibHandle, _game: &mut Game<'a>, input: &InputFilter) {
+        self.resume |= input.is_key_pressed(rl, KeyboardKey::KEY_ESCAPE)
+            || input.is_key_pressed(rl, KeyboardKey::KEY_P);
+        self.quit_to_title |= input.is_key_pressed(rl, KeyboardKey::KEY_Q);
+    }
+
+    fn step(
+        &mut self,
+        _rl: &mut RaylibHandle,
+        _rlt: &RaylibThread,
+        _game: &mut Game<'a>,
+    ) -> SceneCommand<Game<'a>> {
+        if self.quit_to_title {
+            SceneCommand::ReplaceAll(Box::new(Title::new())).with_transition(Transition::new(
+                TransitionKind::Mosaic(16),
+                TRANSITION_TICKS,
+            ))
+        } else if self.resume {
+            SceneCommand::Pop
+        } else {
+            SceneCommand::Stay
+        }
+    }
+
+    fn draw(
+        &self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        layers: &mut Layers,
+        _game: &Game<'a>,
+    ) {
+        layers.draw(d, rlt, WORLD, |d| {
+            d.draw_rectangle(0, 0, 240, 160, Color::new(0, 0, 0, 160));
+            draw_centered(d, "PAUSED", 56, 20, Color::RAYWHITE);
+            draw_centered(d, "p to resume, q to quit", 96, 10, Color::GRAY);
+        });
+    }
+}
+
+/* ----------- game over ----------------------------------------------- */
+
+pub struct GameOver {
+    restart: bool,
+}
+
+impl<'a> Scene<Game<'a>> for GameOver {
+    fn name(&self) -> &str {
+        "game over"
+    }
+
+    fn input(&mut self, rl: &mut RaylibHandle, _game: &mut Game<'a>, input: &InputFilter) {
+        self.restart |= input.is_key_pressed(rl, KeyboardKey::KEY_ENTER);
+    }
+
+    fn step(
+        &mut self,
+        _rl: &mut RaylibHandle,
+        _rlt: &RaylibThread,
+        _game: &mut Game<'a>,
+    ) -> SceneCommand<Game<'a>> {
+        if self.restart {
+            SceneCommand::Replace(Box::new(Title::new())).with_transition(Transition::new(
+                TransitionKind::WipeHorizontal,
+                TRANSITION_TICKS,
+            ))
+        } else {
+            SceneCommand::Stay
+        }
+    }
+
+    fn draw(
+        &self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        layers: &mut Layers,
+        game: &Game<'a>,
+    ) {
+        layers.draw(d, rlt, WORLD, |d| {
+            draw_centered(d, "GAME OVER", 40, 20, Color::RED);
+            draw_centered(
+                d,
+                &format!("score {}", game.last_score),
+                88,
+                10,
+                Color::RAYWHITE,
+            );
+            draw_centered(d, "press enter", 120, 10, Color::GRAY);
+        });
+    }
+}
@@ -0,0 +1,321 @@
+//! Timers and scripted sequences on the simulation clock.
+//!
+//! Timers fire an event after some ticks, once or on repeat. Scripts are async blocks
+//! polled once per tick that can `wait_ticks(n)` between steps, so a behaviour like
+//! "wait, move, flash, spawn" reads top to bottom instead of as a state machine.
+//! Neither touches sketch state directly: like `World::commands`, they queue events
+//! that `step` hands back for the sketch to apply. Each step fires due timers first and
+//! then polls scripts, each in the order they were added, so the same inputs give the
+//! same events on the same ticks.
+//! ```ignore
+//! enum Cue { Spawn, Flash, Move(Vec2) }
+//!
+//! scheduler.every(120, Cue::Spawn);
+//! scheduler.spawn(|script| async move {
+//!     script.wait_ticks(30).await;
+//!     for _ in 0..10 {
+//!         script.emit(Cue::Move(Vec2::X));
+//!         script.wait_ticks(1).await;
+//!     }
+//!     script.emit(Cue::Flash);
+//! });
+//! // step
+//! for cue in scheduler.step() {
+//!     match cue { .. }
+//! }
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// A timer or script, for cancelling it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+struct Timer<E> {
+    id: TaskId,
+    due: u64,
+    /// Ticks between firings for repeating timers.
+    interval: Option<u64>,
+    event: E,
+}
+
+struct ScriptTask {
+    id: TaskId,
+    future: Pin<Box<dyn Future<Output = ()>>>,
+}
+
+/// What scripts share with the scheduler.
+struct Shared<E> {
+    tick: Cell<u64>,
+    events: RefCell<Vec<E>>,
+}
+
+pub struct Scheduler<E> {
+    shared: Rc<Shared<E>>,
+    timers: Vec<Timer<E>>,
+    scripts: Vec<ScriptTask>,
+    next_id: u64,
+}
+
+impl<E: Clone + 'static> Default for Scheduler<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Clone + 'static> Scheduler<E> {
+    pub fn new() -> Self {
+        Self {
+            shared: Rc::new(Shared {
+                tick: Cell::new(0),
+                events: RefCell::new(Vec::new()),
+            }),
+            timers: Vec::new(),
+            scripts: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Ticks stepped so far.
+    pub fn tick(&self) -> u64 {
+        self.shared.tick.get()
+    }
+
+    /// Fires `event` once, `ticks` steps from now, or on the next step for 0.
+    pub fn after(&mut self, ticks: u32, event: E) -> TaskId {
+        self.add_timer(ticks, None, event)
+    }
+
+    /// Fires `event` every `ticks` steps, starting `ticks` from now.
+    pub fn every(&mut self, ticks: u32, event: E) -> TaskId {
+        self.add_timer(ticks, Some(ticks.max(1) as u64), event)
+    }
+
+    fn add_timer(&mut self, ticks: u32, interval: Option<u64>, event: E) -> TaskId {
+        let id = self.next_id();
+        self.timers.push(Timer {
+            id,
+            due: self.tick() + ticks.max(1) as u64,
+            interval,
+            event,
+        });
+        id
+    }
+
+    /// Starts a script, first polled on the next `step`. It gets a `Script` for
+    /// waiting and emitting events.
+    pub fn spawn<F, Fut>(&mut self, script: F) -> TaskId
+    where
+        F: FnOnce(Script<E>) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let id = self.next_id();
+        let future = script(Script {
+            shared: self.shared.clone(),
+        });
+        self.scripts.push(ScriptTask {
+            id,
+            future: Box::pin(future),
+        });
+        id
+    }
+
+    /// Stops a timer or script. Does nothing if it already finished.
+    pub fn cancel(&mut self, id: TaskId) {
+        self.timers.retain(|timer| timer.id != id);
+        self.scripts.retain(|script| script.id != id);
+    }
+
+    /// Whether a timer or script is still waiting to fire or finish.
+    pub fn is_active(&self, id: TaskId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+            || self.scripts.iter().any(|script| script.id == id)
+    }
+
+    /// Cancels everything; the tick count carries on.
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.scripts.clear();
+        self.shared.events.borrow_mut().clear();
+    }
+
+    /// Advances one tick, firing due timers then polling scripts, and returns the events
+    /// they produced in that order.
+    pub fn step(&mut self) -> Vec<E> {
+        let tick = self.tick() + 1;
+        self.shared.tick.set(tick);
+
+        let mut events = Vec::new();
+        self.timers.retain_mut(|timer| {
+            if timer.due > tick {
+                return true;
+            }
+            events.push(timer.event.clone());
+            match timer.interval {
+                Some(interval) => {
+                    timer.due += interval;
+                    true
+                }
+                None => false,
+            }
+        });
+
+        // nothing needs waking: every script is polled every tick anyway
+        let mut context = Context::from_waker(Waker::noop());
+        self.scripts
+            .retain_mut(|script| script.future.as_mut().poll(&mut context).is_pending());
+        events.append(&mut self.shared.events.borrow_mut());
+        events
+    }
+
+    fn next_id(&mut self) -> TaskId {
+        self.next_id += 1;
+        TaskId(self.next_id)
+    }
+}
+
+/// A running script's view of the scheduler.
+pub struct Script<E> {
+    shared: Rc<Shared<E>>,
+}
+
+impl<E> Clone for Script<E> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<E> Script<E> {
+    /// Queues an event for the `step` that's running.
+    pub fn emit(&self, event: E) {
+        self.shared.events.borrow_mut().push(event);
+    }
+
+    /// The scheduler's tick count.
+    pub fn tick(&self) -> u64 {
+        self.shared.tick.get()
+    }
+
+    /// Resumes `ticks` steps later; 0 carries straight on.
+    pub fn wait_ticks(&self, ticks: u32) -> WaitTicks<E> {
+        WaitTicks {
+            shared: self.shared.clone(),
+            until: self.tick() + ticks as u64,
+        }
+    }
+}
+
+/// Future returned by `Script::wait_ticks`.
+pub struct WaitTicks<E> {
+    shared: Rc<Shared<E>>,
+    until: u64,
+}
+
+impl<E> Future for WaitTicks<E> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<()> {
+        if self.shared.tick.get() >= self.until {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ticks `step` returned anything on, with what it returned.
+    fn run<E: Clone + 'static>(scheduler: &mut Scheduler<E>, ticks: u64) -> Vec<(u64, Vec<E>)> {
+        (0..ticks)
+            .map(|_| scheduler.step())
+            .zip(1..)
+            .filter(|(events, _)| !events.is_empty())
+            .map(|(events, tick)| (tick, events))
+            .collect()
+    }
+
+    #[test]
+    fn after_zero_fires_on_the_next_step() {
+        let mut scheduler = Scheduler::new();
+        scheduler.after(0, "now");
+        scheduler.after(3, "later");
+        assert_eq!(
+            run(&mut scheduler, 5),
+            [(1, vec!["now"]), (3, vec!["later"])]
+        );
+    }
+
+    #[test]
+    fn every_repeats_on_its_interval() {
+        let mut scheduler = Scheduler::new();
+        scheduler.every(3, "beat");
+        let ticks: Vec<u64> = run(&mut scheduler, 10).iter().map(|(t, _)| *t).collect();
+        assert_eq!(ticks, [3, 6, 9]);
+    }
+
+    #[test]
+    fn wait_ticks_resumes_that_many_steps_later() {
+        let mut scheduler = Scheduler::new();
+        scheduler.spawn(|script| async move {
+            script.emit(script.tick());
+            script.wait_ticks(0).await;
+            script.emit(script.tick());
+            script.wait_ticks(2).await;
+            script.emit(script.tick());
+            script.wait_ticks(1).await;
+            script.emit(script.tick());
+        });
+        assert_eq!(
+            run(&mut scheduler, 6),
+            [(1, vec![1, 1]), (3, vec![3]), (4, vec![4])]
+        );
+    }
+
+    #[test]
+    fn cancelled_tasks_never_fire() {
+        let mut scheduler = Scheduler::new();
+        let timer = scheduler.every(2, "timer");
+        let script = scheduler.spawn(|script| async move {
+            script.wait_ticks(3).await;
+            script.emit("script");
+        });
+        assert!(scheduler.is_active(timer) && scheduler.is_active(script));
+        assert_eq!(run(&mut scheduler, 2), [(2, vec!["timer"])]);
+
+        scheduler.cancel(timer);
+        scheduler.cancel(script);
+        assert!(!scheduler.is_active(timer) && !scheduler.is_active(script));
+        assert!(run(&mut scheduler, 10).is_empty());
+    }
+
+    #[test]
+    fn timers_fire_before_scripts_each_in_the_order_added() {
+        let mut scheduler = Scheduler::new();
+        scheduler.spawn(|script| async move {
+            script.emit("first script");
+        });
+        scheduler.after(1, "first timer");
+        scheduler.spawn(|script| async move {
+            script.emit("second script");
+        });
+        scheduler.after(1, "second timer");
+        assert_eq!(
+            scheduler.step(),
+            [
+                "first timer",
+                "second timer",
+                "first script",
+                "second script"
+            ]
+        );
+    }
+}
@@ -0,0 +1,715 @@
+//! Procedural sound effects in the style of DrPetter's sfxr: the same parameter set,
+//! synthesis and presets, rendered to PCM for raylib or a WAV file.
+//!
+//! ```ignore
+//! let coin = SfxrParams::coin(7);
+//! coin.load_into(&mut audio, "coin", 4)?;
+//! coin.save_wav("coin.wav")?;
+//! // or tweak it live, e.g. in `egui_ui`
+//! if state.sound_editor.ui(ui) {
+//!     state.sound_changed = true;
+//! }
+//! ```
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Audio;
+use crate::rng::Rng;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Generation stops here even if the envelope hasn't finished.
+const MAX_SAMPLES: usize = SAMPLE_RATE as usize * 10;
+/// sfxr's fixed output gain, with the 4x it adds when exporting, before `volume`.
+const MASTER_VOLUME: f32 = 0.05 * 4.0;
+const SUPERSAMPLES: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Sawtooth,
+    Sine,
+    Noise,
+}
+
+/// Every value is 0.0 to 1.0, or -1.0 to 1.0 for the signed ones (see `fields_mut`),
+/// matching the sliders of the original tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SfxrParams {
+    pub waveform: Waveform,
+
+    pub attack: f32,
+    pub sustain: f32,
+    /// Volume boost at the start of the sustain.
+    pub punch: f32,
+    pub decay: f32,
+
+    pub base_freq: f32,
+    /// Cuts the sound off when a downward slide reaches this pitch.
+    pub freq_limit: f32,
+    pub freq_ramp: f32,
+    /// Change of `freq_ramp` over time.
+    pub freq_dramp: f32,
+
+    pub vibrato_depth: f32,
+    pub vibrato_speed: f32,
+
+    /// Pitch jump part way through, up for positive values.
+    pub arp_mod: f32,
+    pub arp_speed: f32,
+
+    /// Square wave duty cycle.
+    pub duty: f32,
+    pub duty_ramp: f32,
+
+    /// Restarts the pitch and arpeggio envelopes this often.
+    pub repeat_speed: f32,
+
+    pub phaser_offset: f32,
+    pub phaser_ramp: f32,
+
+    pub lpf_freq: f32,
+    pub lpf_ramp: f32,
+    pub lpf_resonance: f32,
+    pub hpf_freq: f32,
+    pub hpf_ramp: f32,
+
+    pub volume: f32,
+}
+
+impl Default for SfxrParams {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Square,
+            attack: 0.0,
+            sustain: 0.3,
+            punch: 0.0,
+            decay: 0.4,
+            base_freq: 0.3,
+            freq_limit: 0.0,
+            freq_ramp: 0.0,
+            freq_dramp: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            arp_mod: 0.0,
+            arp_speed: 0.0,
+            duty: 0.0,
+            duty_ramp: 0.0,
+            repeat_speed: 0.0,
+            phaser_offset: 0.0,
+            phaser_ramp: 0.0,
+            lpf_freq: 1.0,
+            lpf_ramp: 0.0,
+            lpf_resonance: 0.0,
+            hpf_freq: 0.0,
+            hpf_ramp: 0.0,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SfxrParams {
+    pub fn coin(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let mut p = Self {
+            base_freq: 0.4 + r.float(0.5),
+            sustain: r.float(0.1),
+            decay: 0.1 + r.float(0.4),
+            punch: 0.3 + r.float(0.3),
+            ..Default::default()
+        };
+        if r.flip() {
+            p.arp_speed = 0.5 + r.float(0.2);
+            p.arp_mod = 0.2 + r.float(0.4);
+        }
+        p
+    }
+
+    pub fn laser(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let mut p = Self {
+            waveform: match r.int(2) {
+                0 => Waveform::Square,
+                1 => Waveform::Sawtooth,
+                _ if r.flip() => Waveform::Sawtooth,
+                _ => Waveform::Sine,
+            },
+            ..Default::default()
+        };
+        p.base_freq = 0.5 + r.float(0.5);
+        p.freq_limit = (p.base_freq - 0.2 - r.float(0.6)).max(0.2);
+        p.freq_ramp = -0.15 - r.float(0.2);
+        if r.int(2) == 0 {
+            p.base_freq = 0.3 + r.float(0.6);
+            p.freq_limit = r.float(0.1);
+            p.freq_ramp = -0.35 - r.float(0.3);
+        }
+        if r.flip() {
+            p.duty = r.float(0.5);
+            p.duty_ramp = r.float(0.2);
+        } else {
+            p.duty = 0.4 + r.float(0.5);
+            p.duty_ramp = -r.float(0.7);
+        }
+        p.sustain = 0.1 + r.float(0.2);
+        p.decay = r.float(0.4);
+        if r.flip() {
+            p.punch = r.float(0.3);
+        }
+        if r.int(2) == 0 {
+            p.phaser_offset = r.float(0.2);
+            p.phaser_ramp = -r.float(0.2);
+        }
+        if r.flip() {
+            p.hpf_freq = r.float(0.3);
+        }
+        p
+    }
+
+    pub fn explosion(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let mut p = Self {
+            waveform: Waveform::Noise,
+            ..Default::default()
+        };
+        if r.flip() {
+            p.base_freq = 0.1 + r.float(0.4);
+            p.freq_ramp = -0.1 + r.float(0.4);
+        } else {
+            p.base_freq = 0.2 + r.float(0.7);
+            p.freq_ramp = -0.2 - r.float(0.2);
+        }
+        p.base_freq *= p.base_freq;
+        if r.int(4) == 0 {
+            p.freq_ramp = 0.0;
+        }
+        if r.int(2) == 0 {
+            p.repeat_speed = 0.3 + r.float(0.5);
+        }
+        p.sustain = 0.1 + r.float(0.3);
+        p.decay = r.float(0.5);
+        if !r.flip() {
+            p.phaser_offset = -0.3 + r.float(0.9);
+            p.phaser_ramp = -r.float(0.3);
+        }
+        p.punch = 0.2 + r.float(0.6);
+        if r.flip() {
+            p.vibrato_depth = r.float(0.7);
+            p.vibrato_speed = r.float(0.6);
+        }
+        if r.int(2) == 0 {
+            p.arp_speed = 0.6 + r.float(0.3);
+            p.arp_mod = 0.8 - r.float(1.6);
+        }
+        p
+    }
+
+    pub fn hurt(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let mut p = Self {
+            waveform: match r.int(2) {
+                0 => Waveform::Square,
+                1 => Waveform::Sawtooth,
+                _ => Waveform::Noise,
+            },
+            ..Default::default()
+        };
+        if p.waveform == Waveform::Square {
+            p.duty = r.float(0.6);
+        }
+        p.base_freq = 0.2 + r.float(0.6);
+        p.freq_ramp = -0.3 - r.float(0.4);
+        p.sustain = r.float(0.1);
+        p.decay = 0.1 + r.float(0.2);
+        if r.flip() {
+            p.hpf_freq = r.float(0.3);
+        }
+        p
+    }
+
+    pub fn jump(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let mut p = Self {
+            waveform: Waveform::Square,
+            duty: r.float(0.6),
+            base_freq: 0.3 + r.float(0.3),
+            freq_ramp: 0.1 + r.float(0.2),
+            sustain: 0.1 + r.float(0.3),
+            decay: 0.1 + r.float(0.2),
+            ..Default::default()
+        };
+        if r.flip() {
+            p.hpf_freq = r.float(0.3);
+        }
+        if r.flip() {
+            p.lpf_freq = 1.0 - r.float(0.6);
+        }
+        p
+    }
+
+    /// Anything goes, with the original's nudges towards something audible.
+    pub fn random(seed: u64) -> Self {
+        let mut r = Rng::new(seed);
+        let signed = |r: &mut Rng, power: i32| (r.float(2.0) - 1.0).powi(power);
+        let mut p = Self {
+            waveform: match r.int(3) {
+                0 => Waveform::Square,
+                1 => Waveform::Sawtooth,
+                2 => Waveform::Sine,
+                _ => Waveform::Noise,
+            },
+            ..Default::default()
+        };
+        p.base_freq = if r.flip() {
+            signed(&mut r, 3) + 0.5
+        } else {
+            signed(&mut r, 2)
+        };
+        p.freq_ramp = signed(&mut r, 5);
+        if (p.base_freq > 0.7 && p.freq_ramp > 0.2) || (p.base_freq < 0.2 && p.freq_ramp < -0.05) {
+            p.freq_ramp = -p.freq_ramp;
+        }
+        p.freq_dramp = signed(&mut r, 3);
+        p.duty = signed(&mut r, 1);
+        p.duty_ramp = signed(&mut r, 3);
+        p.vibrato_depth = signed(&mut r, 3);
+        p.vibrato_speed = signed(&mut r, 1);
+        p.attack = signed(&mut r, 3);
+        p.sustain = signed(&mut r, 2);
+        p.decay = signed(&mut r, 1);
+        p.punch = r.float(0.8).powi(2);
+        if p.attack + p.sustain + p.decay < 0.2 {
+            p.sustain += 0.2 + r.float(0.3);
+            p.decay += 0.2 + r.float(0.3);
+        }
+        p.lpf_resonance = signed(&mut r, 1);
+        p.lpf_freq = 1.0 - r.float(1.0).powi(3);
+        p.lpf_ramp = signed(&mut r, 3);
+        if p.lpf_freq < 0.1 && p.lpf_ramp < -0.05 {
+            p.lpf_ramp = -p.lpf_ramp;
+        }
+        p.hpf_freq = r.float(1.0).powi(5);
+        p.hpf_ramp = signed(&mut r, 5);
+        p.phaser_offset = signed(&mut r, 3);
+        p.phaser_ramp = signed(&mut r, 3);
+        p.repeat_speed = signed(&mut r, 1);
+        p.arp_speed = signed(&mut r, 1);
+        p.arp_mod = signed(&mut r, 1);
+        p.clamp();
+        p
+    }
+
+    /// Nudges about half the values by a little, for variations on a sound.
+    pub fn mutate(&mut self, seed: u64) {
+        let mut r = Rng::new(seed);
+        for (_, value, _) in self.fields_mut() {
+            if r.flip() {
+                *value += r.float(0.1) - 0.05;
+            }
+        }
+        self.clamp();
+    }
+
+    /// Every numeric parameter with its name and whether it goes negative.
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut f32, bool); 23] {
+        [
+            ("Attack", &mut self.attack, false),
+            ("Sustain", &mut self.sustain, false),
+            ("Punch", &mut self.punch, false),
+            ("Decay", &mut self.decay, false),
+            ("Frequency", &mut self.base_freq, false),
+            ("Min frequency", &mut self.freq_limit, false),
+            ("Slide", &mut self.freq_ramp, true),
+            ("Delta slide", &mut self.freq_dramp, true),
+            ("Vibrato depth", &mut self.vibrato_depth, false),
+            ("Vibrato speed", &mut self.vibrato_speed, false),
+            ("Change amount", &mut self.arp_mod, true),
+            ("Change speed", &mut self.arp_speed, false),
+            ("Square duty", &mut self.duty, false),
+            ("Duty sweep", &mut self.duty_ramp, true),
+            ("Repeat speed", &mut self.repeat_speed, false),
+            ("Phaser offset", &mut self.phaser_offset, true),
+            ("Phaser sweep", &mut self.phaser_ramp, true),
+            ("LP cutoff", &mut self.lpf_freq, false),
+            ("LP cutoff sweep", &mut self.lpf_ramp, true),
+            ("LP resonance", &mut self.lpf_resonance, false),
+            ("HP cutoff", &mut self.hpf_freq, false),
+            ("HP cutoff sweep", &mut self.hpf_ramp, true),
+            ("Volume", &mut self.volume, false),
+        ]
+    }
+
+    fn clamp(&mut self) {
+        for (_, value, signed) in self.fields_mut() {
+            *value = value.clamp(if signed { -1.0 } else { 0.0 }, 1.0);
+        }
+    }
+
+    /// Mono samples at `SAMPLE_RATE`, -1.0 to 1.0.
+    pub fn generate(&self) -> Vec<f32> {
+        let mut synth = Synth::new(self);
+        let mut samples = Vec::new();
+        while samples.len() < MAX_SAMPLES {
+            match synth.sample(self) {
+                Some(sample) => samples.push(sample),
+                None => break,
+            }
+        }
+        samples
+    }
+
+    /// A complete 16-bit mono WAV file.
+    pub fn to_wav(&self) -> Vec<u8> {
+        let samples = self.generate();
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::with_capacity(44 + data_len as usize);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        wav
+    }
+
+    pub fn save_wav(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_wav())
+    }
+
+    /// Generates the sound and adds it to the bank under `name`, replacing any sound
+    /// already there.
+    pub fn load_into(
+        &self,
+        audio: &mut Audio,
+        name: &str,
+        voices: usize,
+    ) -> Result<(), raylib::core::error::Error> {
+        audio.load_sound_from_memory(name, ".wav", &self.to_wav(), voices)
+    }
+}
+
+/// Running state of one sfxr voice, following the original `SynthSample`.
+struct Synth {
+    rng: Rng,
+    phase: usize,
+    period: f32,
+    max_period: f32,
+    slide: f32,
+    delta_slide: f32,
+    square_duty: f32,
+    square_slide: f32,
+    arp_mod: f32,
+    arp_time: u32,
+    arp_limit: u32,
+    env_stage: usize,
+    env_time: u32,
+    env_length: [u32; 3],
+    env_volume: f32,
+    phaser_phase: f32,
+    phaser_delta: f32,
+    phaser_buffer: [f32; 1024],
+    phaser_pos: usize,
+    noise_buffer: [f32; 32],
+    lp_pos: f32,
+    lp_delta: f32,
+    lp_w: f32,
+    lp_w_delta: f32,
+    lp_damping: f32,
+    hp_pos: f32,
+    hp: f32,
+    hp_delta: f32,
+    vibrato_phase: f32,
+    vibrato_speed: f32,
+    vibrato_depth: f32,
+    repeat_time: u32,
+    repeat_limit: u32,
+}
+
+impl Synth {
+    fn new(p: &SfxrParams) -> Self {
+        let square = |x: f32| x * x;
+        let cube = |x: f32| x * x * x;
+        let mut noise_buffer = [0.0; 32];
+        let mut rng = Rng::new(0x5f3c);
+        for noise in &mut noise_buffer {
+            *noise = rng.float(2.0) - 1.0;
+        }
+        let lp_w = cube(p.lpf_freq) * 0.1;
+        let mut synth = Self {
+            rng,
+            phase: 0,
+            period: 0.0,
+            max_period: 0.0,
+            slide: 0.0,
+            delta_slide: 0.0,
+            square_duty: 0.0,
+            square_slide: 0.0,
+            arp_mod: 0.0,
+            arp_time: 0,
+            arp_limit: 0,
+            env_stage: 0,
+            env_time: 0,
+            env_length: [
+                (square(p.attack) * 100000.0) as u32,
+                (square(p.sustain) * 100000.0) as u32,
+                (square(p.decay) * 100000.0) as u32,
+            ],
+            env_volume: 0.0,
+            phaser_phase: square(p.phaser_offset) * 1020.0 * p.phaser_offset.signum(),
+            phaser_delta: square(p.phaser_ramp) * p.phaser_ramp.signum(),
+            phaser_buffer: [0.0; 1024],
+            phaser_pos: 0,
+            noise_buffer,
+            lp_pos: 0.0,
+            lp_delta: 0.0,
+            lp_w,
+            lp_w_delta: 1.0 + p.lpf_ramp * 0.0001,
+            lp_damping: (5.0 / (1.0 + square(p.lpf_resonance) * 20.0) * (0.01 + lp_w)).min(0.8),
+            hp_pos: 0.0,
+            hp: square(p.hpf_freq) * 0.1,
+            hp_delta: 1.0 + p.hpf_ramp * 0.0003,
+            vibrato_phase: 0.0,
+            vibrato_speed: square(p.vibrato_speed) * 0.01,
+            vibrato_depth: p.vibrato_depth * 0.5,
+            repeat_time: 0,
+            repeat_limit: if p.repeat_speed == 0.0 {
+                0
+            } else {
+                (square(1.0 - p.repeat_speed) * 20000.0 + 32.0) as u32
+            },
+        };
+        synth.restart(p);
+        synth
+    }
+
+    /// The part of the state a repeat resets.
+    fn restart(&mut self, p: &SfxrParams) {
+        let square = |x: f32| x * x;
+        let cube = |x: f32| x * x * x;
+        self.period = 100.0 / (square(p.base_freq) + 0.001);
+        self.max_period = 100.0 / (square(p.freq_limit) + 0.001);
+        self.slide = 1.0 - cube(p.freq_ramp) * 0.01;
+        self.delta_slide = -cube(p.freq_dramp) * 0.000001;
+        self.square_duty = 0.5 - p.duty * 0.5;
+        self.square_slide = -p.duty_ramp * 0.00005;
+        self.arp_mod = if p.arp_mod >= 0.0 {
+            1.0 - square(p.arp_mod) * 0.9
+        } else {
+            1.0 + square(p.arp_mod) * 10.0
+        };
+        self.arp_time = 0;
+        self.arp_limit = if p.arp_speed == 1.0 {
+            0
+        } else {
+            (square(1.0 - p.arp_speed) * 20000.0 + 32.0) as u32
+        };
+    }
+
+    /// The next output sample, or None once the sound has ended.
+    fn sample(&mut self, p: &SfxrParams) -> Option<f32> {
+        self.repeat_time += 1;
+        if self.repeat_limit != 0 && self.repeat_time >= self.repeat_limit {
+            self.repeat_time = 0;
+            self.restart(p);
+        }
+
+        self.arp_time += 1;
+        if self.arp_limit != 0 && self.arp_time >= self.arp_limit {
+            self.arp_limit = 0;
+            self.period *= self.arp_mod;
+        }
+
+        self.slide += self.delta_slide;
+        self.period *= self.slide;
+        if self.period > self.max_period {
+            self.period = self.max_period;
+            if p.freq_limit > 0.0 {
+                return None;
+            }
+        }
+        let mut period = self.period;
+        if self.vibrato_depth > 0.0 {
+            self.vibrato_phase += self.vibrato_speed;
+            period *= 1.0 + self.vibrato_phase.sin() * self.vibrato_depth;
+        }
+        let period = (period as usize).max(8);
+
+        self.square_duty = (self.square_duty + self.square_slide).clamp(0.0, 0.5);
+
+        self.env_time += 1;
+        if self.env_time > self.env_length[self.env_stage] {
+            self.env_time = 0;
+            self.env_stage += 1;
+            if self.env_stage == 3 {
+                return None;
+            }
+        }
+        let t = self.env_time as f32 / self.env_length[self.env_stage].max(1) as f32;
+        self.env_volume = match self.env_stage {
+            0 => t,
+            1 => 1.0 + (1.0 - t) * 2.0 * p.punch,
+            _ => 1.0 - t,
+        };
+
+        self.phaser_phase += self.phaser_delta;
+        let phaser_offset = (self.phaser_phase.abs() as usize).min(1023);
+
+        if self.hp_delta != 1.0 {
+            self.hp = (self.hp * self.hp_delta).clamp(0.00001, 0.1);
+        }
+
+        let mut total = 0.0;
+        for _ in 0..SUPERSAMPLES {
+            self.phase += 1;
+            if self.phase >= period {
+                self.phase %= period;
+                if p.waveform == Waveform::Noise {
+                    for noise in &mut self.noise_buffer {
+                        *noise = self.rng.float(2.0) - 1.0;
+                    }
+                }
+            }
+            let fp = self.phase as f32 / period as f32;
+            let mut sample = match p.waveform {
+                Waveform::Square => {
+                    if fp < self.square_duty {
+                        0.5
+                    } else {
+                        -0.5
+                    }
+                }
+                Waveform::Sawtooth => 1.0 - fp * 2.0,
+                Waveform::Sine => (fp * 2.0 * PI).sin(),
+                Waveform::Noise => self.noise_buffer[self.phase * 32 / period],
+            };
+
+            // low-pass
+            let previous = self.lp_pos;
+            self.lp_w = (self.lp_w * self.lp_w_delta).clamp(0.0, 0.1);
+            if p.lpf_freq != 1.0 {
+                self.lp_delta += (sample - self.lp_pos) * self.lp_w;
+                self.lp_delta -= self.lp_delta * self.lp_damping;
+            } else {
+                self.lp_pos = sample;
+                self.lp_delta = 0.0;
+            }
+            self.lp_pos += self.lp_delta;
+
+            // high-pass
+            self.hp_pos += self.lp_pos - previous;
+            self.hp_pos -= self.hp_pos * self.hp;
+            sample = self.hp_pos;
+
+            // phaser
+            self.phaser_buffer[self.phaser_pos & 1023] = sample;
+            sample += self.phaser_buffer[(self.phaser_pos + 1024 - phaser_offset) & 1023];
+            self.phaser_pos = (self.phaser_pos + 1) & 1023;
+
+            total += sample * self.env_volume;
+        }
+
+        let sample = total / SUPERSAMPLES as f32 * MASTER_VOLUME * 2.0 * p.volume;
+        Some(sample.clamp(-1.0, 1.0))
+    }
+}
+
+type Preset = fn(u64) -> SfxrParams;
+
+/// Preset buttons, waveform and sliders for a set of parameters, for an egui window.
+pub struct SfxrEditor {
+    pub params: SfxrParams,
+    /// Where Export writes to.
+    pub export_path: String,
+    seed: u64,
+}
+
+impl SfxrEditor {
+    pub fn new(params: SfxrParams) -> Self {
+        Self {
+            params,
+            export_path: "sfx.wav".to_string(),
+            seed: 1,
+        }
+    }
+
+    /// True when the sound changed or Play was pressed, i.e. it should be regenerated
+    /// and played.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            let presets: [(&str, Preset); 6] = [
+                ("Coin", SfxrParams::coin),
+                ("Jump", SfxrParams::jump),
+                ("Hurt", SfxrParams::hurt),
+                ("Explosion", SfxrParams::explosion),
+                ("Laser", SfxrParams::laser),
+                ("Random", SfxrParams::random),
+            ];
+            for (label, preset) in presets {
+                if ui.button(label).clicked() {
+                    self.seed += 1;
+                    self.params = preset(self.seed);
+                    changed = true;
+                }
+            }
+            if ui.button("Mutate").clicked() {
+                self.seed += 1;
+                self.params.mutate(self.seed);
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            for (waveform, label) in [
+                (Waveform::Square, "Square"),
+                (Waveform::Sawtooth, "Saw"),
+                (Waveform::Sine, "Sine"),
+                (Waveform::Noise, "Noise"),
+            ] {
+                changed |= ui
+                    .selectable_value(&mut self.params.waveform, waveform, label)
+                    .changed();
+            }
+        });
+        egui::Grid::new("sfxr_params")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (label, value, signed) in self.params.fields_mut() {
+                    ui.label(label);
+                    let min = if signed { -1.0 } else { 0.0 };
+                    let response = ui.add(egui::Slider::new(value, min..=1.0));
+                    // regenerate when a drag ends rather than every frame of it
+                    changed |=
+                        response.drag_stopped() || (response.changed() && !response.dragged());
+                    ui.end_row();
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Play").clicked() {
+                changed = true;
+            }
+            if ui.button("Export").clicked() {
+                match self.params.save_wav(&self.export_path) {
+                    Ok(()) => crate::editor::log(format!("saved {}", self.export_path)),
+                    Err(e) => {
+                        crate::editor::log(format!("could not save {}: {}", self.export_path, e))
+                    }
+                }
+            }
+            ui.text_edit_singleline(&mut self.export_path);
+        });
+        changed
+    }
+}
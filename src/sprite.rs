@@ -0,0 +1,691 @@
+//! Sprite sheets exported from Aseprite (File > Export Sprite Sheet, JSON data, either
+//! "Hash" or "Array"), and tick-driven animation over them.
+//!
+//! ```ignore
+//! let sheet = SpriteSheet::load(&mut rl, &rlt, "assets/hero.json")?;
+//! let mut hero = AnimatedSprite::new(&sheet);
+//! hero.play(&sheet, "run", PlayMode::Loop);
+//! // in step
+//! hero.step(&sheet, timestep.dt());
+//! // in draw, inside a layer
+//! hero.draw(d, &sheet, position, Color::WHITE);
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use glam::{IVec2, UVec2, Vec2};
+use indexmap::IndexMap;
+use raylib::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug)]
+pub enum SpriteError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Texture(raylib::core::error::Error),
+    /// The frame at this index is stored turned 90 degrees (TexturePacker's "allow
+    /// rotation"), which drawing doesn't undo.
+    RotatedFrame(usize),
+}
+
+impl fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpriteError::Io(e) => write!(f, "could not read sprite sheet: {}", e),
+            SpriteError::Json(e) => write!(f, "could not parse sprite sheet: {}", e),
+            SpriteError::Texture(e) => write!(f, "could not load sprite sheet image: {}", e),
+            SpriteError::RotatedFrame(index) => write!(
+                f,
+                "sprite sheet frame {} is rotated, export the sheet without rotation",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpriteError {}
+
+impl From<std::io::Error> for SpriteError {
+    fn from(e: std::io::Error) -> Self {
+        SpriteError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SpriteError {
+    fn from(e: serde_json::Error) -> Self {
+        SpriteError::Json(e)
+    }
+}
+
+impl From<raylib::core::error::Error> for SpriteError {
+    fn from(e: raylib::core::error::Error) -> Self {
+        SpriteError::Texture(e)
+    }
+}
+
+/* ----------- Aseprite JSON ------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct RawRect {
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct RawSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct RawPoint {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrame {
+    frame: RawRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: RawRect,
+    source_size: RawSize,
+    duration: u32,
+}
+
+/// "Hash" exports key frames by filename in export order, "Array" exports a list.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawFrames {
+    Array(Vec<RawFrame>),
+    Hash(IndexMap<String, RawFrame>),
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: Direction,
+    // Aseprite writes the repeat count as a string, and leaves it out for "forever"
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSliceKey {
+    frame: usize,
+    bounds: RawRect,
+    center: Option<RawRect>,
+    pivot: Option<RawPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSlice {
+    name: String,
+    keys: Vec<RawSliceKey>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMeta {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<RawTag>,
+    #[serde(default)]
+    slices: Vec<RawSlice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSheet {
+    frames: RawFrames,
+    meta: RawMeta,
+}
+
+/* ----------- sheet data ---------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IRect {
+    pub pos: IVec2,
+    pub size: IVec2,
+}
+
+impl From<RawRect> for IRect {
+    fn from(r: RawRect) -> Self {
+        Self {
+            pos: IVec2::new(r.x, r.y),
+            size: IVec2::new(r.w, r.h),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// Where the frame is in the sheet image.
+    pub source: IRect,
+    /// Where the trimmed frame sits inside the untrimmed sprite.
+    pub offset: IVec2,
+    /// Untrimmed sprite size, the same for every frame of a sprite.
+    pub size: UVec2,
+    pub duration_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub name: String,
+    /// First and last frame, inclusive.
+    pub from: usize,
+    pub to: usize,
+    pub direction: Direction,
+    /// None repeats forever.
+    pub repeat: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SliceKey {
+    /// First frame this key applies to; it lasts until the next key.
+    pub frame: usize,
+    pub bounds: IRect,
+    /// Nine-patch centre, relative to `bounds`.
+    pub center: Option<IRect>,
+    /// Relative to `bounds`.
+    pub pivot: Option<IVec2>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    pub fn key_at(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+/// Everything in the JSON, without the image.
+#[derive(Debug, Clone)]
+pub struct SheetData {
+    /// Path of the sheet image, as written by Aseprite (relative to the JSON file).
+    pub image: String,
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+    pub slices: Vec<Slice>,
+}
+
+impl SheetData {
+    pub fn parse(json: &str) -> Result<Self, SpriteError> {
+        let raw: RawSheet = serde_json::from_str(json)?;
+        let raw_frames = match raw.frames {
+            RawFrames::Array(frames) => frames,
+            RawFrames::Hash(frames) => frames.into_values().collect(),
+        };
+        let frames = raw_frames
+            .into_iter()
+            .enumerate()
+            .map(|(index, f)| {
+                if f.rotated {
+                    return Err(SpriteError::RotatedFrame(index));
+                }
+                Ok(Frame {
+                    source: f.frame.into(),
+                    offset: IVec2::new(f.sprite_source_size.x, f.sprite_source_size.y),
+                    size: UVec2::new(f.source_size.w, f.source_size.h),
+                    duration_ms: f.duration.max(1),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let tags = raw
+            .meta
+            .frame_tags
+            .into_iter()
+            .map(|t| Tag {
+                name: t.name,
+                from: t.from,
+                to: t.to,
+                direction: t.direction,
+                repeat: t
+                    .repeat
+                    .and_then(|r| r.parse().ok())
+                    .filter(|&repeat| repeat > 0),
+            })
+            .collect();
+        let slices = raw
+            .meta
+            .slices
+            .into_iter()
+            .map(|s| Slice {
+                name: s.name,
+                keys: s
+                    .keys
+                    .into_iter()
+                    .map(|k| SliceKey {
+                        frame: k.frame,
+                        bounds: k.bounds.into(),
+                        center: k.center.map(IRect::from),
+                        pivot: k.pivot.map(|p| IVec2::new(p.x, p.y)),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(Self {
+            image: raw.meta.image,
+            frames,
+            tags,
+            slices,
+        })
+    }
+}
+
+pub struct SpriteSheet {
+    pub texture: Texture2D,
+    pub data: SheetData,
+}
+
+impl SpriteSheet {
+    /// Loads the JSON and the image it names, which is looked up next to the JSON.
+    pub fn load(
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        json_path: impl AsRef<Path>,
+    ) -> Result<Self, SpriteError> {
+        let json_path = json_path.as_ref();
+        let data = SheetData::parse(&std::fs::read_to_string(json_path)?)?;
+        let image_path = json_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(&data.image);
+        let texture = rl.load_texture(rlt, &image_path.to_string_lossy())?;
+        Ok(Self { texture, data })
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.data.frames
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.data.tags.iter().find(|tag| tag.name == name)
+    }
+
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.data.slices.iter().find(|slice| slice.name == name)
+    }
+
+    /// The pivot for `frame` in untrimmed sprite pixels: the first slice with a pivot
+    /// key for that frame, or the top left corner.
+    pub fn pivot(&self, frame: usize) -> IVec2 {
+        self.data
+            .slices
+            .iter()
+            .filter_map(|slice| slice.key_at(frame))
+            .find_map(|key| key.pivot.map(|pivot| key.bounds.pos + pivot))
+            .unwrap_or(IVec2::ZERO)
+    }
+
+    /// Draws `frame` with `origin` (in untrimmed sprite pixels) at `position`, snapped to
+    /// whole canvas pixels. Flips mirror around the untrimmed sprite, so the origin
+    /// stays put.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_frame(
+        &self,
+        d: &mut impl RaylibDraw,
+        frame: usize,
+        position: Vec2,
+        origin: IVec2,
+        flip_x: bool,
+        flip_y: bool,
+        tint: Color,
+    ) {
+        let Some(frame) = self.data.frames.get(frame) else {
+            return;
+        };
+        let size = frame.size.as_ivec2();
+        let mut offset = frame.offset;
+        let mut origin = origin;
+        if flip_x {
+            offset.x = size.x - offset.x - frame.source.size.x;
+            origin.x = size.x - origin.x;
+        }
+        if flip_y {
+            offset.y = size.y - offset.y - frame.source.size.y;
+            origin.y = size.y - origin.y;
+        }
+        let top_left = position.round().as_ivec2() - origin + offset;
+
+        let source = frame.source;
+        let sign = |flip: bool| if flip { -1.0 } else { 1.0 };
+        let source_rec = Rectangle::new(
+            source.pos.x as f32,
+            source.pos.y as f32,
+            source.size.x as f32 * sign(flip_x),
+            source.size.y as f32 * sign(flip_y),
+        );
+        d.draw_texture_rec(
+            &self.texture,
+            source_rec,
+            Vector2::new(top_left.x as f32, top_left.y as f32),
+            tint,
+        );
+    }
+}
+
+/* ----------- animation ----------------------------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    Loop,
+    /// Bounces back and forth, whatever direction the tag has.
+    PingPong,
+    /// Stops on the last frame and reports `is_finished`; ping-pong tags stop once
+    /// they're back where they started.
+    Once,
+}
+
+/// Playback state for one sprite. Works as an ECS component; the sheet is passed in
+/// rather than stored so many sprites can share one.
+#[derive(Debug, Clone)]
+pub struct AnimatedSprite {
+    pub mode: PlayMode,
+    /// Playback rate multiplier.
+    pub speed: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    tag: Option<String>,
+    from: usize,
+    to: usize,
+    /// Passes through the tag before stopping, from its repeat count in Aseprite.
+    repeat: Option<u32>,
+    passes: u32,
+    /// The tag itself is ping-pong, so it bounces in any mode.
+    ping_pong_tag: bool,
+    frame: usize,
+    elapsed_ms: f32,
+    backwards: bool,
+    finished: bool,
+}
+
+impl AnimatedSprite {
+    /// Loops over every frame in the sheet.
+    pub fn new(sheet: &SpriteSheet) -> Self {
+        Self::over_frames(sheet.frames().len())
+    }
+
+    fn over_frames(count: usize) -> Self {
+        Self {
+            mode: PlayMode::Loop,
+            speed: 1.0,
+            flip_x: false,
+            flip_y: false,
+            tag: None,
+            from: 0,
+            to: count.saturating_sub(1),
+            repeat: None,
+            passes: 0,
+            ping_pong_tag: false,
+            frame: 0,
+            elapsed_ms: 0.0,
+            backwards: false,
+            finished: false,
+        }
+    }
+
+    /// Starts the named tag from its first frame, unless it's already playing.
+    /// The tag's own direction applies: reverse tags play backwards and ping-pong tags
+    /// bounce even in `Loop` or `Once`. Tags with a repeat count stop after that many
+    /// passes (one way through, so each half of a ping-pong counts) whatever the mode.
+    /// Returns false if the sheet has no such tag.
+    pub fn play(&mut self, sheet: &SpriteSheet, tag: &str, mode: PlayMode) -> bool {
+        if self.tag.as_deref() == Some(tag) && self.mode == mode && !self.finished {
+            return true;
+        }
+        let Some(found) = sheet.tag(tag) else {
+            return false;
+        };
+        self.start(found, mode);
+        true
+    }
+
+    fn start(&mut self, tag: &Tag, mode: PlayMode) {
+        self.backwards = matches!(
+            tag.direction,
+            Direction::Reverse | Direction::PingpongReverse
+        );
+        self.ping_pong_tag = matches!(
+            tag.direction,
+            Direction::Pingpong | Direction::PingpongReverse
+        );
+        self.from = tag.from;
+        self.to = tag.to;
+        self.repeat = tag.repeat;
+        self.passes = 0;
+        self.frame = if self.backwards { self.to } else { self.from };
+        self.tag = Some(tag.name.clone());
+        self.mode = mode;
+        self.elapsed_ms = 0.0;
+        self.finished = false;
+    }
+
+    /// Advances by one tick of `dt` seconds, using each frame's own duration.
+    pub fn step(&mut self, sheet: &SpriteSheet, dt: f32) {
+        if self.finished {
+            return;
+        }
+        self.elapsed_ms += dt * 1000.0 * self.speed;
+        while let Some(frame) = sheet.frames().get(self.frame) {
+            let duration = frame.duration_ms as f32;
+            if self.elapsed_ms < duration {
+                break;
+            }
+            self.elapsed_ms -= duration;
+            self.advance();
+            if self.finished {
+                break;
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        let at_end = if self.backwards {
+            self.frame == self.from
+        } else {
+            self.frame == self.to
+        };
+        if !at_end {
+            if self.backwards {
+                self.frame -= 1;
+            } else {
+                self.frame += 1;
+            }
+            return;
+        }
+        self.passes += 1;
+        if self.repeat.is_some_and(|repeat| self.passes >= repeat) {
+            self.finished = true;
+            self.elapsed_ms = 0.0;
+            return;
+        }
+        if self.from == self.to {
+            self.finished = self.mode == PlayMode::Once;
+            return;
+        }
+        let bounces = self.mode == PlayMode::PingPong || self.ping_pong_tag;
+        if bounces && (self.mode != PlayMode::Once || self.passes < 2) {
+            self.backwards = !self.backwards;
+            self.frame = if self.backwards {
+                self.frame - 1
+            } else {
+                self.frame + 1
+            };
+        } else if self.mode == PlayMode::Once {
+            self.finished = true;
+            self.elapsed_ms = 0.0;
+        } else {
+            self.frame = if self.backwards { self.to } else { self.from };
+        }
+    }
+
+    /// Index into the sheet's frames.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// True once a `Once` animation, or a tag's repeat count, has shown its last frame
+    /// for its full duration.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Draws the current frame with the sheet's pivot at `position`.
+    pub fn draw(&self, d: &mut impl RaylibDraw, sheet: &SpriteSheet, position: Vec2, tint: Color) {
+        sheet.draw_frame(
+            d,
+            self.frame,
+            position,
+            sheet.pivot(self.frame),
+            self.flip_x,
+            self.flip_y,
+            tint,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: &str = r#"{
+        "frame": { "x": 16, "y": 0, "w": 14, "h": 15 },
+        "rotated": false,
+        "spriteSourceSize": { "x": 1, "y": 1, "w": 14, "h": 15 },
+        "sourceSize": { "w": 16, "h": 16 },
+        "duration": 100
+    }"#;
+
+    fn sheet_json(frames: &str, tags: &str) -> String {
+        format!(
+            r#"{{ "frames": {}, "meta": {{ "image": "hero.png", "frameTags": [{}] }} }}"#,
+            frames, tags
+        )
+    }
+
+    fn tag(direction: Direction, from: usize, to: usize, repeat: Option<u32>) -> Tag {
+        Tag {
+            name: "walk".to_string(),
+            from,
+            to,
+            direction,
+            repeat,
+        }
+    }
+
+    /// Frames shown by advancing `count` times after the first.
+    fn frames(sprite: &mut AnimatedSprite, count: usize) -> Vec<usize> {
+        let mut shown = vec![sprite.frame()];
+        for _ in 0..count {
+            sprite.advance();
+            shown.push(sprite.frame());
+        }
+        shown
+    }
+
+    #[test]
+    fn parses_hash_layout_in_export_order() {
+        let json = sheet_json(
+            &format!(r#"{{ "hero 1.ase": {}, "hero 0.ase": {} }}"#, FRAME, FRAME),
+            r#"{ "name": "run", "from": 0, "to": 1, "direction": "pingpong", "repeat": "3" }"#,
+        );
+        let data = SheetData::parse(&json).unwrap();
+        assert_eq!(data.image, "hero.png");
+        assert_eq!(data.frames.len(), 2);
+        let frame = data.frames[0];
+        assert_eq!(frame.source.pos, IVec2::new(16, 0));
+        assert_eq!(frame.source.size, IVec2::new(14, 15));
+        assert_eq!(frame.offset, IVec2::new(1, 1));
+        assert_eq!(frame.size, UVec2::new(16, 16));
+        assert_eq!(frame.duration_ms, 100);
+        assert_eq!(data.tags[0].direction, Direction::Pingpong);
+        assert_eq!(data.tags[0].repeat, Some(3));
+    }
+
+    #[test]
+    fn parses_array_layout() {
+        let json = sheet_json(
+            &format!("[{}, {}, {}]", FRAME, FRAME, FRAME),
+            r#"{ "name": "idle", "from": 1, "to": 2 }"#,
+        );
+        let data = SheetData::parse(&json).unwrap();
+        assert_eq!(data.frames.len(), 3);
+        let idle = &data.tags[0];
+        assert_eq!((idle.from, idle.to), (1, 2));
+        assert_eq!(idle.direction, Direction::Forward);
+        assert_eq!(idle.repeat, None);
+    }
+
+    #[test]
+    fn rejects_rotated_frames() {
+        let rotated = FRAME.replace(r#""rotated": false"#, r#""rotated": true"#);
+        let json = sheet_json(&format!("[{}, {}]", FRAME, rotated), "");
+        assert!(matches!(
+            SheetData::parse(&json),
+            Err(SpriteError::RotatedFrame(1))
+        ));
+    }
+
+    #[test]
+    fn repeat_stops_after_that_many_passes() {
+        let mut sprite = AnimatedSprite::over_frames(4);
+        sprite.start(&tag(Direction::Forward, 1, 3, Some(2)), PlayMode::Loop);
+        assert_eq!(frames(&mut sprite, 5), [1, 2, 3, 1, 2, 3]);
+        assert!(!sprite.is_finished());
+        sprite.advance();
+        assert!(sprite.is_finished());
+        assert_eq!(sprite.frame(), 3);
+    }
+
+    #[test]
+    fn ping_pong_tags_bounce_in_loop_mode() {
+        let mut sprite = AnimatedSprite::over_frames(4);
+        sprite.start(&tag(Direction::Pingpong, 0, 2, None), PlayMode::Loop);
+        assert_eq!(frames(&mut sprite, 6), [0, 1, 2, 1, 0, 1, 2]);
+
+        sprite.start(&tag(Direction::PingpongReverse, 0, 2, None), PlayMode::Loop);
+        assert_eq!(frames(&mut sprite, 6), [2, 1, 0, 1, 2, 1, 0]);
+    }
+
+    #[test]
+    fn ping_pong_tags_stop_back_at_the_start_once() {
+        let mut sprite = AnimatedSprite::over_frames(3);
+        sprite.start(&tag(Direction::Pingpong, 0, 2, None), PlayMode::Once);
+        assert_eq!(frames(&mut sprite, 4), [0, 1, 2, 1, 0]);
+        sprite.advance();
+        assert!(sprite.is_finished());
+        assert_eq!(sprite.frame(), 0);
+    }
+
+    #[test]
+    fn ping_pong_mode_bounces_forward_tags() {
+        let mut sprite = AnimatedSprite::over_frames(3);
+        sprite.start(&tag(Direction::Forward, 0, 2, None), PlayMode::PingPong);
+        assert_eq!(frames(&mut sprite, 5), [0, 1, 2, 1, 0, 1]);
+    }
+}
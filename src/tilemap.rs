@@ -0,0 +1,1318 @@
+//! Maps made in Tiled, from either JSON (.tmj) or XML (.tmx), and a renderer that only
+//! draws the tiles a camera can see.
+//!
+//! ```ignore
+//! let mut map = TileMap::load(&mut rl, &rlt, "assets/level1.tmx")?;
+//! // in step
+//! map.step(timestep.dt());
+//! // in draw, inside the canvas layer
+//! map.draw(d, Rectangle::new(camera.x, camera.y, 320.0, 180.0), Color::WHITE);
+//! ```
+//!
+//! Supported: tile layers stored as CSV or uncompressed base64, object layers, groups
+//! (flattened, with their offsets and visibility applied to the layers inside them),
+//! custom properties on the map, layers, objects and tiles, external tilesets in either
+//! format (.tsx or .tsj, whatever the map is), animated tiles and flipped tiles. Not
+//! supported: isometric, staggered and hexagonal maps, infinite maps, compressed tile
+//! data, image layers and image-collection tilesets.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use glam::{UVec2, Vec2};
+use raylib::prelude::*;
+
+#[derive(Debug)]
+pub enum TileMapError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Xml(roxmltree::Error),
+    Texture(raylib::core::error::Error),
+    /// Something the loader can't make sense of or doesn't support.
+    Invalid(String),
+}
+
+impl fmt::Display for TileMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TileMapError::Io(e) => write!(f, "could not read map: {}", e),
+            TileMapError::Json(e) => write!(f, "could not parse map: {}", e),
+            TileMapError::Xml(e) => write!(f, "could not parse map: {}", e),
+            TileMapError::Texture(e) => write!(f, "could not load tileset image: {}", e),
+            TileMapError::Invalid(msg) => write!(f, "unsupported map: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TileMapError {}
+
+impl From<std::io::Error> for TileMapError {
+    fn from(e: std::io::Error) -> Self {
+        TileMapError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for TileMapError {
+    fn from(e: serde_json::Error) -> Self {
+        TileMapError::Json(e)
+    }
+}
+
+impl From<roxmltree::Error> for TileMapError {
+    fn from(e: roxmltree::Error) -> Self {
+        TileMapError::Xml(e)
+    }
+}
+
+impl From<raylib::core::error::Error> for TileMapError {
+    fn from(e: raylib::core::error::Error) -> Self {
+        TileMapError::Texture(e)
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> TileMapError {
+    TileMapError::Invalid(msg.into())
+}
+
+/// Drawing assumes a square grid; isometric, staggered and hexagonal maps would come out
+/// scrambled.
+fn check_orientation(orientation: &str) -> Result<(), TileMapError> {
+    match orientation {
+        "orthogonal" => Ok(()),
+        other => Err(invalid(format!(
+            "{} maps aren't supported, only orthogonal",
+            other
+        ))),
+    }
+}
+
+/// Reads an external tileset. Tiled lets a map of either format use a tileset of
+/// either format, so this goes by the tileset's own extension: .tsx is read as XML,
+/// anything else as JSON.
+fn load_tileset(path: &Path, first_gid: u32) -> Result<Tileset, TileMapError> {
+    let text = std::fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    if path.extension().is_some_and(|ext| ext == "tsx") {
+        xml::parse_tileset(&text, first_gid, dir)
+    } else {
+        json::parse_tileset(&text, first_gid, dir)
+    }
+}
+
+/* ----------- map data ------------------------------------------------ */
+
+/// Tiled keeps flip flags in the top bits of each global tile id.
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+const GID_MASK: u32 =
+    !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+    File(String),
+    /// Id of another object in the map.
+    Object(u32),
+}
+
+impl Property {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Property::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Ints convert too, since Tiled users rarely pick float for whole numbers.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Property::Float(f) => Some(*f),
+            Property::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(s) | Property::File(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+pub type Properties = HashMap<String, Property>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationFrame {
+    /// Local id within the same tileset.
+    pub tile: u32,
+    pub duration_ms: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TileData {
+    pub class: String,
+    pub properties: Properties,
+    pub animation: Vec<AnimationFrame>,
+}
+
+pub struct Tileset {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    /// Resolved against the map (or external tileset) file.
+    pub image: Option<PathBuf>,
+    /// Only tiles with a class, properties or an animation are listed, by local id.
+    pub tiles: HashMap<u32, TileData>,
+    pub texture: Option<Texture2D>,
+}
+
+impl Tileset {
+    /// The frame of an animated tile showing at `clock_ms`, otherwise the tile itself.
+    pub fn animated(&self, local: u32, clock_ms: f64) -> u32 {
+        let Some(data) = self.tiles.get(&local) else {
+            return local;
+        };
+        let total: u32 = data.animation.iter().map(|frame| frame.duration_ms).sum();
+        if total == 0 {
+            return local;
+        }
+        let mut t = (clock_ms as u64 % total as u64) as u32;
+        for frame in &data.animation {
+            if t < frame.duration_ms {
+                return frame.tile;
+            }
+            t -= frame.duration_ms;
+        }
+        local
+    }
+
+    fn source_rect(&self, local: u32) -> Rectangle {
+        let columns = self.columns.max(1);
+        let (col, row) = (local % columns, local / columns);
+        Rectangle::new(
+            (self.margin + col * (self.tile_size.x + self.spacing)) as f32,
+            (self.margin + row * (self.tile_size.y + self.spacing)) as f32,
+            self.tile_size.x as f32,
+            self.tile_size.y as f32,
+        )
+    }
+}
+
+pub struct TileLayer {
+    pub name: String,
+    pub class: String,
+    pub width: u32,
+    pub height: u32,
+    /// Global tile ids row by row, flip flags included; 0 is empty.
+    pub tiles: Vec<u32>,
+    pub offset: Vec2,
+    pub opacity: f32,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+impl TileLayer {
+    /// The global tile id at a cell, without flip flags; 0 for empty or out of bounds.
+    pub fn gid(&self, x: i32, y: i32) -> u32 {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return 0;
+        }
+        self.tiles[(y as u32 * self.width + x as u32) as usize] & GID_MASK
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// Points relative to the object's position.
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub position: Vec2,
+    pub size: Vec2,
+    /// Degrees clockwise.
+    pub rotation: f32,
+    pub visible: bool,
+    /// For tile objects, with flip flags.
+    pub gid: Option<u32>,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+pub struct ObjectLayer {
+    pub name: String,
+    pub class: String,
+    pub objects: Vec<MapObject>,
+    pub offset: Vec2,
+    pub opacity: f32,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+impl ObjectLayer {
+    pub fn object(&self, name: &str) -> Option<&MapObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+}
+
+pub enum MapLayer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+}
+
+impl MapLayer {
+    pub fn name(&self) -> &str {
+        match self {
+            MapLayer::Tiles(layer) => &layer.name,
+            MapLayer::Objects(layer) => &layer.name,
+        }
+    }
+}
+
+pub struct TileMap {
+    /// In tiles.
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: UVec2,
+    pub background: Option<Color>,
+    pub properties: Properties,
+    /// Sorted by first global id.
+    pub tilesets: Vec<Tileset>,
+    /// Bottom to top, groups flattened.
+    pub layers: Vec<MapLayer>,
+    clock_ms: f64,
+}
+
+impl TileMap {
+    /// Reads the map, its external tilesets and the tileset images. Files ending in
+    /// .tmx are read as XML, anything else as JSON.
+    pub fn load(
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, TileMapError> {
+        let mut map = Self::from_file(path)?;
+        map.load_textures(rl, rlt)?;
+        Ok(map)
+    }
+
+    /// Reads the map and its external tilesets, without touching the GPU. Only
+    /// orthogonal maps are supported.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, TileMapError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if path.extension().is_some_and(|ext| ext == "tmx") {
+            xml::parse_map(&text, dir)
+        } else {
+            json::parse_map(&text, dir)
+        }
+    }
+
+    pub fn load_textures(
+        &mut self,
+        rl: &mut RaylibHandle,
+        rlt: &RaylibThread,
+    ) -> Result<(), TileMapError> {
+        for tileset in &mut self.tilesets {
+            if let Some(image) = &tileset.image {
+                tileset.texture = Some(rl.load_texture(rlt, &image.to_string_lossy())?);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn pixel_size(&self) -> UVec2 {
+        UVec2::new(self.width, self.height) * self.tile_size
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&MapLayer> {
+        self.layers.iter().find(|layer| layer.name() == name)
+    }
+
+    pub fn tile_layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find_map(|layer| match layer {
+            MapLayer::Tiles(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+    }
+
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.layers.iter().find_map(|layer| match layer {
+            MapLayer::Objects(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+    }
+
+    /// The tileset a global id belongs to and the id within it. Flip flags are ignored.
+    pub fn tileset_for(&self, gid: u32) -> Option<(&Tileset, u32)> {
+        let gid = gid & GID_MASK;
+        if gid == 0 {
+            return None;
+        }
+        let tileset = self
+            .tilesets
+            .iter()
+            .rev()
+            .find(|tileset| tileset.first_gid <= gid)?;
+        Some((tileset, gid - tileset.first_gid))
+    }
+
+    pub fn tile_data(&self, gid: u32) -> Option<&TileData> {
+        let (tileset, local) = self.tileset_for(gid)?;
+        tileset.tiles.get(&local)
+    }
+
+    /// Advances animated tiles by one tick of `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        self.clock_ms += dt as f64 * 1000.0;
+    }
+
+    /// Draws every visible tile layer. `camera` is the part of the map, in map pixels,
+    /// that lands at the canvas origin; its position is rounded to whole pixels.
+    pub fn draw(&self, d: &mut impl RaylibDraw, camera: Rectangle, tint: Color) {
+        for layer in &self.layers {
+            if let MapLayer::Tiles(layer) = layer {
+                self.draw_tile_layer(d, layer, camera, tint);
+            }
+        }
+    }
+
+    /// Draws one tile layer by name, for putting sprites between layers.
+    pub fn draw_layer(&self, d: &mut impl RaylibDraw, name: &str, camera: Rectangle, tint: Color) {
+        if let Some(layer) = self.tile_layer(name) {
+            self.draw_tile_layer(d, layer, camera, tint);
+        }
+    }
+
+    pub fn draw_tile_layer(
+        &self,
+        d: &mut impl RaylibDraw,
+        layer: &TileLayer,
+        camera: Rectangle,
+        tint: Color,
+    ) {
+        if !layer.visible || layer.opacity <= 0.0 {
+            return;
+        }
+        let tint = Color::new(
+            tint.r,
+            tint.g,
+            tint.b,
+            (tint.a as f32 * layer.opacity.min(1.0)) as u8,
+        );
+        let grid = self.tile_size.as_vec2();
+        let origin = Vec2::new(camera.x, camera.y).round() - layer.offset.round();
+
+        // tiles bigger than the grid hang up and to the right of their cell
+        let largest = self
+            .tilesets
+            .iter()
+            .fold(self.tile_size, |size, tileset| size.max(tileset.tile_size));
+        let overhang = (largest.as_vec2() / grid).ceil().as_ivec2() - 1;
+
+        let first = (origin / grid).floor().as_ivec2() - overhang.with_y(0);
+        let last = ((origin + Vec2::new(camera.width, camera.height)) / grid)
+            .ceil()
+            .as_ivec2()
+            + overhang.with_x(0);
+        let first = first.max(glam::IVec2::ZERO);
+        let last = last.min(glam::IVec2::new(layer.width as i32, layer.height as i32));
+
+        for y in first.y..last.y {
+            for x in first.x..last.x {
+                let raw = layer.tiles[(y as u32 * layer.width + x as u32) as usize];
+                let Some((tileset, local)) = self.tileset_for(raw) else {
+                    continue;
+                };
+                let Some(texture) = &tileset.texture else {
+                    continue;
+                };
+                let local = tileset.animated(local, self.clock_ms);
+                let size = tileset.tile_size.as_vec2();
+                let top_left =
+                    Vec2::new(x as f32, (y + 1) as f32) * grid - Vec2::new(0.0, size.y) - origin;
+                draw_tile(d, texture, tileset.source_rect(local), raw, top_left, tint);
+            }
+        }
+    }
+}
+
+/// Draws a tile with its flip flags. A diagonal flip is a transpose, which raylib can
+/// only do as a quarter turn of a vertically flipped source, with the other two flips
+/// swapping axes to match.
+fn draw_tile(
+    d: &mut impl RaylibDraw,
+    texture: &Texture2D,
+    mut source: Rectangle,
+    raw_gid: u32,
+    top_left: Vec2,
+    tint: Color,
+) {
+    let h = raw_gid & FLIPPED_HORIZONTALLY != 0;
+    let v = raw_gid & FLIPPED_VERTICALLY != 0;
+    let (flip_x, flip_y, rotation) = if raw_gid & FLIPPED_DIAGONALLY != 0 {
+        (v, !h, 90.0)
+    } else {
+        (h, v, 0.0)
+    };
+    let (width, height) = (source.width, source.height);
+    if flip_x {
+        source.width = -source.width;
+    }
+    if flip_y {
+        source.height = -source.height;
+    }
+    let half = Vector2::new(width / 2.0, height / 2.0);
+    d.draw_texture_pro(
+        texture,
+        source,
+        Rectangle::new(top_left.x + half.x, top_left.y + half.y, width, height),
+        half,
+        rotation,
+        tint,
+    );
+}
+
+/// "#AARRGGBB" or "#RRGGBB".
+fn parse_color(s: &str) -> Option<Color> {
+    let hex = s.trim().trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [a, r, g, b] = match hex.len() {
+        8 => value.to_be_bytes(),
+        6 => (value | 0xff00_0000).to_be_bytes(),
+        _ => return None,
+    };
+    Some(Color::new(r, g, b, a))
+}
+
+fn parse_property(kind: &str, value: &str) -> Option<Property> {
+    Some(match kind {
+        "bool" => Property::Bool(value == "true"),
+        "int" => Property::Int(value.parse().ok()?),
+        "float" => Property::Float(value.parse().ok()?),
+        "color" => Property::Color(parse_color(value).unwrap_or(Color::BLANK)),
+        "file" => Property::File(value.to_string()),
+        "object" => Property::Object(value.parse().ok()?),
+        _ => Property::String(value.to_string()),
+    })
+}
+
+fn decode_base64(
+    data: &str,
+    compression: Option<&str>,
+    len: usize,
+) -> Result<Vec<u32>, TileMapError> {
+    if let Some(compression) = compression.filter(|c| !c.is_empty()) {
+        return Err(invalid(format!(
+            "{} compressed tile data, save the map with CSV or uncompressed base64",
+            compression
+        )));
+    }
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| invalid(format!("bad base64 tile data: {}", e)))?;
+    if bytes.len() != len * 4 {
+        return Err(invalid("tile data doesn't match the layer size"));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/* ----------- JSON (.tmj, .tsj) ------------------------------------- */
+
+mod json {
+    use super::*;
+    use serde::Deserialize;
+
+    fn one() -> f32 {
+        1.0
+    }
+
+    fn orthogonal() -> String {
+        "orthogonal".to_string()
+    }
+
+    fn yes() -> bool {
+        true
+    }
+
+    #[derive(Deserialize)]
+    struct RawProperty {
+        name: String,
+        #[serde(rename = "type", default)]
+        kind: String,
+        value: serde_json::Value,
+    }
+
+    #[derive(Deserialize)]
+    struct RawFrame {
+        tileid: u32,
+        duration: u32,
+    }
+
+    #[derive(Deserialize)]
+    struct RawTile {
+        id: u32,
+        #[serde(default, alias = "type")]
+        class: String,
+        #[serde(default)]
+        properties: Vec<RawProperty>,
+        #[serde(default)]
+        animation: Vec<RawFrame>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawTileset {
+        #[serde(default)]
+        firstgid: u32,
+        source: Option<String>,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        tilewidth: u32,
+        #[serde(default)]
+        tileheight: u32,
+        #[serde(default)]
+        columns: u32,
+        #[serde(default)]
+        tilecount: u32,
+        #[serde(default)]
+        spacing: u32,
+        #[serde(default)]
+        margin: u32,
+        image: Option<String>,
+        #[serde(default)]
+        tiles: Vec<RawTile>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawData {
+        Csv(Vec<u32>),
+        Base64(String),
+    }
+
+    #[derive(Deserialize)]
+    struct RawPoint {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Deserialize)]
+    struct RawText {
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    struct RawObject {
+        id: u32,
+        #[serde(default)]
+        name: String,
+        #[serde(default, alias = "type")]
+        class: String,
+        x: f32,
+        y: f32,
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "yes")]
+        visible: bool,
+        gid: Option<u32>,
+        #[serde(default)]
+        ellipse: bool,
+        #[serde(default)]
+        point: bool,
+        polygon: Option<Vec<RawPoint>>,
+        polyline: Option<Vec<RawPoint>>,
+        text: Option<RawText>,
+        #[serde(default)]
+        properties: Vec<RawProperty>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawLayer {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        class: String,
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        data: Option<RawData>,
+        compression: Option<String>,
+        #[serde(default)]
+        objects: Vec<RawObject>,
+        #[serde(default)]
+        layers: Vec<RawLayer>,
+        #[serde(default)]
+        offsetx: f32,
+        #[serde(default)]
+        offsety: f32,
+        #[serde(default = "one")]
+        opacity: f32,
+        #[serde(default = "yes")]
+        visible: bool,
+        #[serde(default)]
+        properties: Vec<RawProperty>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawMap {
+        #[serde(default = "orthogonal")]
+        orientation: String,
+        width: u32,
+        height: u32,
+        tilewidth: u32,
+        tileheight: u32,
+        #[serde(default)]
+        infinite: bool,
+        backgroundcolor: Option<String>,
+        #[serde(default)]
+        properties: Vec<RawProperty>,
+        #[serde(default)]
+        tilesets: Vec<RawTileset>,
+        #[serde(default)]
+        layers: Vec<RawLayer>,
+    }
+
+    fn properties(raw: Vec<RawProperty>) -> Properties {
+        raw.into_iter()
+            .filter_map(|p| {
+                let value = match p.value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                Some((p.name, parse_property(&p.kind, &value)?))
+            })
+            .collect()
+    }
+
+    fn tileset(raw: RawTileset, first_gid: u32, dir: &Path) -> Result<Tileset, TileMapError> {
+        if let Some(source) = &raw.source {
+            return load_tileset(&dir.join(source), first_gid);
+        }
+        let tiles = raw
+            .tiles
+            .into_iter()
+            .map(|t| {
+                let data = TileData {
+                    class: t.class,
+                    properties: properties(t.properties),
+                    animation: t
+                        .animation
+                        .into_iter()
+                        .map(|f| AnimationFrame {
+                            tile: f.tileid,
+                            duration_ms: f.duration,
+                        })
+                        .collect(),
+                };
+                (t.id, data)
+            })
+            .collect();
+        Ok(Tileset {
+            first_gid,
+            name: raw.name,
+            tile_size: UVec2::new(raw.tilewidth, raw.tileheight),
+            columns: raw.columns,
+            tile_count: raw.tilecount,
+            spacing: raw.spacing,
+            margin: raw.margin,
+            image: raw.image.map(|image| dir.join(image)),
+            tiles,
+            texture: None,
+        })
+    }
+
+    fn object(raw: RawObject) -> MapObject {
+        let points = |points: Vec<RawPoint>| -> Vec<Vec2> {
+            points.iter().map(|p| Vec2::new(p.x, p.y)).collect()
+        };
+        let shape = if let Some(polygon) = raw.polygon {
+            ObjectShape::Polygon(points(polygon))
+        } else if let Some(polyline) = raw.polyline {
+            ObjectShape::Polyline(points(polyline))
+        } else if let Some(text) = raw.text {
+            ObjectShape::Text(text.text)
+        } else if raw.ellipse {
+            ObjectShape::Ellipse
+        } else if raw.point {
+            ObjectShape::Point
+        } else {
+            ObjectShape::Rectangle
+        };
+        MapObject {
+            id: raw.id,
+            name: raw.name,
+            class: raw.class,
+            position: Vec2::new(raw.x, raw.y),
+            size: Vec2::new(raw.width, raw.height),
+            rotation: raw.rotation,
+            visible: raw.visible,
+            gid: raw.gid,
+            shape,
+            properties: properties(raw.properties),
+        }
+    }
+
+    fn layers(
+        raw: Vec<RawLayer>,
+        offset: Vec2,
+        visible: bool,
+        out: &mut Vec<MapLayer>,
+    ) -> Result<(), TileMapError> {
+        for raw in raw {
+            let layer_offset = offset + Vec2::new(raw.offsetx, raw.offsety);
+            let layer_visible = visible && raw.visible;
+            match raw.kind.as_str() {
+                "tilelayer" => {
+                    let len = (raw.width * raw.height) as usize;
+                    let tiles = match raw.data {
+                        Some(RawData::Csv(tiles)) => tiles,
+                        Some(RawData::Base64(data)) => {
+                            decode_base64(&data, raw.compression.as_deref(), len)?
+                        }
+                        None => return Err(invalid("tile layer without data (infinite map?)")),
+                    };
+                    if tiles.len() != len {
+                        return Err(invalid("tile data doesn't match the layer size"));
+                    }
+                    out.push(MapLayer::Tiles(TileLayer {
+                        name: raw.name,
+                        class: raw.class,
+                        width: raw.width,
+                        height: raw.height,
+                        tiles,
+                        offset: layer_offset,
+                        opacity: raw.opacity,
+                        visible: layer_visible,
+                        properties: properties(raw.properties),
+                    }));
+                }
+                "objectgroup" => out.push(MapLayer::Objects(ObjectLayer {
+                    name: raw.name,
+                    class: raw.class,
+                    objects: raw.objects.into_iter().map(object).collect(),
+                    offset: layer_offset,
+                    opacity: raw.opacity,
+                    visible: layer_visible,
+                    properties: properties(raw.properties),
+                })),
+                "group" => layers(raw.layers, layer_offset, layer_visible, out)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn parse_tileset(
+        text: &str,
+        first_gid: u32,
+        dir: &Path,
+    ) -> Result<Tileset, TileMapError> {
+        let raw: RawTileset = serde_json::from_str(text)?;
+        tileset(raw, first_gid, dir)
+    }
+
+    pub(super) fn parse_map(text: &str, dir: &Path) -> Result<TileMap, TileMapError> {
+        let raw: RawMap = serde_json::from_str(text)?;
+        check_orientation(&raw.orientation)?;
+        if raw.infinite {
+            return Err(invalid("infinite maps aren't supported"));
+        }
+        let mut tilesets = raw
+            .tilesets
+            .into_iter()
+            .map(|t| {
+                let first_gid = t.firstgid;
+                tileset(t, first_gid, dir)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+        let mut out = Vec::new();
+        layers(raw.layers, Vec2::ZERO, true, &mut out)?;
+        Ok(TileMap {
+            width: raw.width,
+            height: raw.height,
+            tile_size: UVec2::new(raw.tilewidth, raw.tileheight),
+            background: raw.backgroundcolor.as_deref().and_then(parse_color),
+            properties: properties(raw.properties),
+            tilesets,
+            layers: out,
+            clock_ms: 0.0,
+        })
+    }
+}
+
+/* ----------- XML (.tmx, .tsx) -------------------------------------- */
+
+mod xml {
+    use super::*;
+    use roxmltree::{Document, Node};
+    use std::str::FromStr;
+
+    fn attr<T: FromStr>(node: Node, name: &str) -> Result<T, TileMapError> {
+        let value = node
+            .attribute(name)
+            .ok_or_else(|| invalid(format!("<{}> is missing {}", node.tag_name().name(), name)))?;
+        value
+            .parse()
+            .map_err(|_| invalid(format!("bad {} on <{}>", name, node.tag_name().name())))
+    }
+
+    fn attr_or<T: FromStr>(node: Node, name: &str, default: T) -> T {
+        node.attribute(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn text_attr(node: Node, name: &str) -> String {
+        node.attribute(name).unwrap_or_default().to_string()
+    }
+
+    /// Tiled 1.9 renamed "type" to "class" on objects and tiles.
+    fn class(node: Node) -> String {
+        node.attribute("class")
+            .or(node.attribute("type"))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        node.children().find(|c| c.has_tag_name(name))
+    }
+
+    fn properties(node: Node) -> Properties {
+        let Some(properties) = child(node, "properties") else {
+            return Properties::new();
+        };
+        properties
+            .children()
+            .filter(|c| c.has_tag_name("property"))
+            .filter_map(|p| {
+                let name = p.attribute("name")?;
+                // multi-line strings are stored as text rather than an attribute
+                let value = p.attribute("value").or(p.text()).unwrap_or_default();
+                let kind = p.attribute("type").unwrap_or("string");
+                Some((name.to_string(), parse_property(kind, value)?))
+            })
+            .collect()
+    }
+
+    fn tileset(node: Node, first_gid: u32, dir: &Path) -> Result<Tileset, TileMapError> {
+        if let Some(source) = node.attribute("source") {
+            return load_tileset(&dir.join(source), first_gid);
+        }
+        let mut tiles = HashMap::new();
+        for tile in node.children().filter(|c| c.has_tag_name("tile")) {
+            let animation = child(tile, "animation")
+                .map(|animation| {
+                    animation
+                        .children()
+                        .filter(|c| c.has_tag_name("frame"))
+                        .map(|frame| {
+                            Ok(AnimationFrame {
+                                tile: attr(frame, "tileid")?,
+                                duration_ms: attr(frame, "duration")?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, TileMapError>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+            let data = TileData {
+                class: class(tile),
+                properties: properties(tile),
+                animation,
+            };
+            tiles.insert(attr(tile, "id")?, data);
+        }
+        Ok(Tileset {
+            first_gid,
+            name: text_attr(node, "name"),
+            tile_size: UVec2::new(attr(node, "tilewidth")?, attr(node, "tileheight")?),
+            columns: attr_or(node, "columns", 0),
+            tile_count: attr_or(node, "tilecount", 0),
+            spacing: attr_or(node, "spacing", 0),
+            margin: attr_or(node, "margin", 0),
+            image: child(node, "image")
+                .and_then(|image| image.attribute("source"))
+                .map(|source| dir.join(source)),
+            tiles,
+            texture: None,
+        })
+    }
+
+    fn tile_data(layer: Node, len: usize) -> Result<Vec<u32>, TileMapError> {
+        let data = child(layer, "data").ok_or_else(|| invalid("tile layer without data"))?;
+        if child(data, "chunk").is_some() {
+            return Err(invalid("infinite maps aren't supported"));
+        }
+        let text = data.text().unwrap_or_default();
+        let tiles = match data.attribute("encoding") {
+            Some("csv") => text
+                .split(',')
+                .map(|gid| gid.trim().parse().map_err(|_| invalid("bad CSV tile data")))
+                .collect::<Result<Vec<u32>, _>>()?,
+            Some("base64") => decode_base64(text, data.attribute("compression"), len)?,
+            Some(other) => return Err(invalid(format!("unknown tile encoding {}", other))),
+            None => data
+                .children()
+                .filter(|c| c.has_tag_name("tile"))
+                .map(|tile| attr_or(tile, "gid", 0))
+                .collect(),
+        };
+        if tiles.len() != len {
+            return Err(invalid("tile data doesn't match the layer size"));
+        }
+        Ok(tiles)
+    }
+
+    fn points(s: &str) -> Vec<Vec2> {
+        s.split_whitespace()
+            .filter_map(|pair| {
+                let (x, y) = pair.split_once(',')?;
+                Some(Vec2::new(x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn object(node: Node) -> Result<MapObject, TileMapError> {
+        let shape = if let Some(polygon) = child(node, "polygon") {
+            ObjectShape::Polygon(points(polygon.attribute("points").unwrap_or_default()))
+        } else if let Some(polyline) = child(node, "polyline") {
+            ObjectShape::Polyline(points(polyline.attribute("points").unwrap_or_default()))
+        } else if let Some(text) = child(node, "text") {
+            ObjectShape::Text(text.text().unwrap_or_default().to_string())
+        } else if child(node, "ellipse").is_some() {
+            ObjectShape::Ellipse
+        } else if child(node, "point").is_some() {
+            ObjectShape::Point
+        } else {
+            ObjectShape::Rectangle
+        };
+        Ok(MapObject {
+            id: attr(node, "id")?,
+            name: text_attr(node, "name"),
+            class: class(node),
+            position: Vec2::new(attr_or(node, "x", 0.0), attr_or(node, "y", 0.0)),
+            size: Vec2::new(attr_or(node, "width", 0.0), attr_or(node, "height", 0.0)),
+            rotation: attr_or(node, "rotation", 0.0),
+            visible: attr_or(node, "visible", 1) != 0,
+            gid: node.attribute("gid").and_then(|gid| gid.parse().ok()),
+            shape,
+            properties: properties(node),
+        })
+    }
+
+    fn layers(
+        parent: Node,
+        offset: Vec2,
+        visible: bool,
+        out: &mut Vec<MapLayer>,
+    ) -> Result<(), TileMapError> {
+        for node in parent.children().filter(|c| c.is_element()) {
+            let layer_offset =
+                offset + Vec2::new(attr_or(node, "offsetx", 0.0), attr_or(node, "offsety", 0.0));
+            let layer_visible = visible && attr_or(node, "visible", 1) != 0;
+            let opacity = attr_or(node, "opacity", 1.0);
+            match node.tag_name().name() {
+                "layer" => {
+                    let width = attr(node, "width")?;
+                    let height = attr(node, "height")?;
+                    out.push(MapLayer::Tiles(TileLayer {
+                        name: text_attr(node, "name"),
+                        class: text_attr(node, "class"),
+                        width,
+                        height,
+                        tiles: tile_data(node, (width * height) as usize)?,
+                        offset: layer_offset,
+                        opacity,
+                        visible: layer_visible,
+                        properties: properties(node),
+                    }));
+                }
+                "objectgroup" => out.push(MapLayer::Objects(ObjectLayer {
+                    name: text_attr(node, "name"),
+                    class: text_attr(node, "class"),
+                    objects: node
+                        .children()
+                        .filter(|c| c.has_tag_name("object"))
+                        .map(object)
+                        .collect::<Result<_, _>>()?,
+                    offset: layer_offset,
+                    opacity,
+                    visible: layer_visible,
+                    properties: properties(node),
+                })),
+                "group" => layers(node, layer_offset, layer_visible, out)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn parse_tileset(
+        text: &str,
+        first_gid: u32,
+        dir: &Path,
+    ) -> Result<Tileset, TileMapError> {
+        let doc = Document::parse(text)?;
+        tileset(doc.root_element(), first_gid, dir)
+    }
+
+    pub(super) fn parse_map(text: &str, dir: &Path) -> Result<TileMap, TileMapError> {
+        let doc = Document::parse(text)?;
+        let map = doc.root_element();
+        check_orientation(map.attribute("orientation").unwrap_or("orthogonal"))?;
+        if attr_or(map, "infinite", 0) != 0 {
+            return Err(invalid("infinite maps aren't supported"));
+        }
+        let mut tilesets = map
+            .children()
+            .filter(|c| c.has_tag_name("tileset"))
+            .map(|t| tileset(t, attr(t, "firstgid")?, dir))
+            .collect::<Result<Vec<_>, _>>()?;
+        tilesets.sort_by_key(|tileset| tileset.first_gid);
+        let mut out = Vec::new();
+        layers(map, Vec2::ZERO, true, &mut out)?;
+        Ok(TileMap {
+            width: attr(map, "width")?,
+            height: attr(map, "height")?,
+            tile_size: UVec2::new(attr(map, "tilewidth")?, attr(map, "tileheight")?),
+            background: map.attribute("backgroundcolor").and_then(parse_color),
+            properties: properties(map),
+            tilesets,
+            layers: out,
+            clock_ms: 0.0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILESET_JSON: &str = r#"{
+        "firstgid": 1, "name": "terrain", "tilewidth": 8, "tileheight": 8,
+        "columns": 4, "tilecount": 16, "image": "terrain.png",
+        "tiles": [
+            { "id": 2, "type": "water",
+              "properties": [{ "name": "solid", "type": "bool", "value": false }],
+              "animation": [{ "tileid": 2, "duration": 100 }, { "tileid": 3, "duration": 50 }] },
+            { "id": 5, "class": "wall",
+              "properties": [{ "name": "hp", "type": "int", "value": 3 }] }
+        ]
+    }"#;
+
+    fn json_map(orientation: &str, data: &str) -> String {
+        format!(
+            r#"{{
+                "orientation": "{}", "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+                "tilesets": [{}],
+                "layers": [
+                    {{ "type": "tilelayer", "name": "ground", "width": 2, "height": 2,
+                       "data": {} }},
+                    {{ "type": "objectgroup", "name": "things", "offsetx": 4, "objects": [
+                        {{ "id": 1, "name": "spawn", "x": 8, "y": 16, "point": true }},
+                        {{ "id": 2, "name": "door", "type": "exit", "x": 0, "y": 0,
+                           "width": 8, "height": 16,
+                           "properties": [{{ "name": "to", "type": "string", "value": "cave" }}] }},
+                        {{ "id": 3, "x": 1, "y": 2, "polygon": [{{ "x": 0, "y": 0 }}, {{ "x": 4, "y": 2 }}] }}
+                    ] }}
+                ]
+            }}"#,
+            orientation, TILESET_JSON, data
+        )
+    }
+
+    fn xml_map(orientation: &str, csv: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <map orientation="{}" width="2" height="2" tilewidth="8" tileheight="8" infinite="0">
+              <tileset firstgid="1" name="terrain" tilewidth="8" tileheight="8" columns="4">
+                <image source="terrain.png"/>
+                <tile id="2" type="water">
+                  <properties><property name="solid" type="bool" value="false"/></properties>
+                  <animation>
+                    <frame tileid="2" duration="100"/>
+                    <frame tileid="3" duration="50"/>
+                  </animation>
+                </tile>
+              </tileset>
+              <layer name="ground" width="2" height="2">
+                <data encoding="csv">{}</data>
+              </layer>
+              <objectgroup name="things">
+                <object id="1" name="spawn" x="8" y="16"><point/></object>
+                <object id="2" name="door" class="exit" x="0" y="0" width="8" height="16">
+                  <properties><property name="to" value="cave"/></properties>
+                </object>
+              </objectgroup>
+            </map>"#,
+            orientation, csv
+        )
+    }
+
+    fn parse_json(text: &str) -> Result<TileMap, TileMapError> {
+        json::parse_map(text, Path::new("maps"))
+    }
+
+    fn parse_xml(text: &str) -> Result<TileMap, TileMapError> {
+        xml::parse_map(text, Path::new("maps"))
+    }
+
+    /// The local tile an animated gid shows at the map's current clock.
+    fn showing(map: &TileMap, gid: u32) -> u32 {
+        let (tileset, local) = map.tileset_for(gid).unwrap();
+        tileset.animated(local, map.clock_ms)
+    }
+
+    #[test]
+    fn rejects_csv_of_the_wrong_length() {
+        let err = parse_json(&json_map("orthogonal", "[1, 2, 3]"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, TileMapError::Invalid(_)));
+        let err = parse_xml(&xml_map("orthogonal", "1,2,3,4,5"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, TileMapError::Invalid(_)));
+    }
+
+    #[test]
+    fn rejects_non_orthogonal_maps() {
+        for orientation in ["isometric", "staggered", "hexagonal"] {
+            let err = parse_json(&json_map(orientation, "[1, 2, 3, 4]"))
+                .err()
+                .unwrap();
+            assert!(matches!(err, TileMapError::Invalid(_)));
+            let err = parse_xml(&xml_map(orientation, "1,2,3,4")).err().unwrap();
+            assert!(matches!(err, TileMapError::Invalid(_)));
+        }
+    }
+
+    #[test]
+    fn decodes_flip_flags() {
+        let flipped = [
+            6 | FLIPPED_HORIZONTALLY,
+            6 | FLIPPED_VERTICALLY,
+            6 | FLIPPED_DIAGONALLY,
+            6 | FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY,
+        ];
+        let csv = flipped.map(|gid| gid.to_string()).join(",");
+        let from_json = parse_json(&json_map("orthogonal", &format!("[{}]", csv))).unwrap();
+        let from_xml = parse_xml(&xml_map("orthogonal", &csv)).unwrap();
+        for map in [from_json, from_xml] {
+            let ground = map.tile_layer("ground").unwrap();
+            assert_eq!(ground.tiles, flipped);
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                assert_eq!(ground.gid(x, y), 6);
+                let (tileset, local) = map
+                    .tileset_for(ground.tiles[y as usize * 2 + x as usize])
+                    .unwrap();
+                assert_eq!((tileset.name.as_str(), local), ("terrain", 5));
+            }
+            assert_eq!(ground.gid(2, 0), 0);
+            assert_eq!(ground.gid(-1, 0), 0);
+        }
+    }
+
+    #[test]
+    fn reads_object_layers() {
+        let from_json = parse_json(&json_map("orthogonal", "[1, 2, 3, 4]")).unwrap();
+        let from_xml = parse_xml(&xml_map("orthogonal", "1,2,3,4")).unwrap();
+        for map in [&from_json, &from_xml] {
+            let things = map.object_layer("things").unwrap();
+            let spawn = things.object("spawn").unwrap();
+            assert_eq!(spawn.shape, ObjectShape::Point);
+            assert_eq!(spawn.position, Vec2::new(8.0, 16.0));
+            let door = things.object("door").unwrap();
+            assert_eq!(door.class, "exit");
+            assert_eq!(door.shape, ObjectShape::Rectangle);
+            assert_eq!(door.size, Vec2::new(8.0, 16.0));
+            assert_eq!(door.properties["to"].as_str(), Some("cave"));
+        }
+        let things = from_json.object_layer("things").unwrap();
+        assert_eq!(things.offset, Vec2::new(4.0, 0.0));
+        assert_eq!(
+            things.objects[2].shape,
+            ObjectShape::Polygon(vec![Vec2::ZERO, Vec2::new(4.0, 2.0)])
+        );
+    }
+
+    #[test]
+    fn reads_tile_properties() {
+        let map = parse_json(&json_map("orthogonal", "[1, 2, 3, 4]")).unwrap();
+        let water = map.tile_data(3).unwrap();
+        assert_eq!(water.class, "water");
+        assert_eq!(water.properties["solid"].as_bool(), Some(false));
+        let wall = map.tile_data(6 | FLIPPED_VERTICALLY).unwrap();
+        assert_eq!(wall.class, "wall");
+        assert_eq!(wall.properties["hp"].as_int(), Some(3));
+        assert!(map.tile_data(1).is_none());
+        assert_eq!(
+            map.tilesets[0].image.as_deref(),
+            Some(Path::new("maps/terrain.png"))
+        );
+
+        let map = parse_xml(&xml_map("orthogonal", "1,2,3,4")).unwrap();
+        let water = map.tile_data(3).unwrap();
+        assert_eq!(water.class, "water");
+        assert_eq!(water.properties["solid"], Property::Bool(false));
+        assert_eq!(water.animation.len(), 2);
+    }
+
+    #[test]
+    fn step_advances_animated_tiles() {
+        let mut map = parse_json(&json_map("orthogonal", "[3, 3, 3, 3]")).unwrap();
+        assert_eq!(showing(&map, 3), 2);
+        map.step(0.05);
+        assert_eq!(showing(&map, 3), 2);
+        map.step(0.06);
+        assert_eq!(showing(&map, 3), 3);
+        // the loop is 150 ms long
+        map.step(0.05);
+        assert_eq!(showing(&map, 3), 2);
+        // tiles without an animation stay put
+        assert_eq!(showing(&map, 6), 5);
+    }
+}
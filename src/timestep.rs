@@ -0,0 +1,122 @@
+//! Fixed-rate simulation ticks driven by a frame-time accumulator.
+//!
+//! ```ignore
+//! for _ in 0..timestep.advance(rl.get_frame_time()) {
+//!     sketch::step(&mut state, timestep.dt());
+//! }
+//! sketch::draw(&state, &mut d, timestep.alpha());
+//! ```
+//! `alpha` is how far real time has got towards the next tick, so `draw` can blend the
+//! previous and current tick instead of stuttering when the frame and tick rates differ.
+
+use egui::{DragValue, Ui};
+
+/// Frames longer than this (a breakpoint, dragging the window) are clamped so the
+/// simulation doesn't try to catch up with hundreds of ticks at once.
+const MAX_FRAME_TIME: f32 = 0.25;
+
+#[derive(Debug, Clone)]
+pub struct Timestep {
+    pub ticks_per_second: u32,
+    pub paused: bool,
+    /// Multiplies real time before it reaches the accumulator; below 1.0 is slow motion.
+    pub time_scale: f32,
+    /// Ticks run since startup.
+    pub tick: u64,
+    accumulator: f32,
+    // single steps requested while paused
+    pending_steps: u32,
+}
+
+impl Timestep {
+    pub fn new(ticks_per_second: u32) -> Self {
+        Self {
+            ticks_per_second,
+            paused: false,
+            time_scale: 1.0,
+            tick: 0,
+            accumulator: 0.0,
+            pending_steps: 0,
+        }
+    }
+
+    /// Seconds per tick.
+    pub fn dt(&self) -> f32 {
+        1.0 / self.ticks_per_second.max(1) as f32
+    }
+
+    /// Adds a frame's worth of time and returns how many ticks to run this frame.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        if self.paused {
+            let steps = std::mem::take(&mut self.pending_steps);
+            self.tick += steps as u64;
+            return steps;
+        }
+
+        let dt = self.dt();
+        self.accumulator += frame_time.min(MAX_FRAME_TIME) * self.time_scale;
+        let mut steps = 0;
+        while self.accumulator >= dt {
+            self.accumulator -= dt;
+            steps += 1;
+        }
+        self.tick += steps as u64;
+        steps
+    }
+
+    /// Runs exactly one tick on the next `advance` while paused.
+    pub fn step_once(&mut self) {
+        self.pending_steps += 1;
+    }
+
+    /// Fraction of a tick between the last tick and the next, in `0.0..1.0`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt()).clamp(0.0, 1.0)
+    }
+
+    /// Tick rate, pause, single step and slow motion controls.
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("tick rate");
+            ui.add(
+                DragValue::new(&mut self.ticks_per_second)
+                    .range(1..=240)
+                    .suffix(" Hz"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("time scale");
+            ui.add(
+                DragValue::new(&mut self.time_scale)
+                    .range(0.0..=4.0)
+                    .speed(0.01)
+                    .suffix("x"),
+            );
+            for scale in [0.1, 0.25, 0.5, 1.0] {
+                if ui.small_button(format!("{}x", scale)).clicked() {
+                    self.time_scale = scale;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let label = if self.paused { "Resume" } else { "Pause" };
+            if ui.button(label).clicked() {
+                self.paused = !self.paused;
+            }
+            if ui
+                .add_enabled(self.paused, egui::Button::new("Step"))
+                .clicked()
+            {
+                self.step_once();
+            }
+            ui.monospace(format!("tick {}", self.tick));
+        });
+    }
+
+    /// `ui` in its own window.
+    pub fn window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Time")
+            .default_open(false)
+            .show(ctx, |ui| self.ui(ui));
+    }
+}
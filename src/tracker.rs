@@ -0,0 +1,553 @@
+//! Song position for tracker music (.xm and .mod), for syncing gameplay to the beat.
+//!
+//! raylib streams the audio but won't say where in the song it is, so this reads the
+//! module's order list and patterns, works out when each row starts from the speed,
+//! tempo, jump and break effects, and follows along on fixed timestep ticks. Being
+//! driven by ticks rather than the audio clock, the same tick always lands on the same
+//! row, with or without a sound card. Ticks and the sound card drift apart over time,
+//! though, so while music is actually playing feed `resync` the stream's position.
+//! ```ignore
+//! audio.load_music("theme", "assets/theme.xm")?;
+//! let mut beat = TrackerSync::new(TrackerSong::load("assets/theme.xm")?, FRAMES_PER_SECOND);
+//! audio.play_music("theme", 0.0);
+//! beat.restart();
+//! // step
+//! if let Some(played) = audio.music_time_played() {
+//!     beat.resync(played as f64);
+//! }
+//! for row in beat.step() {
+//!     if row.row % 4 == 0 {
+//!         state.pulse = 1.0;
+//!     }
+//! }
+//! let SongPosition { order, row, tick, .. } = beat.position();
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum TrackerError {
+    Io(std::io::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrackerError::Io(e) => write!(f, "{}", e),
+            TrackerError::Invalid(msg) => write!(f, "invalid module: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {}
+
+impl From<std::io::Error> for TrackerError {
+    fn from(e: std::io::Error) -> Self {
+        TrackerError::Io(e)
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> TrackerError {
+    TrackerError::Invalid(msg.into())
+}
+
+/// Rows to give up at, for songs that never end or loop in a way this can follow.
+const MAX_ROWS: usize = 100_000;
+
+/// Seconds the tick clock may be off from the stream's before `resync` moves it. raylib's
+/// clock only advances an audio buffer at a time, so anything smaller is noise.
+const RESYNC_TOLERANCE: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SongPosition {
+    /// Index into the order list.
+    pub order: usize,
+    pub pattern: usize,
+    pub row: usize,
+    /// Tracker tick within the row, 0 on the row's first.
+    pub tick: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RowTiming {
+    order: usize,
+    pattern: usize,
+    row: usize,
+    /// Seconds from the start of the song.
+    start: f64,
+    ticks: u32,
+    tick_seconds: f64,
+}
+
+/// The order list and pattern effects that decide timing; no notes or samples.
+struct Patterns {
+    orders: Vec<usize>,
+    restart: usize,
+    speed: u32,
+    bpm: u32,
+    /// Per pattern, per row, the (effect, param) pairs of every channel.
+    rows: Vec<Vec<Vec<(u8, u8)>>>,
+    /// Whether F00 stops the song, as in ProTracker, rather than being ignored.
+    f00_stops: bool,
+}
+
+/// When every row of a module starts.
+pub struct TrackerSong {
+    rows: Vec<RowTiming>,
+    /// Row the song goes back to once it ends.
+    loop_start: usize,
+    length: f64,
+}
+
+impl TrackerSong {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TrackerError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Reads an XM, or failing that a 31 sample MOD.
+    pub fn parse(bytes: &[u8]) -> Result<Self, TrackerError> {
+        let patterns = if bytes.starts_with(b"Extended Module: ") {
+            parse_xm(bytes)?
+        } else {
+            parse_mod(bytes)?
+        };
+        Ok(Self::from_patterns(&patterns))
+    }
+
+    fn from_patterns(p: &Patterns) -> Self {
+        let mut rows: Vec<RowTiming> = Vec::new();
+        let mut seen = HashSet::new();
+        let (mut speed, mut bpm) = (p.speed.max(1), p.bpm.max(1));
+        let (mut order, mut row) = (0, 0);
+        let mut time = 0.0;
+        let mut loop_to = None;
+        while rows.len() < MAX_ROWS {
+            let Some(&pattern) = p.orders.get(order) else {
+                break;
+            };
+            let Some(cells) = p.rows.get(pattern).and_then(|rows| rows.get(row)) else {
+                // a missing pattern or a break past the end moves on
+                order += 1;
+                row = 0;
+                continue;
+            };
+            if !seen.insert((order, row)) {
+                loop_to = Some((order, row));
+                break;
+            }
+
+            let (mut jump, mut break_to, mut delay, mut stop) = (None, None, 0, false);
+            for &(effect, param) in cells {
+                match effect {
+                    0x0f if param == 0 => stop = p.f00_stops,
+                    0x0f if param < 32 => speed = param as u32,
+                    0x0f => bpm = param as u32,
+                    0x0b => jump = Some(param as usize),
+                    0x0d => break_to = Some((param >> 4) as usize * 10 + (param & 0x0f) as usize),
+                    0x0e if param >> 4 == 0x0e => delay = (param & 0x0f) as u32,
+                    _ => {}
+                }
+            }
+
+            // a row is 2.5 / bpm seconds per tick, per the Amiga's 50Hz at 125 bpm
+            let tick_seconds = 2.5 / bpm as f64;
+            let ticks = speed * (1 + delay);
+            rows.push(RowTiming {
+                order,
+                pattern,
+                row,
+                start: time,
+                ticks,
+                tick_seconds,
+            });
+            time += ticks as f64 * tick_seconds;
+
+            if stop {
+                break;
+            }
+            if jump.is_some() || break_to.is_some() {
+                order = jump.unwrap_or(order + 1);
+                row = break_to.unwrap_or(0);
+            } else {
+                row += 1;
+                if row >= p.rows[pattern].len() {
+                    order += 1;
+                    row = 0;
+                }
+            }
+        }
+
+        let (order, row) = loop_to.unwrap_or((p.restart, 0));
+        let loop_start = rows
+            .iter()
+            .position(|r| r.order == order && r.row == row)
+            .unwrap_or(0);
+        Self {
+            rows,
+            loop_start,
+            length: time,
+        }
+    }
+
+    /// Seconds until the song first ends or loops.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Where the song is `seconds` after it started, looping like raylib's player.
+    pub fn position_at(&self, seconds: f64) -> SongPosition {
+        let Some(index) = self
+            .row_serial(seconds)
+            .map(|serial| self.row_index(serial))
+        else {
+            return SongPosition::default();
+        };
+        let timing = &self.rows[index];
+        let into_row = self.wrap_time(seconds) - timing.start;
+        SongPosition {
+            order: timing.order,
+            pattern: timing.pattern,
+            row: timing.row,
+            tick: ((into_row / timing.tick_seconds) as u32).min(timing.ticks - 1),
+        }
+    }
+
+    /// Seconds folded back into the first play through.
+    fn wrap_time(&self, seconds: f64) -> f64 {
+        let loop_time = self.rows.get(self.loop_start).map_or(0.0, |r| r.start);
+        let loop_length = self.length - loop_time;
+        if seconds < self.length || loop_length <= 0.0 {
+            seconds.min(self.length)
+        } else {
+            loop_time + (seconds - loop_time) % loop_length
+        }
+    }
+
+    /// Counts rows played across loops, so rows crossed between two times can be listed.
+    fn row_serial(&self, seconds: f64) -> Option<usize> {
+        if self.rows.is_empty() {
+            return None;
+        }
+        let index_at = |t: f64| (self.rows.partition_point(|r| r.start <= t)).max(1) - 1;
+        let loop_time = self.rows[self.loop_start].start;
+        let loop_length = self.length - loop_time;
+        if seconds < self.length || loop_length <= 0.0 {
+            return Some(index_at(seconds));
+        }
+        let loops = ((seconds - loop_time) / loop_length) as usize;
+        let index = index_at(self.wrap_time(seconds));
+        Some(index + loops * (self.rows.len() - self.loop_start))
+    }
+
+    fn row_index(&self, serial: usize) -> usize {
+        if serial < self.rows.len() {
+            serial
+        } else {
+            let loop_rows = self.rows.len() - self.loop_start;
+            self.loop_start + (serial - self.loop_start) % loop_rows
+        }
+    }
+
+    fn row_position(&self, serial: usize) -> SongPosition {
+        let timing = &self.rows[self.row_index(serial)];
+        SongPosition {
+            order: timing.order,
+            pattern: timing.pattern,
+            row: timing.row,
+            tick: 0,
+        }
+    }
+}
+
+/// Follows a song on fixed timestep ticks.
+pub struct TrackerSync {
+    song: TrackerSong,
+    ticks_per_second: u32,
+    ticks: u64,
+    /// Serial of the last row reported by `step`.
+    last_row: Option<usize>,
+    started: Vec<SongPosition>,
+}
+
+impl TrackerSync {
+    /// `ticks_per_second` is the sketch's `FRAMES_PER_SECOND`.
+    pub fn new(song: TrackerSong, ticks_per_second: u32) -> Self {
+        Self {
+            song,
+            ticks_per_second: ticks_per_second.max(1),
+            ticks: 0,
+            last_row: None,
+            started: Vec::new(),
+        }
+    }
+
+    pub fn song(&self) -> &TrackerSong {
+        &self.song
+    }
+
+    /// Back to the top; call alongside `Audio::play_music`.
+    pub fn restart(&mut self) {
+        self.ticks = 0;
+        self.last_row = None;
+        self.started.clear();
+    }
+
+    /// Advances one tick and returns the rows that started during it, oldest first.
+    /// Usually none or one, more when the song is faster than the tick rate.
+    pub fn step(&mut self) -> &[SongPosition] {
+        self.ticks += 1;
+        self.started.clear();
+        if let Some(current) = self.song.row_serial(self.seconds()) {
+            let first = self.last_row.map_or(0, |last| last + 1);
+            for serial in first..=current {
+                self.started.push(self.song.row_position(serial));
+            }
+            // after a resync backwards, hold on to the furthest row until it's passed again
+            self.last_row = Some(self.last_row.map_or(current, |last| last.max(current)));
+        }
+        &self.started
+    }
+
+    /// Moves the tick clock to `played_seconds`, the stream's own position from
+    /// `Audio::music_time_played`, if they've drifted apart by more than a buffer's worth;
+    /// call it every tick while the music plays. Rows already reported aren't reported
+    /// again, and rows jumped over come out of the next `step`.
+    pub fn resync(&mut self, played_seconds: f64) {
+        let now = self.seconds();
+        // the stream's clock starts over when the song loops, so compare within a play
+        // through; a huge gap means one clock has wrapped and the other hasn't yet
+        let drift = played_seconds - self.song.wrap_time(now);
+        if drift.abs() < RESYNC_TOLERANCE || drift.abs() > self.song.length() / 2.0 {
+            return;
+        }
+        let ticks = (now + drift) * self.ticks_per_second as f64;
+        self.ticks = ticks.round().max(0.0) as u64;
+    }
+
+    pub fn position(&self) -> SongPosition {
+        self.song.position_at(self.seconds())
+    }
+
+    /// Seconds since `restart`, by tick count.
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 / self.ticks_per_second as f64
+    }
+}
+
+/* ----------- formats -------------------------------------------------- */
+
+fn u16_le(bytes: &[u8], at: usize) -> Result<usize, TrackerError> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("truncated"))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> Result<usize, TrackerError> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| invalid("truncated"))
+}
+
+fn parse_xm(bytes: &[u8]) -> Result<Patterns, TrackerError> {
+    let header_size = u32_le(bytes, 60)?;
+    let song_length = u16_le(bytes, 64)?;
+    let restart = u16_le(bytes, 66)?;
+    let channels = u16_le(bytes, 68)?;
+    let pattern_count = u16_le(bytes, 70)?;
+    let speed = u16_le(bytes, 76)? as u32;
+    let bpm = u16_le(bytes, 78)? as u32;
+    let orders = bytes
+        .get(80..80 + song_length.min(256))
+        .ok_or_else(|| invalid("truncated order table"))?
+        .iter()
+        .map(|&o| o as usize)
+        .collect();
+
+    let mut rows = Vec::with_capacity(pattern_count);
+    let mut at = 60 + header_size;
+    for _ in 0..pattern_count {
+        let pattern_header = u32_le(bytes, at)?;
+        let row_count = u16_le(bytes, at + 5)?;
+        let packed_size = u16_le(bytes, at + 7)?;
+        let data_start = at + pattern_header;
+        let data = bytes
+            .get(data_start..data_start + packed_size)
+            .ok_or_else(|| invalid("truncated pattern"))?;
+        at = data_start + packed_size;
+
+        let mut pattern = vec![Vec::new(); row_count];
+        // an empty pattern has no data at all
+        let mut i = 0;
+        'rows: for row in pattern.iter_mut() {
+            for _ in 0..channels {
+                let Some(&first) = data.get(i) else {
+                    break 'rows;
+                };
+                // a set top bit says which of note, instrument, volume, effect and
+                // param follow; otherwise all five do, starting with this byte
+                let flags = if first & 0x80 != 0 {
+                    i += 1;
+                    first
+                } else {
+                    0x1f
+                };
+                let mut field = |bit: u8| {
+                    if flags & bit == 0 {
+                        return 0;
+                    }
+                    i += 1;
+                    data.get(i - 1).copied().unwrap_or(0)
+                };
+                field(0x01);
+                field(0x02);
+                field(0x04);
+                let effect = field(0x08);
+                let param = field(0x10);
+                if effect != 0 || param != 0 {
+                    row.push((effect, param));
+                }
+            }
+        }
+        rows.push(pattern);
+    }
+
+    Ok(Patterns {
+        orders,
+        restart,
+        speed,
+        bpm,
+        rows,
+        f00_stops: false,
+    })
+}
+
+fn parse_mod(bytes: &[u8]) -> Result<Patterns, TrackerError> {
+    let signature = bytes
+        .get(1080..1084)
+        .ok_or_else(|| invalid("too short for a MOD"))?;
+    let digit = |b: u8| b.is_ascii_digit().then(|| (b - b'0') as usize);
+    let channels = match signature {
+        b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => 4,
+        b"FLT8" | b"OKTA" | b"CD81" => 8,
+        [d, b'C', b'H', b'N'] => digit(*d).ok_or_else(|| invalid("unknown signature"))?,
+        [a, b, b'C', b'H'] => match (digit(*a), digit(*b)) {
+            (Some(a), Some(b)) => a * 10 + b,
+            _ => return Err(invalid("unknown signature")),
+        },
+        _ => return Err(invalid("not an XM or 31 sample MOD")),
+    };
+    if channels == 0 {
+        return Err(invalid("no channels"));
+    }
+
+    let song_length = (bytes[950] as usize).min(128);
+    let restart = bytes[951] as usize;
+    let order_table = &bytes[952..1080];
+    let orders: Vec<usize> = order_table[..song_length]
+        .iter()
+        .map(|&o| o as usize)
+        .collect();
+    // every pattern in the table is stored, even ones past the song's length
+    let pattern_count = order_table
+        .iter()
+        .map(|&o| o as usize + 1)
+        .max()
+        .unwrap_or(0);
+
+    let pattern_size = 64 * channels * 4;
+    let mut rows = Vec::with_capacity(pattern_count);
+    for p in 0..pattern_count {
+        let start = 1084 + p * pattern_size;
+        let data = bytes
+            .get(start..start + pattern_size)
+            .ok_or_else(|| invalid("truncated pattern"))?;
+        let pattern = data
+            .chunks(channels * 4)
+            .map(|row| {
+                row.chunks(4)
+                    .map(|cell| (cell[2] & 0x0f, cell[3]))
+                    .filter(|&(effect, param)| effect != 0 || param != 0)
+                    .collect()
+            })
+            .collect();
+        rows.push(pattern);
+    }
+
+    Ok(Patterns {
+        orders,
+        // 127 is a common "no restart" marker
+        restart: if restart < song_length { restart } else { 0 },
+        speed: 6,
+        bpm: 125,
+        rows,
+        f00_stops: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4 channel MOD playing one empty pattern: 64 rows of 0.12 seconds.
+    fn empty_mod(signature: &[u8; 4]) -> Vec<u8> {
+        let mut bytes = vec![0; 1084 + 64 * 4 * 4];
+        bytes[950] = 1;
+        bytes[1080..1084].copy_from_slice(signature);
+        bytes
+    }
+
+    #[test]
+    fn mod_rows_follow_speed_and_tempo() {
+        let song = TrackerSong::parse(&empty_mod(b"M.K.")).unwrap();
+        assert!((song.length() - 64.0 * 0.12).abs() < 1e-9);
+        assert_eq!(song.position_at(0.25).row, 2);
+        // loops back to the top
+        assert_eq!(song.position_at(song.length() + 0.13).row, 1);
+    }
+
+    #[test]
+    fn zero_channel_mods_are_rejected() {
+        for signature in [b"0CHN", b"00CH"] {
+            let result = TrackerSong::parse(&empty_mod(signature));
+            assert!(matches!(result, Err(TrackerError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn resync_moves_to_the_stream_without_repeating_rows() {
+        let song = TrackerSong::parse(&empty_mod(b"M.K.")).unwrap();
+        let mut beat = TrackerSync::new(song, 60);
+        for _ in 0..60 {
+            beat.step();
+        }
+        assert_eq!(beat.position().row, 8);
+
+        // within tolerance: left alone
+        beat.resync(1.05);
+        assert_eq!(beat.seconds(), 1.0);
+
+        // ahead: the rows jumped over all come out of the next step
+        beat.resync(1.5);
+        let rows: Vec<usize> = beat.step().iter().map(|p| p.row).collect();
+        assert_eq!(rows, [9, 10, 11, 12]);
+
+        // behind: nothing is reported twice
+        beat.resync(1.0);
+        let mut steps = 0;
+        let rows = loop {
+            steps += 1;
+            let rows: Vec<usize> = beat.step().iter().map(|p| p.row).collect();
+            if !rows.is_empty() {
+                break rows;
+            }
+        };
+        assert_eq!(rows, [13]);
+        // row 13 starts at 1.56 seconds, tick 94
+        assert_eq!(steps, 34);
+    }
+}
@@ -0,0 +1,249 @@
+//! Scene transitions at canvas resolution.
+//!
+//! The last frame before a transition is kept as the outgoing image; while it plays,
+//! each new frame of the canvas is captured as the incoming image and a shader blends
+//! the two back into the canvas. Only the canvas layer is transitioned, so anything that
+//! should take part belongs there rather than in a higher-resolution layer on top.
+
+use glam::UVec2;
+use raylib::core::error::Error;
+use raylib::prelude::*;
+
+const TRANSITION_FS: &str = r#"
+#version 330
+
+#define KIND_FADE 0
+#define KIND_IRIS 1
+#define KIND_WIPE_HORIZONTAL 2
+#define KIND_WIPE_VERTICAL 3
+#define KIND_DISSOLVE 4
+#define KIND_MOSAIC 5
+
+in vec2 fragTexCoord;
+
+uniform sampler2D texture0; // outgoing
+uniform sampler2D incoming;
+uniform int kind;
+uniform float progress;
+uniform vec4 color;
+uniform vec2 canvasSize;
+uniform float maxBlock;
+
+out vec4 finalColor;
+
+// both images are render textures drawn flipped, so v runs bottom to top
+vec4 at(sampler2D tex, vec2 pixel)
+{
+    return texture(tex, vec2(pixel.x/canvasSize.x, 1.0 - pixel.y/canvasSize.y));
+}
+
+float bayer4(vec2 pixel)
+{
+    const float m[16] = float[16](
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0);
+    int x = int(mod(pixel.x, 4.0));
+    int y = int(mod(pixel.y, 4.0));
+    return (m[y*4 + x] + 0.5)/16.0;
+}
+
+void main()
+{
+    // pixel centre in canvas space, top left origin
+    vec2 pixel = floor(vec2(fragTexCoord.x, 1.0 - fragTexCoord.y)*canvasSize) + 0.5;
+    // 0 -> 1 -> 0 over the transition, for effects that cover then uncover
+    float cover = 1.0 - abs(progress*2.0 - 1.0);
+    bool second = progress >= 0.5;
+
+    if (kind == KIND_FADE)
+    {
+        vec4 scene = second ? at(incoming, pixel) : at(texture0, pixel);
+        finalColor = mix(scene, color, cover);
+    }
+    else if (kind == KIND_IRIS)
+    {
+        float radius = length(canvasSize*0.5)*(1.0 - cover);
+        vec4 scene = second ? at(incoming, pixel) : at(texture0, pixel);
+        finalColor = length(pixel - canvasSize*0.5) < radius ? scene : color;
+    }
+    else if (kind == KIND_WIPE_HORIZONTAL)
+    {
+        finalColor = pixel.x < progress*canvasSize.x ? at(incoming, pixel) : at(texture0, pixel);
+    }
+    else if (kind == KIND_WIPE_VERTICAL)
+    {
+        finalColor = pixel.y < progress*canvasSize.y ? at(incoming, pixel) : at(texture0, pixel);
+    }
+    else if (kind == KIND_DISSOLVE)
+    {
+        finalColor = bayer4(pixel) < progress ? at(incoming, pixel) : at(texture0, pixel);
+    }
+    else // KIND_MOSAIC
+    {
+        float block = max(1.0, floor(mix(1.0, maxBlock, cover)));
+        vec2 snapped = (floor(pixel/block) + 0.5)*block;
+        finalColor = second ? at(incoming, snapped) : at(texture0, snapped);
+    }
+}
+"#;
+
+#[derive(Debug, Clone, Copy)]
+pub enum TransitionKind {
+    /// Fades out to the color, then in to the new scene.
+    Fade(Color),
+    /// A circle closes on the old scene, leaving the color, then opens on the new one.
+    Iris(Color),
+    /// The new scene slides in from the left edge.
+    WipeHorizontal,
+    /// The new scene slides in from the top edge.
+    WipeVertical,
+    /// Pixels switch over in a 4x4 ordered dither pattern.
+    Dissolve,
+    /// The old scene pixelates into blocks up to this size, then the new one sharpens.
+    Mosaic(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    /// Length of the whole transition.
+    pub ticks: u32,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, ticks: u32) -> Self {
+        Self { kind, ticks }
+    }
+
+    pub fn fade(color: Color, ticks: u32) -> Self {
+        Self::new(TransitionKind::Fade(color), ticks)
+    }
+}
+
+struct Locations {
+    incoming: i32,
+    kind: i32,
+    progress: i32,
+    color: i32,
+    canvas_size: i32,
+    max_block: i32,
+}
+
+/// The blend shader plus the outgoing and incoming canvas captures.
+pub struct TransitionRenderer {
+    shader: Shader,
+    locs: Locations,
+    outgoing: RenderTexture2D,
+    incoming: RenderTexture2D,
+}
+
+impl TransitionRenderer {
+    pub fn new(rl: &mut RaylibHandle, rlt: &RaylibThread, dims: UVec2) -> Result<Self, Error> {
+        let shader = rl.load_shader_from_memory(rlt, None, Some(TRANSITION_FS));
+        let locs = Locations {
+            incoming: shader.get_shader_location("incoming"),
+            kind: shader.get_shader_location("kind"),
+            progress: shader.get_shader_location("progress"),
+            color: shader.get_shader_location("color"),
+            canvas_size: shader.get_shader_location("canvasSize"),
+            max_block: shader.get_shader_location("maxBlock"),
+        };
+        Ok(Self {
+            shader,
+            locs,
+            outgoing: rl.load_render_texture(rlt, dims.x, dims.y)?,
+            incoming: rl.load_render_texture(rlt, dims.x, dims.y)?,
+        })
+    }
+
+    pub fn dims(&self) -> UVec2 {
+        UVec2::new(
+            self.outgoing.texture.width as u32,
+            self.outgoing.texture.height as u32,
+        )
+    }
+
+    /// Keeps a copy of the canvas as the image to transition away from.
+    pub fn capture_outgoing(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        canvas: &RenderTexture2D,
+    ) {
+        copy(d, rlt, canvas, &mut self.outgoing);
+    }
+
+    /// Captures the canvas as the incoming image, then draws the blend of outgoing and
+    /// incoming at `progress` (0.0 to 1.0) back into it.
+    pub fn blend_into(
+        &mut self,
+        d: &mut RaylibDrawHandle,
+        rlt: &RaylibThread,
+        canvas: &mut RenderTexture2D,
+        transition: &Transition,
+        progress: f32,
+    ) {
+        copy(d, rlt, canvas, &mut self.incoming);
+
+        let (kind, color, max_block) = match transition.kind {
+            TransitionKind::Fade(color) => (0, color, 1),
+            TransitionKind::Iris(color) => (1, color, 1),
+            TransitionKind::WipeHorizontal => (2, Color::BLANK, 1),
+            TransitionKind::WipeVertical => (3, Color::BLANK, 1),
+            TransitionKind::Dissolve => (4, Color::BLANK, 1),
+            TransitionKind::Mosaic(max_block) => (5, Color::BLANK, max_block.max(1)),
+        };
+        let dims = self.dims();
+        let locs = &self.locs;
+        self.shader.set_shader_value(locs.kind, kind);
+        self.shader
+            .set_shader_value(locs.progress, progress.clamp(0.0, 1.0));
+        self.shader.set_shader_value(
+            locs.color,
+            [
+                color.r as f32 / 255.0,
+                color.g as f32 / 255.0,
+                color.b as f32 / 255.0,
+                color.a as f32 / 255.0,
+            ],
+        );
+        self.shader
+            .set_shader_value(locs.canvas_size, [dims.x as f32, dims.y as f32]);
+        self.shader
+            .set_shader_value(locs.max_block, max_block as f32);
+
+        let raw_shader: ffi::Shader = *self.shader.as_ref();
+        let mut d = d.begin_texture_mode(rlt, canvas);
+        d.clear_background(Color::BLANK);
+        let mut d = d.begin_shader_mode(&mut self.shader);
+        // samplers have to be set while the shader is active, after which raylib binds
+        // the texture alongside texture0 for the draw
+        unsafe {
+            ffi::SetShaderValueTexture(raw_shader, locs.incoming, self.incoming.texture);
+        }
+        d.draw_texture_rec(
+            &self.outgoing,
+            flipped_source(dims),
+            Vector2::zero(),
+            Color::WHITE,
+        );
+    }
+}
+
+fn flipped_source(dims: UVec2) -> Rectangle {
+    Rectangle::new(0.0, 0.0, dims.x as f32, -(dims.y as f32))
+}
+
+fn copy(
+    d: &mut RaylibDrawHandle,
+    rlt: &RaylibThread,
+    from: &RenderTexture2D,
+    to: &mut RenderTexture2D,
+) {
+    let dims = UVec2::new(from.texture.width as u32, from.texture.height as u32);
+    let mut d = d.begin_texture_mode(rlt, to);
+    d.clear_background(Color::BLANK);
+    d.draw_texture_rec(from, flipped_source(dims), Vector2::zero(), Color::WHITE);
+}
@@ -0,0 +1,773 @@
+//! Tweens and easing curves, advanced one fixed tick at a time.
+//!
+//! A `Tween` eases a value between two ends over a number of ticks and can hand each
+//! new value to a target, some context `C` passed to `step`. Sequences, parallel groups,
+//! waits and calls combine into anything longer. Keep the animation and its target in
+//! separate fields so both can be borrowed at once; it only moves when stepped, so it
+//! pauses with the simulation and plays back the same every time.
+//! ```ignore
+//! // a value on its own
+//! let mut bob = Tween::new(0.0, 3.0, 40, Ease::InOutSine).yoyo().repeat(Repeat::Forever);
+//! bob.step(&mut ());
+//! let y = base_y + bob.value();
+//!
+//! // driving fields of a target
+//! let mut intro = Sequence::new()
+//!     .then(Tween::new(-40.0, 8.0, 30, Ease::OutBack).on_update(|t: &mut Title, x| t.x = x))
+//!     .then(Wait::new(60))
+//!     .then(Parallel::new()
+//!         .with(Tween::new(8.0, 200.0, 20, Ease::InQuad).on_update(|t: &mut Title, x| t.x = x))
+//!         .with(Tween::new(Color::WHITE, Color::BLANK, 20, Ease::Linear).on_update(|t: &mut Title, c| t.color = c)))
+//!     .then(Call::new(|t: &mut Title| t.done = true));
+//! // step
+//! intro.step(&mut state.title);
+//! ```
+
+use glam::{IVec2, Vec2, Vec3, Vec4};
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+/* ----------- easing --------------------------------------------------- */
+
+/// Robert Penner's easing curves, mapping progress 0.0..=1.0 to eased progress. `Back`
+/// and `Elastic` overshoot outside that range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ease {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InQuart,
+    OutQuart,
+    InOutQuart,
+    InQuint,
+    OutQuint,
+    InOutQuint,
+    InSine,
+    OutSine,
+    InOutSine,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    InCirc,
+    OutCirc,
+    InOutCirc,
+    InBack,
+    OutBack,
+    InOutBack,
+    InElastic,
+    OutElastic,
+    InOutElastic,
+    InBounce,
+    OutBounce,
+    InOutBounce,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        let power_in = |n: i32| t.powi(n);
+        let power_out = |n: i32| 1.0 - (1.0 - t).powi(n);
+        let power_in_out = |n: i32| {
+            if t < 0.5 {
+                2f32.powi(n - 1) * t.powi(n)
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(n) / 2.0
+            }
+        };
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+        const ELASTIC: f32 = 2.0 * PI / 3.0;
+        const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+        match self {
+            Ease::Linear => t,
+            Ease::InQuad => power_in(2),
+            Ease::OutQuad => power_out(2),
+            Ease::InOutQuad => power_in_out(2),
+            Ease::InCubic => power_in(3),
+            Ease::OutCubic => power_out(3),
+            Ease::InOutCubic => power_in_out(3),
+            Ease::InQuart => power_in(4),
+            Ease::OutQuart => power_out(4),
+            Ease::InOutQuart => power_in_out(4),
+            Ease::InQuint => power_in(5),
+            Ease::OutQuint => power_out(5),
+            Ease::InOutQuint => power_in_out(5),
+            Ease::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Ease::OutSine => (t * PI / 2.0).sin(),
+            Ease::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Ease::InExpo if t == 0.0 => 0.0,
+            Ease::InExpo => 2f32.powf(10.0 * t - 10.0),
+            Ease::OutExpo if t == 1.0 => 1.0,
+            Ease::OutExpo => 1.0 - 2f32.powf(-10.0 * t),
+            Ease::InOutExpo if t == 0.0 || t == 1.0 => t,
+            Ease::InOutExpo if t < 0.5 => 2f32.powf(20.0 * t - 10.0) / 2.0,
+            Ease::InOutExpo => (2.0 - 2f32.powf(-20.0 * t + 10.0)) / 2.0,
+            Ease::InCirc => 1.0 - (1.0 - t * t).sqrt(),
+            Ease::OutCirc => (1.0 - (t - 1.0).powi(2)).sqrt(),
+            Ease::InOutCirc if t < 0.5 => (1.0 - (1.0 - (2.0 * t).powi(2)).sqrt()) / 2.0,
+            Ease::InOutCirc => ((1.0 - (-2.0 * t + 2.0).powi(2)).sqrt() + 1.0) / 2.0,
+            Ease::InBack => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Ease::OutBack => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Ease::InOutBack if t < 0.5 => {
+                (2.0 * t).powi(2) * ((BACK_IN_OUT + 1.0) * 2.0 * t - BACK_IN_OUT) / 2.0
+            }
+            Ease::InOutBack => {
+                ((2.0 * t - 2.0).powi(2) * ((BACK_IN_OUT + 1.0) * (t * 2.0 - 2.0) + BACK_IN_OUT)
+                    + 2.0)
+                    / 2.0
+            }
+            Ease::InElastic | Ease::OutElastic | Ease::InOutElastic if t == 0.0 || t == 1.0 => t,
+            Ease::InElastic => -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC).sin(),
+            Ease::OutElastic => 2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0,
+            Ease::InOutElastic if t < 0.5 => {
+                -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.0
+            }
+            Ease::InOutElastic => {
+                2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin() / 2.0
+                    + 1.0
+            }
+            Ease::InBounce => 1.0 - bounce_out(1.0 - t),
+            Ease::OutBounce => bounce_out(t),
+            Ease::InOutBounce if t < 0.5 => (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0,
+            Ease::InOutBounce => (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0,
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values a tween can move between.
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec2::lerp(self, to, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec3::lerp(self, to, t)
+    }
+}
+
+impl Lerp for Vec4 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec4::lerp(self, to, t)
+    }
+}
+
+/// Rounded to the nearest pixel, for positions that should move in whole steps.
+impl Lerp for IVec2 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self.as_vec2().lerp(to.as_vec2(), t).round().as_ivec2()
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let channel =
+            |a: u8, b: u8| Lerp::lerp(a as f32, b as f32, t).round().clamp(0.0, 255.0) as u8;
+        Color::new(
+            channel(self.r, to.r),
+            channel(self.g, to.g),
+            channel(self.b, to.b),
+            channel(self.a, to.a),
+        )
+    }
+}
+
+/* ----------- animations ----------------------------------------------- */
+
+/// Anything that plays out over ticks against a target `C`.
+pub trait Animation<C> {
+    /// Advances one tick. True once finished, and on every step after.
+    fn step(&mut self, target: &mut C) -> bool;
+    /// Back to the start, as if never stepped.
+    fn reset(&mut self);
+    fn is_finished(&self) -> bool;
+    /// Ticks from start to finish, None for ones that never end.
+    fn duration(&self) -> Option<u32>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Plays this many extra times; `Times(0)`, the default, plays once.
+    Times(u32),
+    Forever,
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Repeat::Times(0)
+    }
+}
+
+impl Repeat {
+    fn runs(self) -> Option<u32> {
+        match self {
+            Repeat::Times(n) => Some(n.saturating_add(1)),
+            Repeat::Forever => None,
+        }
+    }
+}
+
+type Update<C, T> = Box<dyn FnMut(&mut C, T)>;
+type Callback<C> = Box<dyn FnMut(&mut C)>;
+
+/// Eases from one value to another over `ticks` steps.
+pub struct Tween<T: Lerp, C = ()> {
+    from: T,
+    to: T,
+    ticks: u32,
+    ease: Ease,
+    delay: u32,
+    repeat: Repeat,
+    /// Every other run plays backwards.
+    yoyo: bool,
+    on_update: Option<Update<C, T>>,
+    on_complete: Option<Callback<C>>,
+    elapsed: u32,
+    run: u32,
+    value: T,
+    finished: bool,
+}
+
+impl<T: Lerp, C> Tween<T, C> {
+    pub fn new(from: T, to: T, ticks: u32, ease: Ease) -> Self {
+        Self {
+            from,
+            to,
+            ticks: ticks.max(1),
+            ease,
+            delay: 0,
+            repeat: Repeat::default(),
+            yoyo: false,
+            on_update: None,
+            on_complete: None,
+            elapsed: 0,
+            run: 0,
+            value: from,
+            finished: false,
+        }
+    }
+
+    /// Waits this many ticks before the first run.
+    pub fn delay(mut self, ticks: u32) -> Self {
+        self.delay = ticks;
+        self
+    }
+
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Runs alternate direction, so `Repeat::Times(1)` goes there and back.
+    pub fn yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+
+    /// Called with the new value on every tick it changes.
+    pub fn on_update(mut self, update: impl FnMut(&mut C, T) + 'static) -> Self {
+        self.on_update = Some(Box::new(update));
+        self
+    }
+
+    /// Called once, on the tick the last run ends.
+    pub fn on_complete(mut self, complete: impl FnMut(&mut C) + 'static) -> Self {
+        self.on_complete = Some(Box::new(complete));
+        self
+    }
+
+    /// The current value; `from` until the tween starts.
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    /// Progress through the current run, 0.0 to 1.0, before easing.
+    pub fn progress(&self) -> f32 {
+        self.elapsed.saturating_sub(self.delay) as f32 / self.ticks as f32
+    }
+}
+
+impl<T: Lerp, C> Animation<C> for Tween<T, C> {
+    fn step(&mut self, target: &mut C) -> bool {
+        if self.finished {
+            return true;
+        }
+        self.elapsed += 1;
+        if self.elapsed <= self.delay {
+            return false;
+        }
+
+        let backwards = self.yoyo && self.run % 2 == 1;
+        let t = self.progress();
+        let eased = self.ease.apply(if backwards { 1.0 - t } else { t });
+        self.value = self.from.lerp(self.to, eased);
+        if let Some(update) = &mut self.on_update {
+            update(target, self.value);
+        }
+
+        if self.elapsed - self.delay >= self.ticks {
+            self.run += 1;
+            if self.repeat.runs().is_some_and(|runs| self.run >= runs) {
+                self.finished = true;
+                if let Some(complete) = &mut self.on_complete {
+                    complete(target);
+                }
+            } else {
+                // the delay is only at the very start
+                self.elapsed = self.delay;
+            }
+        }
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0;
+        self.run = 0;
+        self.value = self.from;
+        self.finished = false;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn duration(&self) -> Option<u32> {
+        Some(self.delay + self.ticks * self.repeat.runs()?)
+    }
+}
+
+/// Does nothing for a while; a delay between the parts of a sequence.
+pub struct Wait {
+    ticks: u32,
+    elapsed: u32,
+}
+
+impl Wait {
+    pub fn new(ticks: u32) -> Self {
+        Self { ticks, elapsed: 0 }
+    }
+}
+
+impl<C> Animation<C> for Wait {
+    fn step(&mut self, _target: &mut C) -> bool {
+        self.elapsed = (self.elapsed + 1).min(self.ticks);
+        self.elapsed >= self.ticks
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.ticks
+    }
+
+    fn duration(&self) -> Option<u32> {
+        Some(self.ticks)
+    }
+}
+
+/// Runs a closure once. Takes no time, so in a sequence it fires on the same tick the
+/// part before it finishes.
+pub struct Call<C> {
+    call: Callback<C>,
+    done: bool,
+}
+
+impl<C> Call<C> {
+    pub fn new(call: impl FnMut(&mut C) + 'static) -> Self {
+        Self {
+            call: Box::new(call),
+            done: false,
+        }
+    }
+}
+
+impl<C> Animation<C> for Call<C> {
+    fn step(&mut self, target: &mut C) -> bool {
+        if !self.done {
+            (self.call)(target);
+            self.done = true;
+        }
+        true
+    }
+
+    fn reset(&mut self) {
+        self.done = false;
+    }
+
+    fn is_finished(&self) -> bool {
+        self.done
+    }
+
+    fn duration(&self) -> Option<u32> {
+        Some(0)
+    }
+}
+
+/// Plays its parts one after another.
+pub struct Sequence<C> {
+    parts: Vec<Box<dyn Animation<C>>>,
+    current: usize,
+    repeat: Repeat,
+    run: u32,
+    on_complete: Option<Callback<C>>,
+    finished: bool,
+}
+
+impl<C> Default for Sequence<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Sequence<C> {
+    pub fn new() -> Self {
+        Self {
+            parts: Vec::new(),
+            current: 0,
+            repeat: Repeat::default(),
+            run: 0,
+            on_complete: None,
+            finished: false,
+        }
+    }
+
+    pub fn then(mut self, part: impl Animation<C> + 'static) -> Self {
+        self.parts.push(Box::new(part));
+        self
+    }
+
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn on_complete(mut self, complete: impl FnMut(&mut C) + 'static) -> Self {
+        self.on_complete = Some(Box::new(complete));
+        self
+    }
+}
+
+impl<C> Animation<C> for Sequence<C> {
+    fn step(&mut self, target: &mut C) -> bool {
+        if self.finished {
+            return true;
+        }
+        // one tick for the part that's playing, then any instant parts that follow it
+        let mut ticked = false;
+        loop {
+            let Some(part) = self.parts.get_mut(self.current) else {
+                self.run += 1;
+                if self.repeat.runs().is_some_and(|runs| self.run >= runs)
+                    || self.parts.iter().all(|part| part.duration() == Some(0))
+                {
+                    self.finished = true;
+                    if let Some(complete) = &mut self.on_complete {
+                        complete(target);
+                    }
+                    return true;
+                }
+                // the next run starts on the next tick
+                self.current = 0;
+                for part in &mut self.parts {
+                    part.reset();
+                }
+                return false;
+            };
+            let instant = part.duration() == Some(0);
+            if ticked && !instant {
+                return false;
+            }
+            if part.step(target) {
+                self.current += 1;
+            } else {
+                return false;
+            }
+            ticked |= !instant;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+        self.run = 0;
+        self.finished = false;
+        for part in &mut self.parts {
+            part.reset();
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn duration(&self) -> Option<u32> {
+        let once = self
+            .parts
+            .iter()
+            .map(|part| part.duration())
+            .sum::<Option<u32>>()?;
+        Some(once * self.repeat.runs()?)
+    }
+}
+
+/// Plays its parts together, finishing when the longest does.
+pub struct Parallel<C> {
+    parts: Vec<Box<dyn Animation<C>>>,
+    on_complete: Option<Callback<C>>,
+    finished: bool,
+}
+
+impl<C> Default for Parallel<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Parallel<C> {
+    pub fn new() -> Self {
+        Self {
+            parts: Vec::new(),
+            on_complete: None,
+            finished: false,
+        }
+    }
+
+    pub fn with(mut self, part: impl Animation<C> + 'static) -> Self {
+        self.parts.push(Box::new(part));
+        self
+    }
+
+    pub fn on_complete(mut self, complete: impl FnMut(&mut C) + 'static) -> Self {
+        self.on_complete = Some(Box::new(complete));
+        self
+    }
+}
+
+impl<C> Animation<C> for Parallel<C> {
+    fn step(&mut self, target: &mut C) -> bool {
+        if self.finished {
+            return true;
+        }
+        let mut all_done = true;
+        for part in &mut self.parts {
+            all_done &= part.step(target);
+        }
+        if all_done {
+            self.finished = true;
+            if let Some(complete) = &mut self.on_complete {
+                complete(target);
+            }
+        }
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.finished = false;
+        for part in &mut self.parts {
+            part.reset();
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn duration(&self) -> Option<u32> {
+        self.parts
+            .iter()
+            .map(|part| part.duration())
+            .try_fold(0, |longest, duration| Some(longest.max(duration?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Log = Vec<&'static str>;
+
+    const EASES: [Ease; 31] = [
+        Ease::Linear,
+        Ease::InQuad,
+        Ease::OutQuad,
+        Ease::InOutQuad,
+        Ease::InCubic,
+        Ease::OutCubic,
+        Ease::InOutCubic,
+        Ease::InQuart,
+        Ease::OutQuart,
+        Ease::InOutQuart,
+        Ease::InQuint,
+        Ease::OutQuint,
+        Ease::InOutQuint,
+        Ease::InSine,
+        Ease::OutSine,
+        Ease::InOutSine,
+        Ease::InExpo,
+        Ease::OutExpo,
+        Ease::InOutExpo,
+        Ease::InCirc,
+        Ease::OutCirc,
+        Ease::InOutCirc,
+        Ease::InBack,
+        Ease::OutBack,
+        Ease::InOutBack,
+        Ease::InElastic,
+        Ease::OutElastic,
+        Ease::InOutElastic,
+        Ease::InBounce,
+        Ease::OutBounce,
+        Ease::InOutBounce,
+    ];
+
+    fn values<C>(tween: &mut Tween<f32, C>, target: &mut C, ticks: usize) -> Vec<f32> {
+        (0..ticks)
+            .map(|_| {
+                tween.step(target);
+                tween.value()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn eases_start_at_zero_and_end_at_one() {
+        for ease in EASES {
+            assert!(ease.apply(0.0).abs() < 1e-5, "{:?} at 0", ease);
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-5, "{:?} at 1", ease);
+        }
+    }
+
+    #[test]
+    fn yoyo_repeats_alternate_direction() {
+        let mut tween = Tween::new(0.0, 10.0, 2, Ease::Linear)
+            .yoyo()
+            .repeat(Repeat::Times(2));
+        assert_eq!(Animation::<()>::duration(&tween), Some(6));
+        assert_eq!(
+            values(&mut tween, &mut (), 7),
+            [5.0, 10.0, 5.0, 0.0, 5.0, 10.0, 10.0]
+        );
+        assert!(Animation::<()>::is_finished(&tween));
+    }
+
+    #[test]
+    fn delay_only_comes_before_the_first_run() {
+        let mut tween = Tween::new(0.0, 4.0, 2, Ease::Linear)
+            .delay(2)
+            .repeat(Repeat::Times(1));
+        assert_eq!(Animation::<()>::duration(&tween), Some(6));
+        assert_eq!(
+            values(&mut tween, &mut (), 6),
+            [0.0, 0.0, 2.0, 4.0, 2.0, 4.0]
+        );
+        assert!(Animation::<()>::is_finished(&tween));
+    }
+
+    #[test]
+    fn forever_never_finishes() {
+        let mut tween = Tween::new(0.0, 1.0, 3, Ease::Linear).repeat(Repeat::Forever);
+        for _ in 0..100 {
+            assert!(!tween.step(&mut ()));
+        }
+        assert_eq!(Animation::<()>::duration(&tween), None);
+    }
+
+    #[test]
+    fn instant_calls_run_on_the_tick_the_part_before_ends() {
+        let mut sequence = Sequence::new()
+            .then(Call::new(|log: &mut Log| log.push("a")))
+            .then(Wait::new(2))
+            .then(Call::new(|log: &mut Log| log.push("b")))
+            .then(Call::new(|log: &mut Log| log.push("c")))
+            .then(Wait::new(1))
+            .then(Call::new(|log: &mut Log| log.push("d")));
+        assert_eq!(sequence.duration(), Some(3));
+
+        let mut log = Log::new();
+        assert!(!sequence.step(&mut log));
+        assert_eq!(log, ["a"]);
+        assert!(!sequence.step(&mut log));
+        assert_eq!(log, ["a", "b", "c"]);
+        assert!(sequence.step(&mut log));
+        assert_eq!(log, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn sequence_of_only_calls_finishes_even_repeating_forever() {
+        let mut sequence = Sequence::new()
+            .then(Call::new(|log: &mut Log| log.push("a")))
+            .then(Call::new(|log: &mut Log| log.push("b")))
+            .repeat(Repeat::Forever);
+        let mut log = Log::new();
+        assert!(sequence.step(&mut log));
+        assert!(sequence.step(&mut log));
+        assert_eq!(log, ["a", "b"]);
+    }
+
+    #[test]
+    fn parallel_finishes_with_its_longest_part() {
+        let mut parallel = Parallel::new()
+            .with(Tween::new(0.0, 1.0, 3, Ease::Linear))
+            .with(Wait::new(5));
+        assert_eq!(Animation::<()>::duration(&parallel), Some(5));
+        let finished: Vec<bool> = (0..6).map(|_| parallel.step(&mut ())).collect();
+        assert_eq!(finished, [false, false, false, false, true, true]);
+    }
+
+    #[test]
+    fn on_complete_fires_once() {
+        let tween = Tween::new(0.0, 1.0, 2, Ease::Linear)
+            .repeat(Repeat::Times(1))
+            .on_complete(|log: &mut Log| log.push("tween"));
+        let parallel = Parallel::new()
+            .with(Wait::new(3))
+            .with(tween)
+            .on_complete(|log: &mut Log| log.push("parallel"));
+        let mut sequence = Sequence::new()
+            .then(parallel)
+            .then(Wait::new(1))
+            .repeat(Repeat::Times(1))
+            .on_complete(|log: &mut Log| log.push("sequence"));
+
+        let mut log = Log::new();
+        for _ in 0..30 {
+            sequence.step(&mut log);
+        }
+        // the sequence resets its parts between runs, so they complete once per run
+        assert_eq!(log, ["tween", "parallel", "tween", "parallel", "sequence"]);
+    }
+}